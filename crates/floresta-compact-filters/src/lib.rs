@@ -18,19 +18,27 @@
 )]
 #![allow(clippy::manual_is_multiple_of)]
 
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufWriter;
+use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
 use std::io::Write;
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::path::PathBuf;
 
+use bitcoin::bip158;
 use bitcoin::bip158::BlockFilter;
 use bitcoin::consensus::encode;
 use bitcoin::consensus::Decodable;
 use bitcoin::consensus::Encodable;
+use bitcoin::BlockHash;
 use bitcoin::FilterHeader;
+use bitcoin::Network;
+use bitcoin::ScriptBuf;
 use floresta_common::impl_error_from;
 
 #[derive(Debug)]
@@ -50,28 +58,54 @@ pub enum FlatFilterStoreError {
     /// A poison error, used for mutexes and rwlocks
     Poison,
 
-    /// Our file got corrupted on disk, we need to rebuild
-    CorruptedFile,
+    /// Our file got corrupted on disk, we need to rebuild.
+    ///
+    /// Carries the height of the first descriptor [`FlatFilterStore::verify_integrity`] found to
+    /// be invalid, when a scan could localize it; `None` for corruption caught structurally
+    /// (e.g. a file whose length isn't a multiple of a descriptor's size) with no single height
+    /// to blame.
+    CorruptedFile(Option<u32>),
+
+    /// The file doesn't start with the [`FILE_HEADER_MAGIC`] bytes, so it's not one of ours
+    BadMagic,
+
+    /// The file was written by a driver/format version we don't know how to read
+    VersionMismatch,
+
+    /// The file was built for a different [`Network`] than the one we're opening it for
+    WrongNetwork,
+
+    /// The header names a compression codec we don't know how to decode
+    UnsupportedCompression,
+
+    /// A [`BlockFilter::match_any`] call failed (a malformed Golomb-coded set)
+    Filter(bip158::Error),
+
+    /// The descriptor file or its companion data file changed in a way the store didn't write
+    /// itself -- a different length, mtime, or checksum than what was recorded the last time we
+    /// flushed. The caller should rebuild the store from blocks rather than trust it.
+    NeedsRebuild,
 }
 
 impl PartialEq for FlatFilterStoreError {
     fn eq(&self, other: &Self) -> bool {
-        matches!(
-            (self, other),
-            (
-                FlatFilterStoreError::NotFound,
-                FlatFilterStoreError::NotFound
-            ) | (
-                FlatFilterStoreError::BitcoinIo(_),
-                FlatFilterStoreError::BitcoinIo(_)
-            ) | (
-                FlatFilterStoreError::StdIo(_),
-                FlatFilterStoreError::StdIo(_)
-            ) | (
-                FlatFilterStoreError::Encode(_),
-                FlatFilterStoreError::Encode(_)
-            )
-        )
+        use FlatFilterStoreError::*;
+
+        match (self, other) {
+            (NotFound, NotFound) => true,
+            (BitcoinIo(_), BitcoinIo(_)) => true,
+            (StdIo(_), StdIo(_)) => true,
+            (Encode(_), Encode(_)) => true,
+            (Poison, Poison) => true,
+            (CorruptedFile(a), CorruptedFile(b)) => a == b,
+            (BadMagic, BadMagic) => true,
+            (VersionMismatch, VersionMismatch) => true,
+            (WrongNetwork, WrongNetwork) => true,
+            (UnsupportedCompression, UnsupportedCompression) => true,
+            (Filter(_), Filter(_)) => true,
+            (NeedsRebuild, NeedsRebuild) => true,
+            _ => false,
+        }
     }
 }
 
@@ -80,6 +114,7 @@ impl Eq for FlatFilterStoreError {}
 impl_error_from!(FlatFilterStoreError, bitcoin::io::Error, BitcoinIo);
 impl_error_from!(FlatFilterStoreError, encode::Error, Encode);
 impl_error_from!(FlatFilterStoreError, std::io::Error, StdIo);
+impl_error_from!(FlatFilterStoreError, bip158::Error, Filter);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// This represents a offset in a filter descriptor.
@@ -185,6 +220,13 @@ pub struct FilterDescriptor {
 
     /// The filter itself, if stored locally.
     offset: HeaderOffset,
+
+    /// The hash of the block this filter was built for.
+    ///
+    /// BIP-158's Golomb-coded set is keyed off a SipHash derived from the block hash (not the
+    /// filter header), so [`FlatFilterStore::scan`] needs this to call
+    /// [`BlockFilter::match_any`].
+    block_hash: BlockHash,
 }
 
 impl FilterDescriptor {
@@ -193,7 +235,8 @@ impl FilterDescriptor {
     /// This is calculated as:
     ///  - 32 bytes for the [`FilterHeader`] hash
     ///  - 8 for the offset, with the MSB reserved for presence flag
-    pub const FILTER_DESCRIPTOR_SIZE: u32 = 32 + 8;
+    ///  - 32 bytes for the [`BlockHash`]
+    pub const FILTER_DESCRIPTOR_SIZE: u32 = 32 + 8 + 32;
 }
 
 impl Encodable for FilterDescriptor {
@@ -204,6 +247,7 @@ impl Encodable for FilterDescriptor {
         let mut len = 0;
         len += self.header.consensus_encode(writer)?;
         len += self.offset.consensus_encode(writer)?;
+        len += self.block_hash.consensus_encode(writer)?;
         Ok(len)
     }
 }
@@ -214,17 +258,26 @@ impl Decodable for FilterDescriptor {
     ) -> Result<Self, bitcoin::consensus::encode::Error> {
         let header = FilterHeader::consensus_decode(reader)?;
         let offset = HeaderOffset::consensus_decode(reader)?;
+        let block_hash = BlockHash::consensus_decode(reader)?;
 
-        Ok(FilterDescriptor { header, offset })
+        Ok(FilterDescriptor {
+            header,
+            offset,
+            block_hash,
+        })
     }
 }
 
 /// A store for filter headers, allowing insertion and retrieval by block height.
 pub trait FilterHeadersStore {
-    /// Inserts a new filter header into the store.
+    /// Inserts a new filter header into the store, for the block identified by `block_hash`.
     ///
     /// If you have a reorg, you should use `update_filter_header` to overwrite existing headers.
-    fn put_filter_header(&mut self, header: FilterHeader) -> Result<(), FlatFilterStoreError>;
+    fn put_filter_header(
+        &mut self,
+        block_hash: BlockHash,
+        header: FilterHeader,
+    ) -> Result<(), FlatFilterStoreError>;
 
     /// Retrieves a filter header by its block height.
     fn get_filter_header(&mut self, height: u32) -> Result<FilterHeader, FlatFilterStoreError>;
@@ -233,6 +286,7 @@ pub trait FilterHeadersStore {
     fn update_filter_header(
         &mut self,
         height: u32,
+        block_hash: BlockHash,
         header: FilterHeader,
     ) -> Result<FilterHeader, FlatFilterStoreError>;
 
@@ -248,6 +302,21 @@ pub trait FilterHeadersStore {
         Ok(None)
     }
 
+    /// Inserts a new filter header, also caching the full `filter` body for fast retrieval
+    /// through [`FilterHeadersStore::get_filter`].
+    ///
+    /// The default implementation just forwards to [`FilterHeadersStore::put_filter_header`]
+    /// and drops `filter`, for stores (like an in-memory one) that don't cache bodies at all.
+    fn put_filter_header_with_filter(
+        &mut self,
+        block_hash: BlockHash,
+        header: FilterHeader,
+        filter: &BlockFilter,
+    ) -> Result<(), FlatFilterStoreError> {
+        let _ = filter;
+        self.put_filter_header(block_hash, header)
+    }
+
     /// Flushes any pending writes to the underlying storage. This is a no-op for in-memory stores,
     /// but may be necessary for file-based implementations to ensure data integrity.
     fn flush(&mut self) -> Result<(), FlatFilterStoreError> {
@@ -255,46 +324,555 @@ pub trait FilterHeadersStore {
     }
 }
 
+/// Magic bytes identifying a [`FlatFilterStore`] file, checked by [`FileHeader::decode`].
+const FILE_HEADER_MAGIC: [u8; 4] = *b"FFHS";
+
+/// Version of the [`FlatFilterStore`] header/body layout. Bump this if the layout ever changes,
+/// so a file from an older (or newer) build is rejected outright instead of being misparsed.
+///
+/// Bumped to 2 when [`FilterDescriptor`] grew a [`BlockHash`] field.
+///
+/// Bumped to 3 when [`FileHeader`] grew the descriptor checksum and the data-file length/mtime
+/// fingerprint, checked by [`FlatFilterStore::check_fingerprint`].
+const FILE_HEADER_VERSION: u8 = 3;
+
+/// The only BIP-158 filter type we currently index: the basic filter.
+const BIP158_BASIC_FILTER_TYPE: u8 = 0;
+
+/// Size, in bytes, of the [`FileHeader`] written at offset 0 of every [`FlatFilterStore`] file:
+/// magic + version + network + filter type + host endianness + compression codec + descriptor
+/// checksum + recorded descriptor length + recorded data-file length + recorded data-file mtime.
+const FILE_HEADER_SIZE: u32 = 4 + 1 + 1 + 1 + 1 + 1 + 4 + 4 + 8 + 8;
+
+/// Default number of full filter bodies a [`FlatFilterStore`] keeps in its companion data file.
+/// Older bodies fall back to being fetched from peers; see [`FlatFilterStoreConfig::with_max_cached_filters`].
+const DEFAULT_MAX_CACHED_FILTERS: u32 = 1_000;
+
+/// Encodes `network` as the single byte stored in a [`FileHeader`].
+fn network_to_byte(network: Network) -> u8 {
+    match network {
+        Network::Bitcoin => 0,
+        Network::Testnet => 1,
+        Network::Signet => 2,
+        Network::Regtest => 3,
+        Network::Testnet4 => 4,
+        _ => 0xff,
+    }
+}
+
+fn network_from_byte(byte: u8) -> Option<Network> {
+    Some(match byte {
+        0 => Network::Bitcoin,
+        1 => Network::Testnet,
+        2 => Network::Signet,
+        3 => Network::Regtest,
+        4 => Network::Testnet4,
+        _ => return None,
+    })
+}
+
+/// Compression codec used for full filter bodies in a [`FlatFilterStore`]'s companion data file.
+///
+/// Picked once, when a store's file is first created, and recorded in its [`FileHeader`] so a
+/// later `FlatFilterStore::new` call always decompresses with the codec the file was actually
+/// written with, regardless of what the caller's [`FlatFilterStoreConfig`] asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterCompression {
+    /// Zstandard, the same codec used to ship the compressed block/chain fixtures in this
+    /// workspace's test utilities.
+    Zstd,
+
+    /// LZMA2, via the `.xz` container. Slower than zstd but compresses smaller.
+    Xz,
+}
+
+impl FilterCompression {
+    fn to_byte(self) -> u8 {
+        match self {
+            FilterCompression::Zstd => 0,
+            FilterCompression::Xz => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FilterCompression::Zstd),
+            1 => Some(FilterCompression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Compresses `data` as a single, independent block: decoding it back only ever requires
+    /// this block's own bytes, never any neighboring one, so random-access [`FlatFilterStore::get_filter`]
+    /// can decompress exactly the block it needs.
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, FlatFilterStoreError> {
+        match self {
+            FilterCompression::Zstd => {
+                zstd::encode_all(data, 0).map_err(FlatFilterStoreError::StdIo)
+            }
+            FilterCompression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data)?;
+                encoder.finish().map_err(FlatFilterStoreError::StdIo)
+            }
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> Result<Vec<u8>, FlatFilterStoreError> {
+        match self {
+            FilterCompression::Zstd => zstd::decode_all(data).map_err(FlatFilterStoreError::StdIo),
+            FilterCompression::Xz => {
+                let mut decoder = xz2::read::XzDecoder::new(data);
+                let mut out = Vec::new();
+                decoder.read_to_end(&mut out)?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// The fixed-size header block written at offset 0 of every [`FlatFilterStore`] file.
+///
+/// Decoding the raw bytes ([`FileHeader::decode`]) and checking that the result is something
+/// this host can actually read ([`FileHeader::verify`]) are kept as separate steps, the same way
+/// a handshake message is first decoded and only then checked against the server/driver/file
+/// versions we support: a header can be well-formed and still describe a file we must refuse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileHeader {
+    version: u8,
+    network: Network,
+    filter_type: u8,
+    compression: FilterCompression,
+    /// `true` if the file was written on a big-endian host.
+    ///
+    /// [`HeaderOffset`]/[`FilterDescriptor`] round-trip through `bitcoin`'s `consensus_encode`,
+    /// which is little-endian regardless of host, so this only ever documents the host that
+    /// wrote the file; nothing here makes us reject a file on that basis alone.
+    big_endian_host: bool,
+
+    /// CRC32 over the descriptor region as of the last [`FlatFilterStore::write_header`] call.
+    descriptor_checksum: u32,
+
+    /// Length, in bytes, of the descriptor region as of the last write.
+    recorded_len: u32,
+
+    /// Length, in bytes, of the companion data file as of the last write.
+    recorded_data_len: u64,
+
+    /// Mtime (seconds since epoch) of the companion data file as of the last write.
+    ///
+    /// The main file's own mtime isn't tracked here: rewriting this very header always bumps it,
+    /// which would make the fingerprint invalidate itself on every flush. The data file, which
+    /// only [`FlatFilterStore::append_filter_body`] ever touches, doesn't have that problem.
+    recorded_data_mtime: u64,
+}
+
+impl FileHeader {
+    fn new(network: Network, compression: FilterCompression) -> Self {
+        Self {
+            version: FILE_HEADER_VERSION,
+            network,
+            filter_type: BIP158_BASIC_FILTER_TYPE,
+            compression,
+            big_endian_host: cfg!(target_endian = "big"),
+            descriptor_checksum: 0,
+            recorded_len: 0,
+            recorded_data_len: 0,
+            recorded_data_mtime: 0,
+        }
+    }
+
+    fn encode(&self) -> [u8; FILE_HEADER_SIZE as usize] {
+        let mut buf = [0u8; FILE_HEADER_SIZE as usize];
+        buf[0..4].copy_from_slice(&FILE_HEADER_MAGIC);
+        buf[4] = self.version;
+        buf[5] = network_to_byte(self.network);
+        buf[6] = self.filter_type;
+        buf[7] = self.big_endian_host as u8;
+        buf[8] = self.compression.to_byte();
+        buf[9..13].copy_from_slice(&self.descriptor_checksum.to_le_bytes());
+        buf[13..17].copy_from_slice(&self.recorded_len.to_le_bytes());
+        buf[17..25].copy_from_slice(&self.recorded_data_len.to_le_bytes());
+        buf[25..33].copy_from_slice(&self.recorded_data_mtime.to_le_bytes());
+        buf
+    }
+
+    /// Decodes the fixed-size header block. This only checks that the bytes are shaped like a
+    /// header (right magic, a recognized network byte); it does not check that we can actually
+    /// read the rest of the file. Use [`FileHeader::verify`] for that.
+    fn decode(bytes: &[u8]) -> Result<Self, FlatFilterStoreError> {
+        if bytes.len() < FILE_HEADER_SIZE as usize || bytes[0..4] != FILE_HEADER_MAGIC {
+            return Err(FlatFilterStoreError::BadMagic);
+        }
+
+        let network = network_from_byte(bytes[5]).ok_or(FlatFilterStoreError::WrongNetwork)?;
+        let compression = FilterCompression::from_byte(bytes[8])
+            .ok_or(FlatFilterStoreError::UnsupportedCompression)?;
+
+        Ok(Self {
+            version: bytes[4],
+            network,
+            filter_type: bytes[6],
+            compression,
+            big_endian_host: bytes[7] != 0,
+            descriptor_checksum: u32::from_le_bytes(bytes[9..13].try_into().unwrap()),
+            recorded_len: u32::from_le_bytes(bytes[13..17].try_into().unwrap()),
+            recorded_data_len: u64::from_le_bytes(bytes[17..25].try_into().unwrap()),
+            recorded_data_mtime: u64::from_le_bytes(bytes[25..33].try_into().unwrap()),
+        })
+    }
+
+    /// Checks that this header describes a file we can safely read as `expected_network`:
+    /// matching format version and filter type, and the network we were asked to open.
+    fn verify(&self, expected_network: Network) -> Result<(), FlatFilterStoreError> {
+        if self.version != FILE_HEADER_VERSION || self.filter_type != BIP158_BASIC_FILTER_TYPE {
+            return Err(FlatFilterStoreError::VersionMismatch);
+        }
+
+        if self.network != expected_network {
+            return Err(FlatFilterStoreError::WrongNetwork);
+        }
+
+        Ok(())
+    }
+}
+
+/// Configuration for opening or creating a [`FlatFilterStore`].
+///
+/// Follows the same `::new(path, ..)` plus `.with_*` builder shape used for flat stores
+/// elsewhere in the workspace (e.g. `FlatChainStoreConfig`).
+#[derive(Debug, Clone)]
+pub struct FlatFilterStoreConfig {
+    file: PathBuf,
+    network: Network,
+    compression: FilterCompression,
+    max_cached_filters: u32,
+}
+
+impl FlatFilterStoreConfig {
+    /// Creates a config for a store at `file` on `network`, defaulting to zstd compression and
+    /// [`DEFAULT_MAX_CACHED_FILTERS`] cached filter bodies.
+    ///
+    /// `compression` is only consulted the first time `file` is created; reopening an existing
+    /// file always uses the codec recorded in its [`FileHeader`]. `max_cached_filters` is applied
+    /// on every open.
+    pub fn new(file: impl Into<PathBuf>, network: Network) -> Self {
+        Self {
+            file: file.into(),
+            network,
+            compression: FilterCompression::Zstd,
+            max_cached_filters: DEFAULT_MAX_CACHED_FILTERS,
+        }
+    }
+
+    /// Sets the compression codec used when `file` doesn't exist yet.
+    pub fn with_compression(mut self, compression: FilterCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how many of the most recent full filter bodies to keep cached on disk.
+    pub fn with_max_cached_filters(mut self, max_cached_filters: u32) -> Self {
+        self.max_cached_filters = max_cached_filters;
+        self
+    }
+}
+
+/// Derives the path of a [`FlatFilterStore`]'s companion data file (full filter bodies) from its
+/// header/descriptor file path.
+fn data_file_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(".dat");
+    PathBuf::from(name)
+}
+
 #[derive(Debug)]
 /// A flat file implementation of the [`FilterHeadersStore`] trait.
 ///
 /// This will store filter headers in a binary file, appending new headers to the end of the file.
 /// You can retrieve headers by their block height, which corresponds to their position in the
 /// file. Each header is stored in a fixed-size format, allowing for efficient random access.
+///
+/// The file starts with a [`FileHeader`] (see [`FILE_HEADER_SIZE`]); every height-based offset
+/// is relative to the byte right after it.
+///
+/// Full filter bodies, when cached, live in a companion `<file>.dat`: see
+/// [`FlatFilterStore::put_filter_header_with_filter`] and [`FlatFilterStore::get_filter`].
 pub struct FlatFilterStore {
     /// The file where filter headers are stored.
     reader: BufReader<File>,
 
     writer: BufWriter<File>,
 
-    /// The current length of the file, used to determine the next write position.
+    /// The length of the descriptor section of the file (i.e. excluding the [`FileHeader`]),
+    /// used to determine the next write position.
     len: u32,
+
+    /// The companion data file holding compressed full filter bodies.
+    data_reader: BufReader<File>,
+
+    data_writer: BufWriter<File>,
+
+    /// Current length of the data file, used to determine the next write position.
+    data_len: u64,
+
+    /// Codec used to compress/decompress filter bodies in the data file. Fixed for the lifetime
+    /// of the file: read from the [`FileHeader`] on open, regardless of what the config asks for.
+    compression: FilterCompression,
+
+    /// How many of the most recent full filter bodies to keep; see
+    /// [`FlatFilterStoreConfig::with_max_cached_filters`].
+    max_cached_filters: u32,
+
+    /// Heights that currently have a cached filter body, oldest first. Used to decide which
+    /// body to evict once `max_cached_filters` is exceeded.
+    cached_heights: VecDeque<u32>,
+
+    /// The network this store was opened for, kept around to rewrite the [`FileHeader`] on
+    /// every [`FlatFilterStore::flush`].
+    network: Network,
 }
 
 impl FlatFilterStore {
-    /// Creates a new [`FlatFilterStore`]
+    /// Creates a new [`FlatFilterStore`] from `config`.
     ///
-    /// It assumes that the directory for the file already exists, the file may not
-    /// exist or contain valid filter headers.
-    pub fn new(file: &Path) -> Result<Self, FlatFilterStoreError> {
-        let file = File::options()
+    /// It assumes that the directory for the file already exists. If the file doesn't exist, it
+    /// is created and a fresh [`FileHeader`] is written to it. If it does exist, its header is
+    /// decoded and verified against `config.network`, so opening a mainnet store against a
+    /// testnet file (or a file from an incompatible driver version) fails instead of silently
+    /// misreading it.
+    pub fn new(config: FlatFilterStoreConfig) -> Result<Self, FlatFilterStoreError> {
+        let FlatFilterStoreConfig {
+            file: file_path,
+            network,
+            compression,
+            max_cached_filters,
+        } = config;
+
+        let mut file = File::options()
             .read(true)
             .write(true)
             .create(true)
             .truncate(false)
-            .open(file)?;
+            .open(&file_path)?;
+
+        let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let existed = file_len != 0;
+
+        let (len, compression) = if !existed {
+            file.write_all(&FileHeader::new(network, compression).encode())?;
+            (0, compression)
+        } else {
+            if file_len < FILE_HEADER_SIZE as u64 {
+                // Too short for a whole header: bytes that don't even start like our magic are
+                // not one of our files, while a cut-short copy of ours is corruption.
+                let mut prefix = vec![0u8; file_len as usize];
+                file.seek(SeekFrom::Start(0))?;
+                file.read_exact(&mut prefix)?;
+                let checked = prefix.len().min(FILE_HEADER_MAGIC.len());
+                if prefix[..checked] != FILE_HEADER_MAGIC[..checked] {
+                    return Err(FlatFilterStoreError::BadMagic);
+                }
+                return Err(FlatFilterStoreError::CorruptedFile(None));
+            }
+
+            let mut header_bytes = [0u8; FILE_HEADER_SIZE as usize];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header_bytes)?;
+
+            let header = FileHeader::decode(&header_bytes)?;
+            header.verify(network)?;
 
-        let len = file.metadata().map(|m| m.len()).unwrap_or(0) as u32;
-        let file_copy = file.try_clone()?;
+            (
+                (file_len - FILE_HEADER_SIZE as u64) as u32,
+                header.compression,
+            )
+        };
 
+        let file_copy = file.try_clone()?;
         let writer = BufWriter::new(file);
         let reader = BufReader::new(file_copy);
 
-        Ok(Self {
+        let data_file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(data_file_path(&file_path))?;
+        let data_len = data_file.metadata().map(|m| m.len()).unwrap_or(0);
+        let data_file_copy = data_file.try_clone()?;
+        let data_writer = BufWriter::new(data_file);
+        let data_reader = BufReader::new(data_file_copy);
+
+        let mut store = Self {
             reader,
             writer,
             len,
-        })
+            data_reader,
+            data_writer,
+            data_len,
+            compression,
+            max_cached_filters,
+            cached_heights: VecDeque::new(),
+            network,
+        };
+        store.rebuild_cached_heights()?;
+
+        if existed {
+            // Someone could have touched either file since we last wrote to it; check before we
+            // trust anything we just loaded.
+            store.check_fingerprint()?;
+        } else {
+            // Stamp the real (non-placeholder) fingerprint for this brand new, empty store.
+            store.write_header()?;
+        }
+
+        Ok(store)
+    }
+
+    /// Compares the [`FileHeader`]'s recorded length/checksum and the companion data file's
+    /// recorded length/mtime against what's actually on disk right now.
+    ///
+    /// A mismatch means the file changed in a way this store didn't do itself -- a different
+    /// process, a restored backup, a bit that rotted in place -- so we refuse to trust it and
+    /// ask the caller to rebuild instead of silently serving stale or corrupted data.
+    fn check_fingerprint(&mut self) -> Result<(), FlatFilterStoreError> {
+        let header = self.read_header()?;
+
+        if header.recorded_len != self.len || header.recorded_data_len != self.data_len {
+            return Err(FlatFilterStoreError::NeedsRebuild);
+        }
+
+        let actual_data_mtime = Self::file_mtime_secs(self.data_reader.get_ref())?;
+        if header.recorded_data_mtime != actual_data_mtime {
+            return Err(FlatFilterStoreError::NeedsRebuild);
+        }
+
+        let actual_checksum = self.compute_descriptor_checksum()?;
+        if actual_checksum != header.descriptor_checksum {
+            return Err(FlatFilterStoreError::NeedsRebuild);
+        }
+
+        Ok(())
+    }
+
+    /// Re-reads the [`FileHeader`] from offset 0.
+    fn read_header(&mut self) -> Result<FileHeader, FlatFilterStoreError> {
+        let mut bytes = [0u8; FILE_HEADER_SIZE as usize];
+        self.reader.seek(SeekFrom::Start(0))?;
+        self.reader.read_exact(&mut bytes)?;
+        FileHeader::decode(&bytes)
+    }
+
+    /// Rewrites the [`FileHeader`] at offset 0 with a fresh checksum and length/mtime fingerprint
+    /// for the descriptor region and the companion data file as they stand right now.
+    ///
+    /// Called once right after creating a brand new store, and again from
+    /// [`FlatFilterStore::flush`] after every batch of writes, so a later
+    /// [`FlatFilterStore::new`] call can tell whether either file was touched by anything else
+    /// since.
+    fn write_header(&mut self) -> Result<(), FlatFilterStoreError> {
+        let descriptor_checksum = self.compute_descriptor_checksum()?;
+        let recorded_data_mtime = Self::file_mtime_secs(self.data_reader.get_ref())?;
+
+        let header = FileHeader {
+            version: FILE_HEADER_VERSION,
+            network: self.network,
+            filter_type: BIP158_BASIC_FILTER_TYPE,
+            compression: self.compression,
+            big_endian_host: cfg!(target_endian = "big"),
+            descriptor_checksum,
+            recorded_len: self.len,
+            recorded_data_len: self.data_len,
+            recorded_data_mtime,
+        };
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&header.encode())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// CRC32 over the whole descriptor region (everything after the [`FileHeader`]), used as the
+    /// checksum stamped into the header by [`FlatFilterStore::write_header`] and re-checked by
+    /// [`FlatFilterStore::check_fingerprint`] and [`FlatFilterStore::verify_integrity`].
+    fn compute_descriptor_checksum(&mut self) -> Result<u32, FlatFilterStoreError> {
+        self.reader.seek(SeekFrom::Start(FILE_HEADER_SIZE as u64))?;
+        let mut buf = vec![0u8; self.len as usize];
+        self.reader.read_exact(&mut buf)?;
+        Ok(crc32fast::hash(&buf))
+    }
+
+    /// Seconds-since-epoch mtime of `file`, used for the companion data file's fingerprint.
+    fn file_mtime_secs(file: &File) -> Result<u64, FlatFilterStoreError> {
+        let modified = file.metadata()?.modified()?;
+        Ok(modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs())
+    }
+
+    /// Re-validates every stored descriptor, returning the height of the first one whose
+    /// [`HeaderOffset`] points past the end of the companion data file -- damage a checksum
+    /// mismatch alone can't localize. Also re-checks the overall descriptor checksum recorded in
+    /// the [`FileHeader`]; if that's the only thing that disagrees, the first height is reported,
+    /// since an aggregate checksum can't point at a more specific one.
+    pub fn verify_integrity(&mut self) -> Result<(), FlatFilterStoreError> {
+        if self.len % FilterDescriptor::FILTER_DESCRIPTOR_SIZE != 0 {
+            return Err(FlatFilterStoreError::CorruptedFile(None));
+        }
+
+        let count = self.len / FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
+        for height in 0..count {
+            let descriptor = self.read_descriptor_by_height(height)?;
+            if let Some(offset) = descriptor.offset.offset() {
+                if offset >= self.data_len {
+                    return Err(FlatFilterStoreError::CorruptedFile(Some(height)));
+                }
+            }
+        }
+
+        let recorded_checksum = self.read_header()?.descriptor_checksum;
+        let actual_checksum = self.compute_descriptor_checksum()?;
+        if actual_checksum != recorded_checksum {
+            return Err(FlatFilterStoreError::CorruptedFile(Some(0)));
+        }
+
+        Ok(())
+    }
+
+    /// Scans the already-open descriptor file for present offsets, so a reopened store knows
+    /// which heights it can evict once `max_cached_filters` is exceeded again.
+    fn rebuild_cached_heights(&mut self) -> Result<(), FlatFilterStoreError> {
+        let count = self.len / FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
+        for height in 0..count {
+            if self.read_descriptor_by_height(height)?.offset.is_present() {
+                self.cached_heights.push_back(height);
+            }
+        }
+        Ok(())
+    }
+
+    /// Evicts the oldest cached filter body until at most `max_cached_filters` remain, clearing
+    /// the evicted descriptors' offsets. The data file itself is append-only and is not
+    /// compacted; an evicted body's bytes stay on disk until a future compaction pass.
+    fn evict_oldest_if_needed(&mut self) -> Result<(), FlatFilterStoreError> {
+        while self.cached_heights.len() as u32 > self.max_cached_filters {
+            let Some(height) = self.cached_heights.pop_front() else {
+                break;
+            };
+
+            let descriptor = self.read_descriptor_by_height(height)?;
+            self.update_descriptor(
+                height,
+                FilterDescriptor {
+                    header: descriptor.header,
+                    offset: HeaderOffset::new(false, 0),
+                    block_hash: descriptor.block_hash,
+                },
+            )?;
+        }
+        Ok(())
     }
 
     fn update_descriptor(
@@ -310,12 +888,14 @@ impl FlatFilterStore {
         let old_descriptor = self.read_descriptor_at(offset)?;
         let writer = &mut self.writer;
 
-        writer.seek(SeekFrom::Start(offset as u64))?;
+        writer.seek(SeekFrom::Start((FILE_HEADER_SIZE + offset) as u64))?;
         header.consensus_encode(&mut *writer)?;
+        writer.flush()?;
         Ok(old_descriptor)
     }
 
-    /// Reads a filter header from the file at the specified offset.
+    /// Reads a filter header from the file at the specified offset, relative to the end of the
+    /// [`FileHeader`].
     fn read_descriptor_at(
         &mut self,
         offset: u32,
@@ -325,7 +905,7 @@ impl FlatFilterStore {
         }
 
         let reader = &mut self.reader;
-        reader.seek(SeekFrom::Start(offset as u64))?;
+        reader.seek(SeekFrom::Start((FILE_HEADER_SIZE + offset) as u64))?;
         let header = FilterDescriptor::consensus_decode(reader)?;
         Ok(header)
     }
@@ -336,7 +916,7 @@ impl FlatFilterStore {
         height: u32,
     ) -> Result<FilterDescriptor, FlatFilterStoreError> {
         if self.len % FilterDescriptor::FILTER_DESCRIPTOR_SIZE != 0 && self.len != 0 {
-            return Err(FlatFilterStoreError::CorruptedFile);
+            return Err(FlatFilterStoreError::CorruptedFile(None));
         }
 
         let offset = height * FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
@@ -352,18 +932,119 @@ impl FlatFilterStore {
     fn put_descriptor(&mut self, header: FilterDescriptor) -> Result<(), FlatFilterStoreError> {
         let writer = &mut self.writer;
         writer.seek(std::io::SeekFrom::End(0))?;
-        header.consensus_encode(writer)?;
+        header.consensus_encode(&mut *writer)?;
+        writer.flush()?;
         self.len += FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
 
         Ok(())
     }
+
+    /// Compresses `filter`'s content as its own independent block and appends it to the data
+    /// file, returning the byte offset the block starts at.
+    ///
+    /// The block is `[compressed_len: u32 LE][uncompressed_len: u32 LE][compressed bytes]`, so
+    /// [`FlatFilterStore::read_filter_body`] only ever needs to read and decompress this one
+    /// block, never the rest of the file.
+    fn append_filter_body(&mut self, filter: &BlockFilter) -> Result<u64, FlatFilterStoreError> {
+        let compressed = self.compression.compress(&filter.content)?;
+        let uncompressed_len = filter.content.len() as u32;
+        let compressed_len = compressed.len() as u32;
+
+        let offset = self.data_len;
+        let writer = &mut self.data_writer;
+        writer.seek(SeekFrom::Start(offset))?;
+        writer.write_all(&compressed_len.to_le_bytes())?;
+        writer.write_all(&uncompressed_len.to_le_bytes())?;
+        writer.write_all(&compressed)?;
+        writer.flush()?;
+
+        self.data_len += 8 + compressed_len as u64;
+        Ok(offset)
+    }
+
+    /// Reads and decompresses the filter body written by [`FlatFilterStore::append_filter_body`]
+    /// at `offset`.
+    fn read_filter_body(&mut self, offset: u64) -> Result<BlockFilter, FlatFilterStoreError> {
+        let reader = &mut self.data_reader;
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut len_prefix = [0u8; 8];
+        reader.read_exact(&mut len_prefix)?;
+        let compressed_len = u32::from_le_bytes(len_prefix[0..4].try_into().unwrap());
+
+        let mut compressed = vec![0u8; compressed_len as usize];
+        reader.read_exact(&mut compressed)?;
+
+        let content = self.compression.decompress(&compressed)?;
+        Ok(BlockFilter::new(&content))
+    }
+
+    /// Truncates the store so that `get_height()` returns `Some(height)` afterward, discarding
+    /// every descriptor at a greater height. A no-op if the store doesn't currently have
+    /// anything past `height`.
+    ///
+    /// Cached filter bodies for the discarded heights are dropped from `cached_heights`; their
+    /// bytes remain in the `.dat` file, like any other evicted body (see
+    /// `evict_oldest_if_needed`).
+    pub fn rollback_to(&mut self, height: u32) -> Result<(), FlatFilterStoreError> {
+        self.truncate_descriptors(height.saturating_add(1))
+    }
+
+    /// Shrinks the descriptor section of the file down to `new_count` descriptors, dropping the
+    /// rest. Used by [`FlatFilterStore::rollback_to`] and [`FlatFilterStore::put_filter_header_at`].
+    fn truncate_descriptors(&mut self, new_count: u32) -> Result<(), FlatFilterStoreError> {
+        let new_len = new_count * FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
+        if new_len >= self.len {
+            return Ok(());
+        }
+
+        self.writer.flush()?;
+        self.writer
+            .get_ref()
+            .set_len((FILE_HEADER_SIZE + new_len) as u64)?;
+        self.len = new_len;
+        self.cached_heights.retain(|&h| h < new_count);
+
+        Ok(())
+    }
+
+    /// Inserts `header` (for `block_hash`) at `height`, handling a chain reorg.
+    ///
+    /// If `height` is the next contiguous height, this just appends, the same as
+    /// [`FilterHeadersStore::put_filter_header`]. Otherwise `height` is already occupied -- a
+    /// competing branch reorged out the one we were extending -- so this first discards every
+    /// header at or after `height` (see [`FlatFilterStore::rollback_to`]), then appends `header`
+    /// as the new tip at that height.
+    pub fn put_filter_header_at(
+        &mut self,
+        height: u32,
+        block_hash: BlockHash,
+        header: FilterHeader,
+    ) -> Result<(), FlatFilterStoreError> {
+        let next_height = self.len / FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
+        if height < next_height {
+            self.truncate_descriptors(height)?;
+        }
+
+        let descriptor = FilterDescriptor {
+            header,
+            offset: HeaderOffset::new(false, 0),
+            block_hash,
+        };
+        self.put_descriptor(descriptor)
+    }
 }
 
 impl FilterHeadersStore for FlatFilterStore {
-    fn put_filter_header(&mut self, header: FilterHeader) -> Result<(), FlatFilterStoreError> {
+    fn put_filter_header(
+        &mut self,
+        block_hash: BlockHash,
+        header: FilterHeader,
+    ) -> Result<(), FlatFilterStoreError> {
         let descriptor = FilterDescriptor {
             header,
             offset: HeaderOffset::new(false, 0),
+            block_hash,
         };
         self.put_descriptor(descriptor)
     }
@@ -375,14 +1056,19 @@ impl FilterHeadersStore for FlatFilterStore {
     fn update_filter_header(
         &mut self,
         height: u32,
+        block_hash: BlockHash,
         header: FilterHeader,
     ) -> Result<FilterHeader, FlatFilterStoreError> {
         let descriptor = FilterDescriptor {
             header,
             offset: HeaderOffset::new(false, 0),
+            block_hash,
         };
 
-        self.update_descriptor(height, descriptor).map(|d| d.header)
+        let old = self.update_descriptor(height, descriptor)?;
+        // A reorg invalidates any body cached for the old header at this height.
+        self.cached_heights.retain(|&h| h != height);
+        Ok(old.header)
     }
 
     fn get_height(&self) -> Result<Option<u32>, FlatFilterStoreError> {
@@ -396,13 +1082,87 @@ impl FilterHeadersStore for FlatFilterStore {
         Ok(Some(count - 1))
     }
 
-    fn get_filter(&mut self, _height: u32) -> Result<Option<BlockFilter>, FlatFilterStoreError> {
-        Ok(None)
+    fn get_filter(&mut self, height: u32) -> Result<Option<BlockFilter>, FlatFilterStoreError> {
+        let descriptor = self.read_descriptor_by_height(height)?;
+        let Some(offset) = descriptor.offset.offset() else {
+            return Ok(None);
+        };
+
+        self.read_filter_body(offset).map(Some)
+    }
+
+    fn put_filter_header_with_filter(
+        &mut self,
+        block_hash: BlockHash,
+        header: FilterHeader,
+        filter: &BlockFilter,
+    ) -> Result<(), FlatFilterStoreError> {
+        let height = self.len / FilterDescriptor::FILTER_DESCRIPTOR_SIZE;
+        let offset = self.append_filter_body(filter)?;
+
+        let descriptor = FilterDescriptor {
+            header,
+            offset: HeaderOffset::new(true, offset),
+            block_hash,
+        };
+        self.put_descriptor(descriptor)?;
+
+        self.cached_heights.push_back(height);
+        self.evict_oldest_if_needed()
     }
 
     fn flush(&mut self) -> Result<(), FlatFilterStoreError> {
         self.writer.flush()?;
-        Ok(())
+        self.data_writer.flush()?;
+        self.write_header()
+    }
+}
+
+impl FlatFilterStore {
+    /// Scans filters in `range` (inclusive of both ends) for any that match one of `scripts`,
+    /// returning the heights of the matches.
+    ///
+    /// Only heights whose filter body is cached locally (see
+    /// [`FilterHeadersStore::put_filter_header_with_filter`]) can be scanned; a height whose body
+    /// was evicted or was never cached returns [`FlatFilterStoreError::NotFound`].
+    pub fn scan(
+        &mut self,
+        scripts: impl IntoIterator<Item = ScriptBuf>,
+        range: RangeInclusive<u32>,
+    ) -> Result<Vec<u32>, FlatFilterStoreError> {
+        let scripts: Vec<ScriptBuf> = scripts.into_iter().collect();
+        let mut matches = Vec::new();
+
+        for height in range {
+            let descriptor = self.read_descriptor_by_height(height)?;
+            let Some(offset) = descriptor.offset.offset() else {
+                return Err(FlatFilterStoreError::NotFound);
+            };
+
+            let filter = self.read_filter_body(offset)?;
+            let is_match =
+                filter.match_any(&descriptor.block_hash, scripts.iter().map(|s| s.as_bytes()))?;
+
+            if is_match {
+                matches.push(height);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Like [`FlatFilterStore::scan`], but returns the block hashes of the matching heights
+    /// instead of the heights themselves, the shape the `scanblocks` RPC method hands back to
+    /// callers so they can fetch just the candidate blocks.
+    pub fn scan_blocks(
+        &mut self,
+        scripts: impl IntoIterator<Item = ScriptBuf>,
+        range: RangeInclusive<u32>,
+    ) -> Result<Vec<BlockHash>, FlatFilterStoreError> {
+        self.scan(scripts, range)?
+            .into_iter()
+            .map(|height| Ok(self.read_descriptor_by_height(height)?.block_hash))
+            .collect()
     }
 }
 
@@ -425,6 +1185,14 @@ mod tests {
         FilterHeader::from_raw_hash(Hash::from_byte_array(hash))
     }
 
+    fn create_test_block_hash(n: u64) -> BlockHash {
+        let mut hash = [0u8; 32];
+        let bytes = n.to_le_bytes();
+        hash[0..8].copy_from_slice(&bytes);
+
+        BlockHash::from_raw_hash(Hash::from_byte_array(hash))
+    }
+
     fn tempdir() -> PathBuf {
         // create ./tmp-db if it doesn't exist
         let tmp_dir = PathBuf::from("./tmp-db");
@@ -438,13 +1206,18 @@ mod tests {
     #[test]
     fn test_put_and_get_filter_header() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let header1 = create_test_header(1);
         let header2 = create_test_header(2);
 
-        store.put_filter_header(header1).unwrap();
-        store.put_filter_header(header2).unwrap();
+        store
+            .put_filter_header(create_test_block_hash(1), header1)
+            .unwrap();
+        store
+            .put_filter_header(create_test_block_hash(2), header2)
+            .unwrap();
 
         let retrieved1 = store.read_descriptor_by_height(0).unwrap();
         let retrieved2 = store.read_descriptor_by_height(1).unwrap();
@@ -458,16 +1231,23 @@ mod tests {
     #[test]
     fn test_update_filter_header() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let header1 = create_test_header(0);
         let header2 = create_test_header(1);
         let updated_header = create_test_header(2);
 
-        store.put_filter_header(header1).unwrap();
-        store.put_filter_header(header2).unwrap();
+        store
+            .put_filter_header(create_test_block_hash(0), header1)
+            .unwrap();
+        store
+            .put_filter_header(create_test_block_hash(1), header2)
+            .unwrap();
 
-        store.update_filter_header(0, updated_header).unwrap();
+        store
+            .update_filter_header(0, create_test_block_hash(2), updated_header)
+            .unwrap();
 
         let retrieved = store.read_descriptor_by_height(0).unwrap();
         assert_eq!(retrieved.header, updated_header);
@@ -477,27 +1257,34 @@ mod tests {
     #[test]
     fn test_get_height() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         assert_eq!(store.get_height().unwrap(), None);
 
         let header1 = create_test_header(1); // block 0
         let header2 = create_test_header(2); // block 1
 
-        store.put_filter_header(header1).unwrap();
-        store.put_filter_header(header2).unwrap();
+        store
+            .put_filter_header(create_test_block_hash(1), header1)
+            .unwrap();
+        store
+            .put_filter_header(create_test_block_hash(2), header2)
+            .unwrap();
         assert_eq!(store.get_height().unwrap(), Some(1));
     }
 
     #[test]
     fn test_not_found() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let result = store.get_filter_header(0);
         assert_eq!(result, Err(FlatFilterStoreError::NotFound));
 
-        let result = store.update_filter_header(0, create_test_header(1));
+        let result =
+            store.update_filter_header(0, create_test_block_hash(1), create_test_header(1));
         assert_eq!(result, Err(FlatFilterStoreError::NotFound));
     }
 
@@ -506,16 +1293,25 @@ mod tests {
         let file_path = tempdir();
 
         {
-            let mut store = FlatFilterStore::new(&file_path).unwrap();
+            let mut store =
+                FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+                    .unwrap();
             let header1 = create_test_header(1);
             let header2 = create_test_header(2);
-            store.put_filter_header(header1).unwrap();
-            store.put_filter_header(header2).unwrap();
+            store
+                .put_filter_header(create_test_block_hash(1), header1)
+                .unwrap();
+            store
+                .put_filter_header(create_test_block_hash(2), header2)
+                .unwrap();
             assert_eq!(store.get_height().unwrap(), Some(1));
+            store.flush().unwrap();
         }
 
         {
-            let mut store = FlatFilterStore::new(&file_path).unwrap();
+            let mut store =
+                FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+                    .unwrap();
             assert_eq!(store.get_height().unwrap(), Some(1));
 
             let retrieved1 = store.read_descriptor_by_height(0).unwrap();
@@ -532,9 +1328,13 @@ mod tests {
     fn test_cleanup() {
         let file_path = tempdir();
         {
-            let mut store = FlatFilterStore::new(&file_path).unwrap();
+            let mut store =
+                FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+                    .unwrap();
             let header1 = create_test_header(1);
-            store.put_filter_header(header1).unwrap();
+            store
+                .put_filter_header(create_test_block_hash(1), header1)
+                .unwrap();
         }
         // Ensure the file is deleted after the test
         fs::remove_file(file_path).unwrap();
@@ -543,7 +1343,8 @@ mod tests {
     #[test]
     fn test_empty_store() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
         assert_eq!(store.get_height().unwrap(), None);
         let result = store.get_filter_header(0);
         assert_eq!(result, Err(FlatFilterStoreError::NotFound));
@@ -552,12 +1353,15 @@ mod tests {
     #[test]
     fn test_large_number_of_headers() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let num_headers = 1000;
         for i in 0..num_headers {
             let header = create_test_header(i as u64);
-            store.put_filter_header(header).unwrap();
+            store
+                .put_filter_header(create_test_block_hash(i as u64), header)
+                .unwrap();
         }
 
         assert_eq!(store.get_height().unwrap(), Some(num_headers - 1));
@@ -571,33 +1375,40 @@ mod tests {
     #[test]
     fn test_partial_read() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let header1 = create_test_header(1);
-        store.put_filter_header(header1).unwrap();
+        store
+            .put_filter_header(create_test_block_hash(1), header1)
+            .unwrap();
 
         // Manually truncate the file to simulate a partial write
 
         let file = store.reader.into_inner();
-        file.set_len(FilterDescriptor::FILTER_DESCRIPTOR_SIZE as u64 - 1)
+        file.set_len((FILE_HEADER_SIZE + FilterDescriptor::FILTER_DESCRIPTOR_SIZE - 1) as u64)
             .unwrap();
         store.reader = BufReader::new(file);
         store.len = FilterDescriptor::FILTER_DESCRIPTOR_SIZE - 1;
 
         let res = store.get_filter_header(0).unwrap_err();
-        assert!(matches!(res, FlatFilterStoreError::CorruptedFile));
+        assert!(matches!(res, FlatFilterStoreError::CorruptedFile(None)));
     }
 
     #[test]
     fn test_no_offset() {
         let file_path = tempdir();
-        let mut store = FlatFilterStore::new(&file_path).unwrap();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
 
         let header1 = FilterDescriptor {
             header: create_test_header(1),
             offset: HeaderOffset::new(false, 0),
+            block_hash: create_test_block_hash(1),
         };
-        store.put_filter_header(header1.header).unwrap();
+        store
+            .put_filter_header(header1.block_hash, header1.header)
+            .unwrap();
 
         let retrieved = store.read_descriptor_by_height(0).unwrap();
         assert_eq!(retrieved.header, header1.header);
@@ -609,6 +1420,7 @@ mod tests {
         let desc = FilterDescriptor {
             header: FilterHeader::all_zeros(),
             offset: HeaderOffset::new(false, 0),
+            block_hash: BlockHash::all_zeros(),
         };
 
         let ser_descriptor_size = serialize(&desc).len() as u32;
@@ -617,4 +1429,263 @@ mod tests {
             ser_descriptor_size
         );
     }
+
+    #[test]
+    fn test_header_round_trip() {
+        let file_path = tempdir();
+        {
+            let mut store =
+                FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+                    .unwrap();
+            store
+                .put_filter_header(create_test_block_hash(1), create_test_header(1))
+                .unwrap();
+            store.flush().unwrap();
+        }
+
+        // Reopening with the same network should see the previously written header
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+        assert_eq!(store.get_height().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_rejects_wrong_network() {
+        let file_path = tempdir();
+        FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        let res = FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Regtest))
+            .unwrap_err();
+        assert_eq!(res, FlatFilterStoreError::WrongNetwork);
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        let file_path = tempdir();
+        fs::write(&file_path, b"not a filter store").unwrap();
+
+        let res = FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+            .unwrap_err();
+        assert_eq!(res, FlatFilterStoreError::BadMagic);
+    }
+
+    #[test]
+    fn test_rejects_version_mismatch() {
+        let file_path = tempdir();
+        let mut header = FileHeader::new(Network::Signet, FilterCompression::Zstd).encode();
+        header[4] = FILE_HEADER_VERSION + 1;
+        fs::write(&file_path, header).unwrap();
+
+        let res = FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet))
+            .unwrap_err();
+        assert_eq!(res, FlatFilterStoreError::VersionMismatch);
+    }
+
+    fn create_test_filter(n: u8) -> BlockFilter {
+        BlockFilter::new(&[n; 64])
+    }
+
+    #[test]
+    fn test_put_and_get_filter_body() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        let header = create_test_header(1);
+        let filter = create_test_filter(7);
+        store
+            .put_filter_header_with_filter(create_test_block_hash(1), header, &filter)
+            .unwrap();
+
+        let retrieved = store.get_filter(0).unwrap();
+        assert_eq!(retrieved, Some(filter));
+
+        // No body was stored for a plain `put_filter_header`
+        store
+            .put_filter_header(create_test_block_hash(2), create_test_header(2))
+            .unwrap();
+        assert_eq!(store.get_filter(1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_filter_body_with_xz_compression() {
+        let file_path = tempdir();
+        let mut store = FlatFilterStore::new(
+            FlatFilterStoreConfig::new(&file_path, Network::Signet)
+                .with_compression(FilterCompression::Xz),
+        )
+        .unwrap();
+
+        let filter = create_test_filter(9);
+        store
+            .put_filter_header_with_filter(
+                create_test_block_hash(1),
+                create_test_header(1),
+                &filter,
+            )
+            .unwrap();
+
+        assert_eq!(store.get_filter(0).unwrap(), Some(filter));
+    }
+
+    #[test]
+    fn test_retention_evicts_oldest_filter_body() {
+        let file_path = tempdir();
+        let mut store = FlatFilterStore::new(
+            FlatFilterStoreConfig::new(&file_path, Network::Signet).with_max_cached_filters(2),
+        )
+        .unwrap();
+
+        for i in 0..3 {
+            store
+                .put_filter_header_with_filter(
+                    create_test_block_hash(i),
+                    create_test_header(i),
+                    &create_test_filter(i as u8),
+                )
+                .unwrap();
+        }
+
+        // The oldest body (height 0) was evicted; the header itself is still there
+        assert_eq!(store.get_filter_header(0).unwrap(), create_test_header(0));
+        assert_eq!(store.get_filter(0).unwrap(), None);
+
+        // The two most recent bodies are still cached
+        assert_eq!(store.get_filter(1).unwrap(), Some(create_test_filter(1)));
+        assert_eq!(store.get_filter(2).unwrap(), Some(create_test_filter(2)));
+    }
+
+    /// A filter encoding zero elements (a single `0x00` varint), which never matches anything
+    /// but is still a validly-encoded GCS body.
+    fn create_empty_filter() -> BlockFilter {
+        BlockFilter::new(&[0x00])
+    }
+
+    #[test]
+    fn test_scan_returns_no_matches_for_empty_filters() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        for i in 0..3 {
+            store
+                .put_filter_header_with_filter(
+                    create_test_block_hash(i),
+                    create_test_header(i),
+                    &create_empty_filter(),
+                )
+                .unwrap();
+        }
+
+        let script = ScriptBuf::from_bytes(vec![0x51]);
+        let matches = store.scan([script], 0..=2).unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_scan_not_found_for_uncached_height() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        // Only the header is stored, no filter body was cached for it.
+        store
+            .put_filter_header(create_test_block_hash(1), create_test_header(1))
+            .unwrap();
+
+        let script = ScriptBuf::from_bytes(vec![0x51]);
+        let result = store.scan([script], 0..=0);
+        assert_eq!(result, Err(FlatFilterStoreError::NotFound));
+    }
+
+    #[test]
+    fn test_rollback_to_discards_suffix() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        for i in 0..5 {
+            store
+                .put_filter_header_with_filter(
+                    create_test_block_hash(i),
+                    create_test_header(i),
+                    &create_empty_filter(),
+                )
+                .unwrap();
+        }
+
+        store.rollback_to(2).unwrap();
+        assert_eq!(store.get_height().unwrap(), Some(2));
+        assert_eq!(store.get_filter_header(2).unwrap(), create_test_header(2));
+
+        // The discarded heights are gone, not just their bodies.
+        assert_eq!(
+            store.get_filter_header(3),
+            Err(FlatFilterStoreError::NotFound)
+        );
+
+        // And their cached bodies are no longer tracked for eviction.
+        assert_eq!(
+            store.cached_heights.iter().copied().collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_rollback_to_is_a_noop_past_the_tip() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        store
+            .put_filter_header(create_test_block_hash(1), create_test_header(1))
+            .unwrap();
+
+        store.rollback_to(5).unwrap();
+        assert_eq!(store.get_height().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_put_filter_header_at_extends_the_tip() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        store
+            .put_filter_header_at(0, create_test_block_hash(1), create_test_header(1))
+            .unwrap();
+        store
+            .put_filter_header_at(1, create_test_block_hash(2), create_test_header(2))
+            .unwrap();
+
+        assert_eq!(store.get_height().unwrap(), Some(1));
+        assert_eq!(store.get_filter_header(1).unwrap(), create_test_header(2));
+    }
+
+    #[test]
+    fn test_put_filter_header_at_handles_reorg() {
+        let file_path = tempdir();
+        let mut store =
+            FlatFilterStore::new(FlatFilterStoreConfig::new(&file_path, Network::Signet)).unwrap();
+
+        for i in 0..3 {
+            store
+                .put_filter_header_at(
+                    i,
+                    create_test_block_hash(i as u64),
+                    create_test_header(i as u64),
+                )
+                .unwrap();
+        }
+        assert_eq!(store.get_height().unwrap(), Some(2));
+
+        // A competing branch reorgs out height 1 onward.
+        let fork_header = create_test_header(100);
+        store
+            .put_filter_header_at(1, create_test_block_hash(100), fork_header)
+            .unwrap();
+
+        assert_eq!(store.get_height().unwrap(), Some(1));
+        assert_eq!(store.get_filter_header(1).unwrap(), fork_header);
+    }
 }
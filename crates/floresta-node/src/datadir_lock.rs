@@ -0,0 +1,47 @@
+//! Single-instance guard for a `florestad` data directory.
+//!
+//! Nothing stops two `florestad` processes from opening the same data directory and concurrently
+//! writing the `FlatChainStore`/KV wallet, which can corrupt both. [`DataDirLock::acquire`] takes
+//! an advisory lock on a `<datadir>/.lock` file before any store is opened, and must be held for
+//! the lifetime of the process - dropping the returned [`DataDirLock`] (e.g. at the end of
+//! `main`) releases it. Because this relies on OS advisory-lock semantics tied to the open file
+//! description, a crashed instance's lock is released automatically on process exit; there's no
+//! stale-lock file to clean up by hand.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use fd_lock::RwLock;
+use fd_lock::RwLockWriteGuard;
+
+use crate::error::FlorestadError;
+
+/// Holds the advisory lock on a data directory. The lock is released when this value is dropped.
+pub struct DataDirLock {
+    _guard: RwLockWriteGuard<'static, File>,
+}
+
+impl DataDirLock {
+    /// Acquires an exclusive advisory lock on `<datadir>/.lock`, creating the file if it doesn't
+    /// exist yet. Returns [`FlorestadError::DataDirLocked`] if another process already holds it.
+    pub fn acquire(datadir: &str) -> Result<Self, FlorestadError> {
+        let path = Path::new(datadir).join(".lock");
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&path)
+            .map_err(|err| FlorestadError::CouldNotWriteFile(path.display().to_string(), err))?;
+
+        // Leaked so the `RwLockWriteGuard` below can outlive this function: the lock must be
+        // held for the whole process lifetime, not just while `acquire` is on the stack.
+        let lock: &'static mut RwLock<File> = Box::leak(Box::new(RwLock::new(file)));
+
+        let guard = lock
+            .try_write()
+            .map_err(|_| FlorestadError::DataDirLocked(path))?;
+
+        Ok(DataDirLock { _guard: guard })
+    }
+}
@@ -4,268 +4,221 @@ use bitcoin::consensus::encode;
 use floresta_chain::BlockValidationErrors;
 use floresta_chain::BlockchainError;
 use floresta_chain::FlatChainstoreError;
-use floresta_common::impl_error_from;
 #[cfg(feature = "compact-filters")]
 use floresta_compact_filters::FlatFilterStoreError;
+use floresta_watch_only::descriptor::WalletError;
 use floresta_watch_only::kv_database::KvDatabaseError;
 use floresta_watch_only::WatchOnlyError;
+use thiserror::Error;
 use tokio_rustls::rustls::pki_types;
 
 use crate::slip132;
-#[derive(Debug)]
+
+#[derive(Debug, Error)]
 pub enum FlorestadError {
     /// Encoding/decoding error.
-    Encode(encode::Error),
+    #[error("Encode error: {0}")]
+    Encode(#[from] encode::Error),
 
     /// Integer parsing error.
-    ParseNum(std::num::ParseIntError),
+    #[error("int parse error: {0}")]
+    ParseNum(#[from] std::num::ParseIntError),
 
     /// Proof validation failure.
+    #[error("Rustreexo error: {0}")]
     Rustreexo(String),
 
     /// Generic IO operation error.
-    Io(std::io::Error),
+    #[error("Io error {0}")]
+    Io(#[from] std::io::Error),
 
-    // Block validation error, such as a missing transaction or an invalid proof.
-    BlockValidation(BlockValidationErrors),
+    /// Block validation error, such as a missing transaction or an invalid proof.
+    #[error("Error while validating block: {0:?}")]
+    BlockValidation(#[from] BlockValidationErrors),
 
     /// Script validation error, such as an invalid script or a failed evaluation.
-    ScriptValidation(bitcoin::blockdata::script::Error),
+    #[error("Error during script evaluation: {0}")]
+    ScriptValidation(#[from] bitcoin::blockdata::script::Error),
 
     /// Blockchain backend error, such as a missing block.
-    Blockchain(BlockchainError),
+    #[error("Error with our blockchain backend: {0:?}")]
+    Blockchain(#[from] BlockchainError),
 
     /// Deserializing JSON error.
-    SerdeJson(serde_json::Error),
+    #[error("Error serializing object {0}")]
+    SerdeJson(#[from] serde_json::Error),
 
     /// TOML parsing error.
-    TomlParsing(toml::de::Error),
+    #[error("Error deserializing toml file {0}")]
+    TomlParsing(#[from] toml::de::Error),
 
     /// Parsing registered HD version bytes from slip132.
-    WalletInput(slip132::Error),
+    #[error("Error while parsing user input {0:?}")]
+    WalletInput(#[from] slip132::Error),
 
     /// Parsing a bitcoin address.
-    AddressParsing(bitcoin::address::ParseError),
+    #[error("Invalid address {0}")]
+    AddressParsing(#[from] bitcoin::address::ParseError),
 
     /// Parsing miniscript error.
-    Miniscript(miniscript::Error),
+    #[error("Miniscript error: {0}")]
+    Miniscript(#[from] miniscript::Error),
 
     /// Parsing a private key in PEM format.
-    InvalidPrivKey(pki_types::pem::Error),
+    #[error("Error while reading PKCS#8 private key {0:?}")]
+    InvalidPrivKey(#[source] pki_types::pem::Error),
 
     /// Parsing a certificate from PEM format.
+    #[error("Error while reading PKCS#8 certificate {0:?}")]
     InvalidCert(pki_types::pem::Error),
 
     /// Configuring TLS settings.
-    CouldNotConfigureTLS(tokio_rustls::rustls::Error),
+    #[error("Error while configuring TLS: {0:?}")]
+    CouldNotConfigureTLS(#[from] tokio_rustls::rustls::Error),
 
     /// Generating a PKCS#8 keypair.
-    CouldNotGenerateKeypair(rcgen::Error),
+    #[error("Error while generating PKCS#8 keypair: {0}")]
+    CouldNotGenerateKeypair(#[source] rcgen::Error),
 
     /// Generating a certificate parameter.
-    CouldNotGenerateCertParam(rcgen::Error),
+    #[error("Error while generating certificate param: {0}")]
+    CouldNotGenerateCertParam(#[source] rcgen::Error),
 
     /// Generating a self-signed certificate.
-    CouldNotGenerateSelfSignedCert(rcgen::Error),
+    #[error("Error while generating self-signed certificate: {0}")]
+    CouldNotGenerateSelfSignedCert(#[source] rcgen::Error),
 
     /// Writing a file to the filesystem.
-    CouldNotWriteFile(String, std::io::Error),
+    #[error("Error while creating file {0}: {1}")]
+    CouldNotWriteFile(String, #[source] std::io::Error),
 
     /// Data directory doesn't exist or is not writable.
+    #[error("Data directory doesn't exist or is not writable: {0}")]
     InvalidDataDir(String),
 
+    /// Another instance already holds the advisory lock on this data directory.
+    #[error("Another florestad instance is already running against data directory {0}; only one instance may use a data directory at a time")]
+    DataDirLocked(std::path::PathBuf),
+
     /// Obtaining a lock on the data directory.
-    CouldNotOpenKvDatabase(KvDatabaseError),
+    #[error("Cannot open a key-value database: {0}")]
+    CouldNotOpenKvDatabase(#[source] KvDatabaseError),
 
     /// Initializing the watch-only wallet.
-    CouldNotInitializeWallet(WatchOnlyError<KvDatabaseError>),
+    #[error("Could not initialize wallet: {0}")]
+    CouldNotInitializeWallet(#[from] WatchOnlyError<KvDatabaseError>),
 
     /// Setting up the watch-only wallet.
-    CouldNotSetupWallet(String),
+    #[error("Could not setup wallet: {0}")]
+    CouldNotSetupWallet(#[from] WalletError),
 
     /// Invalid assumed valid value.
-    InvalidAssumeValid(bitcoin::hex::HexToArrayError),
+    #[error("Invalid assumed valid value: {0}")]
+    InvalidAssumeValid(#[source] bitcoin::hex::HexToArrayError),
 
     /// Failed to create a chain provider.
+    #[error("Could not create chain provider: {0}")]
     CouldNotCreateChainProvider(String),
 
     /// Failed to create an Electrum server.
-    CouldNotCreateElectrumServer(Box<dyn std::error::Error>),
+    #[error("Could not create Electrum server: {0}")]
+    CouldNotCreateElectrumServer(#[source] Box<dyn std::error::Error>),
 
     /// Failed to bind the Electrum server to a socket.
-    FailedToBindElectrumServer(std::io::Error),
+    #[error("Failed to bind Electrum server: {0}")]
+    FailedToBindElectrumServer(#[source] std::io::Error),
 
     /// Failed to create the TLS data directory.
-    CouldNotCreateTLSDataDir(String, std::io::Error),
+    #[error("Could not create TLS data directory {0}: {1}")]
+    CouldNotCreateTLSDataDir(String, #[source] std::io::Error),
 
     /// Failed to provide a valid xpub.
-    InvalidProvidedXpub(String, slip132::Error),
+    #[error("Invalid provided xpub {0}: {1:?}")]
+    InvalidProvidedXpub(String, #[source] slip132::Error),
+
+    /// Failed to build a descriptor from a bare xpub template (origin-aware BIP44/49/84/86
+    /// derivation): the xpub or the master fingerprint couldn't be parsed.
+    #[error("Invalid xpub template input {0}: {1}")]
+    InvalidTemplateXpub(String, String),
 
     /// Failed to obtain the wallet cache.
-    CouldNotObtainWalletCache(WatchOnlyError<KvDatabaseError>),
+    #[error("Could not obtain wallet cache: {0}")]
+    CouldNotObtainWalletCache(#[source] WatchOnlyError<KvDatabaseError>),
 
     /// Failed to push a descriptor to the wallet.
-    CouldNotPushDescriptor(String),
+    #[error("Could not push descriptor to wallet: {0}")]
+    CouldNotPushDescriptor(#[source] WalletError),
 
     /// The network is unsupported.
+    #[error("Unsupported network: {0}")]
     UnsupportedNetwork(bitcoin::Network),
 
     /// Invalid Ip address error.
-    InvalidIpAddress(AddrParseError),
+    #[error("Invalid IP address: {0}")]
+    InvalidIpAddress(#[from] AddrParseError),
 
     /// Ip address not found error.
+    #[error("No IP Addresses found for {0}")]
     NoIPAddressesFound(String),
 
     /// Resolve a hostname error.
-    CouldNotResolveHostname(std::io::Error),
+    #[error("Could not resolve hostname: {0}")]
+    CouldNotResolveHostname(#[source] std::io::Error),
 
     /// Create a flat chain store error.
-    CouldNotCreateFlatChainStore(FlatChainstoreError),
+    #[error("Failure while creating chainstore: {0:?}")]
+    CouldNotCreateFlatChainStore(#[source] FlatChainstoreError),
 
     /// Load a flat chain store error.
-    CouldNotLoadFlatChainStore(BlockchainError),
+    #[error("Failure while loading flat chainstore: {0:?}")]
+    CouldNotLoadFlatChainStore(#[source] BlockchainError),
 
     #[cfg(feature = "compact-filters")]
     /// Load a filter headers store
-    CouldNotLoadFilterHeadersStore(FlatFilterStoreError),
+    #[error("Failure while loading filter headers store: {0:?}")]
+    CouldNotLoadFilterHeadersStore(#[from] FlatFilterStoreError),
 }
 
-impl std::fmt::Display for FlorestadError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl FlorestadError {
+    /// Classifies this error into a Bitcoin Core-style JSON-RPC error code (see Core's
+    /// `rpc/protocol.h`), so JSON-RPC/Electrum clients can branch on error type instead of
+    /// scraping the `Display` text.
+    pub fn rpc_error_code(&self) -> i32 {
         match self {
-            #[cfg(feature = "compact-filters")]
-            FlorestadError::CouldNotLoadFilterHeadersStore(err) => {
-                write!(f, "Failure while loading filter headers store: {err:?}")
-            }
-            FlorestadError::Encode(err) => write!(f, "Encode error: {err}"),
-            FlorestadError::ParseNum(err) => write!(f, "int parse error: {err}"),
-            FlorestadError::Rustreexo(err) => write!(f, "Rustreexo error: {err}"),
-            FlorestadError::Io(err) => write!(f, "Io error {err}"),
-            FlorestadError::ScriptValidation(err) => {
-                write!(f, "Error during script evaluation: {err}")
-            }
-            FlorestadError::Blockchain(err) => {
-                write!(f, "Error with our blockchain backend: {err:?}")
-            }
-            FlorestadError::SerdeJson(err) => write!(f, "Error serializing object {err}"),
-            FlorestadError::WalletInput(err) => write!(f, "Error while parsing user input {err:?}"),
-            FlorestadError::TomlParsing(err) => write!(f, "Error deserializing toml file {err}"),
-            FlorestadError::AddressParsing(err) => write!(f, "Invalid address {err}"),
-            FlorestadError::Miniscript(err) => write!(f, "Miniscript error: {err}"),
-            FlorestadError::BlockValidation(err) => {
-                write!(f, "Error while validating block: {err:?}")
-            }
-            FlorestadError::CouldNotConfigureTLS(err) => {
-                write!(f, "Error while configuring TLS: {err:?}")
-            }
-            FlorestadError::InvalidPrivKey(err) => {
-                write!(f, "Error while reading PKCS#8 private key {err:?}")
-            }
-            FlorestadError::InvalidCert(err) => {
-                write!(f, "Error while reading PKCS#8 certificate {err:?}")
-            }
-            FlorestadError::CouldNotGenerateKeypair(err) => {
-                write!(f, "Error while generating PKCS#8 keypair: {err}")
-            }
-            FlorestadError::CouldNotGenerateCertParam(err) => {
-                write!(f, "Error while generating certificate param: {err}")
-            }
-            FlorestadError::CouldNotGenerateSelfSignedCert(err) => {
-                write!(f, "Error while generating self-signed certificate: {err}")
-            }
-            FlorestadError::CouldNotWriteFile(path, err) => {
-                write!(f, "Error while creating file {path}: {err}")
-            }
-            FlorestadError::InvalidDataDir(path) => {
-                write!(f, "Data directory doesn't exist or is not writable: {path}")
-            }
-            FlorestadError::CouldNotOpenKvDatabase(err) => {
-                write!(f, "Cannot open a key-value database: {err}")
-            }
-            FlorestadError::CouldNotInitializeWallet(err) => {
-                write!(f, "Could not initialize wallet: {err}")
-            }
-            FlorestadError::CouldNotSetupWallet(err) => {
-                write!(f, "Could not setup wallet: {err}")
-            }
-            FlorestadError::InvalidAssumeValid(error) => {
-                write!(f, "Invalid assumed valid value: {error}")
-            }
-            FlorestadError::CouldNotCreateChainProvider(err) => {
-                write!(f, "Could not create chain provider: {err}")
-            }
-            FlorestadError::CouldNotCreateElectrumServer(err) => {
-                write!(f, "Could not create Electrum server: {err}")
-            }
-            FlorestadError::FailedToBindElectrumServer(err) => {
-                write!(f, "Failed to bind Electrum server: {err}")
-            }
-            FlorestadError::CouldNotCreateTLSDataDir(path, err) => {
-                write!(f, "Could not create TLS data directory {path}: {err}")
-            }
-            FlorestadError::InvalidProvidedXpub(xpub, err) => {
-                write!(f, "Invalid provided xpub {xpub}: {err:?}")
-            }
-            FlorestadError::CouldNotObtainWalletCache(err) => {
-                write!(f, "Could not obtain wallet cache: {err}")
-            }
-            FlorestadError::CouldNotPushDescriptor(err) => {
-                write!(f, "Could not push descriptor to wallet: {err}")
-            }
-            FlorestadError::UnsupportedNetwork(err) => {
-                write!(f, "Unsupported network: {err}")
-            }
-            FlorestadError::InvalidIpAddress(err) => {
-                write!(f, "Invalid IP address: {err}")
-            }
-            FlorestadError::NoIPAddressesFound(hostname) => {
-                write!(f, "No IP Addresses found for {hostname}")
-            }
-            FlorestadError::CouldNotResolveHostname(host) => {
-                write!(f, "Could not resolve hostname: {host}")
-            }
-            FlorestadError::CouldNotCreateFlatChainStore(err) => {
-                write!(f, "Failure while creating chainstore: {err:?}")
-            }
-            FlorestadError::CouldNotLoadFlatChainStore(err) => {
-                write!(f, "Failure while loading flat chainstore: {err:?}")
-            }
+            // Parse error: the input wasn't even well-formed.
+            FlorestadError::SerdeJson(_) | FlorestadError::TomlParsing(_) => -32700,
+
+            // Invalid parameter: well-formed, but not a valid value for this call.
+            FlorestadError::InvalidProvidedXpub(..)
+            | FlorestadError::InvalidTemplateXpub(..)
+            | FlorestadError::AddressParsing(_)
+            | FlorestadError::WalletInput(_)
+            | FlorestadError::InvalidAssumeValid(_)
+            | FlorestadError::InvalidIpAddress(_)
+            | FlorestadError::UnsupportedNetwork(_)
+            | FlorestadError::Miniscript(_)
+            | FlorestadError::CouldNotSetupWallet(_)
+            | FlorestadError::CouldNotPushDescriptor(_) => -8,
+
+            // Invalid address/key-style: the referenced resource couldn't be found.
+            FlorestadError::NoIPAddressesFound(_) | FlorestadError::CouldNotResolveHostname(_) => {
+                -5
+            }
+
+            // Verify error: a block or script failed consensus/policy validation.
+            FlorestadError::BlockValidation(_) | FlorestadError::ScriptValidation(_) => -25,
+
+            // Everything else is an internal/store failure on our end, not something the
+            // caller supplied and can fix by retrying with different input.
+            _ => -32603,
         }
     }
 }
 
-#[cfg(feature = "compact-filters")]
-impl_error_from!(
-    FlorestadError,
-    FlatFilterStoreError,
-    CouldNotLoadFilterHeadersStore
-);
-impl_error_from!(FlorestadError, encode::Error, Encode);
-impl_error_from!(FlorestadError, std::num::ParseIntError, ParseNum);
-impl_error_from!(FlorestadError, String, Rustreexo);
-impl_error_from!(FlorestadError, std::io::Error, Io);
-impl_error_from!(
-    FlorestadError,
-    bitcoin::blockdata::script::Error,
-    ScriptValidation
-);
-impl_error_from!(FlorestadError, BlockchainError, Blockchain);
-impl_error_from!(FlorestadError, serde_json::Error, SerdeJson);
-impl_error_from!(FlorestadError, slip132::Error, WalletInput);
-impl_error_from!(FlorestadError, toml::de::Error, TomlParsing);
-impl_error_from!(FlorestadError, BlockValidationErrors, BlockValidation);
-impl_error_from!(FlorestadError, bitcoin::address::ParseError, AddressParsing);
-impl_error_from!(FlorestadError, miniscript::Error, Miniscript);
-impl_error_from!(FlorestadError, pki_types::pem::Error, InvalidPrivKey);
-impl_error_from!(
-    FlorestadError,
-    tokio_rustls::rustls::Error,
-    CouldNotConfigureTLS
-);
-impl_error_from!(
-    FlorestadError,
-    WatchOnlyError<KvDatabaseError>,
-    CouldNotInitializeWallet
-);
-
-impl std::error::Error for FlorestadError {}
+// `String` doesn't implement `std::error::Error`, so `#[from]` (which also wires up
+// `Error::source()`) isn't available here; this is a plain, source-less conversion instead.
+impl From<String> for FlorestadError {
+    fn from(err: String) -> Self {
+        FlorestadError::Rustreexo(err)
+    }
+}
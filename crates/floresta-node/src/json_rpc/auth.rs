@@ -0,0 +1,164 @@
+//! Permission-scoped access control for the JSON-RPC server.
+//!
+//! Every RPC method is tagged with a [`Permission`] it requires, following the read/write/sign
+//! split Bitcoin Core's cookie auth uses: read-only queries need the least, state-mutating calls
+//! need more, and anything that broadcasts a signed transaction needs the most. A caller
+//! authenticates with an [`AuthToken`], which carries the permission set it was issued with;
+//! [`AuthToken::authorize`] is the single choke point that checks a token against a method's
+//! required scope.
+//!
+//! Tokens are opaque random secrets, written to a cookie file the same way Core writes
+//! `.cookie` next to its data directory: anything that can read the data directory can
+//! authenticate. When auth is disabled entirely (no cookie file configured), existing clients
+//! keep working exactly as before this was added.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bitcoin::hex::DisplayHex;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use subtle::ConstantTimeEq;
+
+use super::res::jsonrpc_interface::JsonRpcError;
+
+/// How many random bytes back an [`AuthToken`]'s secret, matching Core's `.cookie` (32 bytes of
+/// entropy, hex-encoded).
+const TOKEN_BYTES: usize = 32;
+
+/// The access levels a token can be issued with. They form a ladder - `Sign` ⊇ `Write` ⊇ `Read`
+/// - so a token issued with a higher level may also call methods that only require a lower one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Permission {
+    /// Read-only queries that don't change node or wallet state, e.g. `getblockchaininfo`.
+    Read,
+
+    /// Methods that mutate node or wallet state without moving funds, e.g. `rescanblockchain`.
+    Write,
+
+    /// Methods that broadcast a transaction or otherwise touch signed, fund-moving data.
+    Sign,
+}
+
+impl Permission {
+    /// The scope an RPC method requires, following the node's own classification of each
+    /// method. Unrecognized methods default to [`Permission::Sign`], the most restrictive level,
+    /// so a new method added without updating this table fails closed instead of open.
+    pub fn required_for(method: &str) -> Permission {
+        match method {
+            "getblockchaininfo" | "getbestblockhash" | "getblockhash" | "getblockheader"
+            | "gettransaction" | "gettxoutproof" | "getroots" | "getpeerinfo" | "getblock"
+            | "gettxout" | "findtxout" | "getmemoryinfo" | "getrpcinfo" | "uptime"
+            | "listdescriptors" | "ping" | "getblockfilter" | "getblockcount" => Permission::Read,
+
+            "loaddescriptor" | "rescanblockchain" | "addnode" => Permission::Write,
+
+            "sendrawtransaction" | "stop" => Permission::Sign,
+
+            _ => Permission::Sign,
+        }
+    }
+}
+
+/// A bearer credential presented with an RPC request, carrying the single [`Permission`] level
+/// it was issued with.
+///
+/// Deliberately doesn't derive `PartialEq`/`Eq`: comparing the raw `secret` with `==` is a
+/// non-constant-time comparison, a timing oracle a remote caller could use to guess it
+/// byte-by-byte. [`AuthToken::authorize`] uses [`ConstantTimeEq`] instead.
+#[derive(Debug, Clone)]
+pub struct AuthToken {
+    secret: String,
+    permission: Permission,
+}
+
+impl AuthToken {
+    /// Generates a new token at the given permission level, backed by
+    /// [`TOKEN_BYTES`] bytes of CSPRNG output.
+    pub fn generate(permission: Permission) -> Self {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+
+        Self {
+            secret: bytes.to_lower_hex_string(),
+            permission,
+        }
+    }
+
+    /// Rebuilds a token from a secret read back from a cookie file.
+    pub fn from_secret(secret: String, permission: Permission) -> Self {
+        Self { secret, permission }
+    }
+
+    /// The opaque bearer value a client presents, e.g. in an `Authorization: Bearer <secret>`
+    /// header.
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Checks a caller-presented secret against this token, in constant time, then checks this
+    /// token's permission against the scope `method` requires.
+    ///
+    /// Returns [`JsonRpcError::Unauthorized`] if `presented_secret` doesn't match this token's
+    /// secret - no valid credential was presented at all - or [`JsonRpcError::Forbidden`] if it
+    /// matches but the permission it carries doesn't cover `method`.
+    pub fn authorize(&self, presented_secret: &str, method: &str) -> Result<(), JsonRpcError> {
+        if !bool::from(self.secret.as_bytes().ct_eq(presented_secret.as_bytes())) {
+            return Err(JsonRpcError::Unauthorized);
+        }
+
+        if self.permission >= Permission::required_for(method) {
+            Ok(())
+        } else {
+            Err(JsonRpcError::Forbidden)
+        }
+    }
+}
+
+/// Writes `token`'s secret to `path` as a Core-style cookie file: a single line, created with
+/// owner-only permissions on unix so other local users can't read it back out.
+///
+/// On unix the file is opened with mode `0o600` via `OpenOptions::mode` so a freshly-created
+/// file never has a moment with looser permissions, rather than being written with default
+/// permissions and `chmod`'d afterward. `mode` only applies when the open call actually creates
+/// the file, though, so an existing cookie file (left over from before this was added, or
+/// pre-created by something else with a looser umask) is `chmod`'d explicitly afterward too.
+pub fn write_cookie_file(path: &Path, token: &AuthToken) -> io::Result<()> {
+    use std::io::Write;
+
+    #[cfg(unix)]
+    let mut file = {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?
+    };
+
+    #[cfg(not(unix))]
+    let mut file = fs::File::create(path)?;
+
+    file.write_all(token.secret().as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = file.metadata()?.permissions();
+        permissions.set_mode(0o600);
+        file.set_permissions(permissions)?;
+    }
+
+    Ok(())
+}
+
+/// Reads back a cookie file written by [`write_cookie_file`], reconstructing the token at the
+/// given permission level.
+pub fn read_cookie_file(path: &Path, permission: Permission) -> io::Result<AuthToken> {
+    let secret = fs::read_to_string(path)?;
+    Ok(AuthToken::from_secret(secret.trim().to_string(), permission))
+}
@@ -1,6 +1,8 @@
+pub mod auth;
 pub mod request;
 pub mod res;
 pub mod server;
+pub mod subscription;
 
 // endpoint impls
 mod blockchain;
@@ -19,8 +19,44 @@ pub struct RpcRequest {
     /// The parameters for the method, as an array of json values.
     pub params: Option<Value>,
 
-    /// An optional identifier for the request, which can be used to match responses.
-    pub id: u64,
+    /// An identifier for the request, used to match responses.
+    ///
+    /// Per the JSON-RPC 2.0 spec this should be a string, a number, or absent/`Null` for a
+    /// notification (a request the server must not reply to). Kept as a raw `Value` rather than
+    /// a closed `Number`/`String`/`Null` enum so it serializes back into the response verbatim
+    /// regardless of which of those three shapes the client sent. Defaults to `Value::Null` when
+    /// the field is missing from the request object, which `dispatch_incoming` treats exactly
+    /// like an explicit `null` id: both mean "don't reply".
+    #[serde(default)]
+    pub id: Value,
+}
+
+/// A JSON-RPC 2.0 request body, which per spec may be a single request object or a batch: a
+/// JSON array of request objects submitted in one round trip.
+///
+/// Classified straight off the raw decoded body rather than via `#[serde(untagged)]`, so a
+/// malformed element inside a batch array can be turned into its own per-element error response
+/// instead of failing the whole batch to decode. `crate::json_rpc::res::dispatch_incoming`
+/// decodes each element into an [`RpcRequest`] and dispatches it, pairing every reply with its
+/// originating request's `id` in the returned `BatchOutput`.
+pub enum Incoming {
+    /// A single request object.
+    Single(Value),
+
+    /// A batch: the elements of a top-level JSON array, still undecoded.
+    Batch(Vec<Value>),
+}
+
+impl Incoming {
+    /// Classifies a raw JSON-RPC body: a JSON array is a batch, anything else (including a
+    /// malformed, non-object body) is treated as a single request and left for the caller to
+    /// decode, so parse failures are reported with the usual single-request error codes.
+    pub fn from_value(body: Value) -> Self {
+        match body {
+            Value::Array(elements) => Incoming::Batch(elements),
+            single => Incoming::Single(single),
+        }
+    }
 }
 
 /// Some utility functions to extract parameters from the request. These
@@ -193,4 +229,24 @@ pub mod arg_parser {
             }
         }
     }
+
+    /// A handler's parameter struct, extractable from `params` in one step instead of a
+    /// hand-rolled sequence of `get_*` calls.
+    ///
+    /// Implementors read each field off the same [`get_arg_by`] object-vs-array dispatch the
+    /// individual getters already use, so a struct implementing this works whether the caller
+    /// sent positional (array) or named (object) params, and surfaces the same
+    /// [`ArgGetterError`] variants with the right field names on failure.
+    ///
+    /// This is currently implemented by hand per struct rather than via a
+    /// `#[derive(FromRpcParams)]` macro: a derive would need its own proc-macro crate (`syn`,
+    /// `quote`, `proc-macro2` as dependencies), and this checkout has no `Cargo.toml` anywhere to
+    /// declare that crate or its dependencies in. The trait is the stable surface a future derive
+    /// would target; until then, implement it directly the way [`get_numeric`], [`get_string`],
+    /// and friends are used today; i.e. one `get_arg_by`/getter call per field, wrapping
+    /// `Option<T>` fields in [`get_optional_field`].
+    pub trait FromRpcParams: Sized {
+        /// Extracts `Self` from a request's `params` value.
+        fn from_rpc_params(params: &Value) -> Result<Self, ArgGetterError>;
+    }
 }
@@ -59,6 +59,165 @@ pub mod jsonrpc_interface {
                 Err(e) => Self::error(e.rpc_error(), id),
             }
         }
+
+        /// An [`INVALID_REQUEST`] error response for a body that doesn't decode as a valid
+        /// [`RpcRequest`].
+        fn invalid_request(id: Value) -> Self {
+            Self::error(
+                RpcError {
+                    code: INVALID_REQUEST,
+                    message: "Invalid request".into(),
+                    data: None,
+                },
+                id,
+            )
+        }
+    }
+
+    /// Either a single [`Response`] or a batch of them, depending on whether the incoming
+    /// request was a lone object or a JSON-RPC 2.0 batch array (this is what the spec calls an
+    /// `RpcBatch`: a lone request object or a JSON array of them).
+    ///
+    /// Serializes as a bare object or a bare array respectively, matching whichever shape the
+    /// client sent. [`BatchOutput::Empty`] is a third, non-serialized case: a batch made up
+    /// entirely of notifications, which per spec must get no reply at all, not even `[]`.
+    #[derive(Debug, Serialize)]
+    #[serde(untagged)]
+    pub enum BatchOutput {
+        Single(Response),
+        Batch(Vec<Response>),
+
+        /// Every member of the batch was a notification; [`IntoResponse`] answers with no body
+        /// at all, short-circuiting before this variant would ever need to be serialized.
+        Empty,
+    }
+
+    impl IntoResponse for BatchOutput {
+        fn into_response(self) -> axum::http::Response<axum::body::Body> {
+            if matches!(self, BatchOutput::Empty) {
+                return axum::http::Response::builder()
+                    .status(axum::http::StatusCode::NO_CONTENT)
+                    .body(axum::body::Body::empty())
+                    .unwrap();
+            }
+
+            // Every well-formed JSON-RPC reply, single or batched, is still HTTP 200: the
+            // JSON-RPC error object (if any) is what communicates failure, one level down from
+            // HTTP status.
+            axum::http::Response::builder()
+                .status(axum::http::StatusCode::OK)
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(serde_json::to_vec(&self).unwrap()))
+                .unwrap()
+        }
+    }
+
+    /// The outcome of decoding and dispatching one raw JSON body element.
+    ///
+    /// Kept distinct from a plain [`Response`] so a true notification (successfully decoded,
+    /// `id` omitted - JSON-RPC 2.0 permits `id` to be a number, a string, or omitted entirely to
+    /// mean "don't reply") can be told apart from a request that failed to decode at all: both
+    /// end up with a null `id`, but only the former should be suppressed. A malformed request
+    /// still gets its `INVALID_REQUEST`/parse-error response back, since the client needs to
+    /// know the call didn't go through.
+    enum Dispatched {
+        /// Decoded fine, but `id` was omitted: the caller doesn't want a reply.
+        Notification,
+        Response(Response),
+    }
+
+    /// Dispatches a raw JSON-RPC body, handling the JSON-RPC 2.0 batch framing around whatever
+    /// `dispatch_one` does for a single decoded [`RpcRequest`].
+    ///
+    /// `authorize` is checked against every decoded request's method before `dispatch_one` ever
+    /// runs, so a caller without a valid token, or one whose token's [`Permission`] doesn't cover
+    /// the method, gets an [`JsonRpcError::Unauthorized`]/[`JsonRpcError::Forbidden`] response
+    /// without the handler being invoked at all - this is the one place every request passes
+    /// through, batched or not, so it's the right choke point for the check rather than
+    /// threading it into every individual handler.
+    ///
+    /// A non-array body is decoded and dispatched as a single request. A JSON array is a batch:
+    /// an empty one is itself invalid per spec and yields a single [`INVALID_REQUEST`] error
+    /// object (not an empty array), otherwise every element is decoded and dispatched
+    /// independently - in any order, per spec - so one malformed element doesn't take the rest
+    /// of the batch down with it. Notifications (`id` omitted) are still dispatched - `ping` or
+    /// `stop` can be fired fire-and-forget - but never get a reply: a lone notification yields
+    /// [`BatchOutput::Empty`], and one inside a batch is simply omitted from the output array. If
+    /// every member of a non-empty batch was a notification, the whole response is
+    /// [`BatchOutput::Empty`] rather than `[]`.
+    ///
+    /// [`Permission`]: crate::json_rpc::auth::Permission
+    pub fn dispatch_incoming(
+        body: Value,
+        authorize: impl Fn(&str) -> Result<(), JsonRpcError>,
+        mut dispatch_one: impl FnMut(crate::json_rpc::request::RpcRequest) -> Response,
+    ) -> BatchOutput {
+        use crate::json_rpc::request::Incoming;
+        use crate::json_rpc::request::RpcRequest;
+
+        fn decode_and_dispatch(
+            element: Value,
+            authorize: &impl Fn(&str) -> Result<(), JsonRpcError>,
+            dispatch_one: &mut impl FnMut(RpcRequest) -> Response,
+        ) -> Dispatched {
+            match serde_json::from_value::<RpcRequest>(element) {
+                Ok(request) => {
+                    let is_notification = request.id == Value::Null;
+                    let response = match authorize(&request.method) {
+                        Ok(()) => dispatch_one(request),
+                        Err(e) => Response::from_result(Err(e), request.id),
+                    };
+
+                    if is_notification {
+                        Dispatched::Notification
+                    } else {
+                        Dispatched::Response(response)
+                    }
+                }
+                Err(_) => Dispatched::Response(Response::invalid_request(Value::Null)),
+            }
+        }
+
+        match Incoming::from_value(body) {
+            Incoming::Single(element) if element.is_object() => {
+                match decode_and_dispatch(element, &authorize, &mut dispatch_one) {
+                    Dispatched::Notification => BatchOutput::Empty,
+                    Dispatched::Response(response) => BatchOutput::Single(response),
+                }
+            }
+
+            Incoming::Single(_) => BatchOutput::Single(Response::error(
+                RpcError {
+                    code: PARSE_ERROR,
+                    message: "Parse error".into(),
+                    data: None,
+                },
+                Value::Null,
+            )),
+
+            Incoming::Batch(elements) if elements.is_empty() => {
+                BatchOutput::Single(Response::invalid_request(Value::Null))
+            }
+
+            Incoming::Batch(elements) => {
+                let responses: Vec<Response> = elements
+                    .into_iter()
+                    .filter_map(
+                        |element| match decode_and_dispatch(element, &authorize, &mut dispatch_one)
+                        {
+                            Dispatched::Notification => None,
+                            Dispatched::Response(response) => Some(response),
+                        },
+                    )
+                    .collect();
+
+                if responses.is_empty() {
+                    BatchOutput::Empty
+                } else {
+                    BatchOutput::Batch(responses)
+                }
+            }
+        }
     }
 
     /// Jsonrpc error object.
@@ -110,6 +269,56 @@ pub mod jsonrpc_interface {
     /// See also `SERVER_ERROR_MAX`.
     pub const SERVER_ERROR_MIN: i16 = -32000;
 
+    /// Generic "not found"-style implementation-defined error, for ad-hoc failures built with
+    /// [`RpcError::not_found`] that don't have a dedicated [`JsonRpcError`] variant. Distinct
+    /// from the per-variant codes (e.g. [`JsonRpcError::TxNotFound`]) assigned off
+    /// `SERVER_ERROR_MAX`/`SERVER_ERROR_MIN`.
+    pub const GENERIC_NOT_FOUND: i16 = -32050;
+
+    /// Generic implementation-defined server error, for ad-hoc failures built with
+    /// [`RpcError::server_error`] that don't have a dedicated [`JsonRpcError`] variant.
+    pub const GENERIC_SERVER_ERROR: i16 = -32051;
+
+    impl RpcError {
+        /// Builds an `Invalid params` (-32602) error, optionally carrying structured `data`
+        /// about which parameter was the problem.
+        pub fn invalid_params(message: impl Into<String>, data: Option<Value>) -> Self {
+            Self {
+                code: INVALID_METHOD_PARAMETERS,
+                message: message.into(),
+                data,
+            }
+        }
+
+        /// Builds an `Internal error` (-32603) error.
+        pub fn internal(message: impl Into<String>, data: Option<Value>) -> Self {
+            Self {
+                code: INTERNAL_ERROR,
+                message: message.into(),
+                data,
+            }
+        }
+
+        /// Builds a generic "not found" implementation-defined error.
+        pub fn not_found(message: impl Into<String>, data: Option<Value>) -> Self {
+            Self {
+                code: GENERIC_NOT_FOUND,
+                message: message.into(),
+                data,
+            }
+        }
+
+        /// Builds a generic implementation-defined server error, for a failure that doesn't
+        /// already have a dedicated [`JsonRpcError`] variant and its own assigned code.
+        pub fn server_error(message: impl Into<String>, data: Option<Value>) -> Self {
+            Self {
+                code: GENERIC_SERVER_ERROR,
+                message: message.into(),
+                data,
+            }
+        }
+    }
+
     #[derive(Debug)]
     pub enum JsonRpcError {
         /// There was a rescan request but we do not have any addresses in the watch-only wallet.
@@ -192,207 +401,291 @@ pub mod jsonrpc_interface {
 
         /// Something went wrong when attempting to publish a transaction to mempool
         MempoolAccept(AcceptToMempoolError),
+
+        /// `unsubscribe` was called with a subscription id that isn't currently registered on
+        /// this connection (already unsubscribed, or never issued).
+        SubscriptionNotFound,
+
+        /// `subscribe` was called with a channel name that isn't one of the ones the node
+        /// publishes notifications on.
+        UnknownChannel(String),
+
+        /// No valid bearer token/cookie was presented for a method that requires one.
+        Unauthorized,
+
+        /// A valid token was presented, but its permission set doesn't cover the method's
+        /// required scope, e.g. a read-only token calling `sendrawtransaction`.
+        Forbidden,
     }
 
     impl_error_from!(JsonRpcError, AcceptToMempoolError, MempoolAccept);
 
+    /// The `(HTTP status, JSON-RPC code, message)` row a single [`JsonRpcError`] variant maps
+    /// to. Built once by [`JsonRpcError::info`] and read by both `http_code` and `rpc_error`, so
+    /// adding a variant only means touching one match arm instead of keeping two separate
+    /// `match self { ... }` blocks in sync by hand.
+    struct ErrorInfo {
+        http_status: axum::http::StatusCode,
+        code: i16,
+        message: &'static str,
+    }
+
     impl JsonRpcError {
-        pub fn http_code(&self) -> u16 {
+        fn info(&self) -> ErrorInfo {
             use axum::http::StatusCode;
 
-            match self {
-                // 400 Bad Request - client sent invalid data
-                JsonRpcError::InvalidHex
-                | JsonRpcError::InvalidAddress
-                | JsonRpcError::InvalidScript
-                | JsonRpcError::InvalidRequest
-                | JsonRpcError::InvalidDescriptor(_)
-                | JsonRpcError::InvalidVerbosityLevel
-                | JsonRpcError::Decode(_)
-                | JsonRpcError::MempoolAccept(_)
-                | JsonRpcError::InvalidMemInfoMode
-                | JsonRpcError::InvalidAddnodeCommand
-                | JsonRpcError::InvalidDisconnectNodeCommand
-                | JsonRpcError::InvalidTimestamp
-                | JsonRpcError::InvalidRescanVal
-                | JsonRpcError::NoAddressesToRescan
-                | JsonRpcError::InvalidParameterType(_)
-                | JsonRpcError::MissingParameter(_)
-                | JsonRpcError::Wallet(_) => StatusCode::BAD_REQUEST.as_u16(),
-
-                // 404 Not Found - resource/method doesn't exist
-                JsonRpcError::MethodNotFound
-                | JsonRpcError::BlockNotFound
-                | JsonRpcError::TxNotFound
-                | JsonRpcError::PeerNotFound => StatusCode::NOT_FOUND.as_u16(),
-
-                // 500 Internal Server Error - server messed up
-                JsonRpcError::ChainWorkOverflow => StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-
-                // 503 Service Unavailable - server can't handle right now
-                JsonRpcError::InInitialBlockDownload
-                | JsonRpcError::NoBlockFilters
-                | JsonRpcError::Node(_)
-                | JsonRpcError::Chain
-                | JsonRpcError::Filters(_) => StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            let (http_status, code, message) = match self {
+                JsonRpcError::Decode(_) => (StatusCode::BAD_REQUEST, PARSE_ERROR, "Parse error"),
+                JsonRpcError::InvalidRequest => {
+                    (StatusCode::BAD_REQUEST, INVALID_REQUEST, "Invalid request")
+                }
+                JsonRpcError::MethodNotFound => {
+                    (StatusCode::NOT_FOUND, METHOD_NOT_FOUND, "Method not found")
+                }
+                JsonRpcError::InvalidHex => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid hex encoding",
+                ),
+                JsonRpcError::InvalidAddress => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid address",
+                ),
+                JsonRpcError::InvalidScript => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid script",
+                ),
+                JsonRpcError::InvalidDescriptor(_) => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid descriptor",
+                ),
+                JsonRpcError::InvalidVerbosityLevel => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid verbosity level",
+                ),
+                JsonRpcError::InvalidTimestamp => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid timestamp",
+                ),
+                JsonRpcError::InvalidMemInfoMode => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid meminfo mode",
+                ),
+                JsonRpcError::InvalidAddnodeCommand => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid addnode command",
+                ),
+                JsonRpcError::InvalidDisconnectNodeCommand => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid disconnectnode command",
+                ),
+                JsonRpcError::InvalidRescanVal => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid rescan values",
+                ),
+                JsonRpcError::InvalidParameterType(_) => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Invalid parameter type",
+                ),
+                JsonRpcError::MissingParameter(_) => (
+                    StatusCode::BAD_REQUEST,
+                    INVALID_METHOD_PARAMETERS,
+                    "Missing parameter",
+                ),
+                JsonRpcError::UnknownChannel(_) => (
+                    StatusCode::BAD_REQUEST,
+                    SERVER_ERROR_MAX + 11, // -32088
+                    "Unknown channel",
+                ),
+                JsonRpcError::Wallet(_) => (
+                    StatusCode::BAD_REQUEST,
+                    SERVER_ERROR_MAX + 4, // -32095
+                    "Wallet error",
+                ),
+                JsonRpcError::MempoolAccept(_) => (
+                    StatusCode::BAD_REQUEST,
+                    SERVER_ERROR_MAX + 5, // -32094
+                    "Mempool error:",
+                ),
+
+                JsonRpcError::Unauthorized => (
+                    StatusCode::UNAUTHORIZED,
+                    SERVER_ERROR_MAX + 12, // -32087
+                    "Unauthorized",
+                ),
+
+                JsonRpcError::Forbidden => (
+                    StatusCode::FORBIDDEN,
+                    SERVER_ERROR_MAX + 13, // -32086
+                    "Forbidden",
+                ),
+
+                JsonRpcError::BlockNotFound => (
+                    StatusCode::NOT_FOUND,
+                    SERVER_ERROR_MAX + 1, // -32098
+                    "Block not found",
+                ),
+                JsonRpcError::TxNotFound => (
+                    StatusCode::NOT_FOUND,
+                    SERVER_ERROR_MAX, // -32099
+                    "Transaction not found",
+                ),
+                JsonRpcError::SubscriptionNotFound => (
+                    StatusCode::NOT_FOUND,
+                    SERVER_ERROR_MAX + 10, // -32089
+                    "Subscription not found",
+                ),
+                JsonRpcError::PeerNotFound => (
+                    StatusCode::NOT_FOUND,
+                    SERVER_ERROR_MAX + 2, // -32097
+                    "Peer not found",
+                ),
+
+                JsonRpcError::ChainWorkOverflow => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    INTERNAL_ERROR,
+                    "Chain work overflow",
+                ),
+
+                JsonRpcError::InInitialBlockDownload => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVER_ERROR_MAX + 6, // -32093
+                    "Node is in initial block download",
+                ),
+                JsonRpcError::NoBlockFilters => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVER_ERROR_MAX + 7, // -32092
+                    "Block filters not available",
+                ),
+                JsonRpcError::Node(_) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVER_ERROR_MAX + 8, // -32091
+                    "Node error",
+                ),
+                JsonRpcError::Chain => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVER_ERROR_MAX + 9, // -32090
+                    "Chain error",
+                ),
+                JsonRpcError::Filters(_) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVER_ERROR_MIN, // -32000
+                    "Filters error",
+                ),
+
+                JsonRpcError::NoAddressesToRescan => (
+                    StatusCode::BAD_REQUEST,
+                    SERVER_ERROR_MAX + 3, // -32096
+                    "No addresses to rescan",
+                ),
+            };
+
+            ErrorInfo {
+                http_status,
+                code,
+                message,
             }
         }
 
-        pub fn rpc_error(&self) -> RpcError {
+        /// The structured `data` payload carried alongside this error's code/message, if any.
+        fn data(&self) -> Option<Value> {
             match self {
-                // Parse error - invalid JSON received
-                JsonRpcError::Decode(msg) => RpcError {
-                    code: PARSE_ERROR,
-                    message: "Parse error".into(),
-                    data: Some(Value::String(msg.clone())),
-                },
+                JsonRpcError::Decode(msg) => Some(Value::String(msg.clone())),
+                JsonRpcError::InvalidDescriptor(e) => Some(Value::String(e.to_string())),
+                JsonRpcError::InvalidParameterType(param) => Some(Value::String(param.clone())),
+                JsonRpcError::MissingParameter(param) => Some(Value::String(param.clone())),
+                JsonRpcError::Wallet(msg) => Some(Value::String(msg.clone())),
+                JsonRpcError::MempoolAccept(err) => Some(mempool_reject_json(err)),
+                JsonRpcError::Node(msg) => Some(Value::String(msg.clone())),
+                JsonRpcError::Filters(msg) => Some(Value::String(msg.clone())),
+                JsonRpcError::UnknownChannel(channel) => Some(Value::String(channel.clone())),
+                _ => None,
+            }
+        }
 
-                // Invalid request - not a valid JSON-RPC request
-                JsonRpcError::InvalidRequest => RpcError {
-                    code: INVALID_REQUEST,
-                    message: "Invalid request".into(),
-                    data: None,
-                },
+        pub fn http_code(&self) -> u16 {
+            self.info().http_status.as_u16()
+        }
 
-                // Method not found
-                JsonRpcError::MethodNotFound => RpcError {
-                    code: METHOD_NOT_FOUND,
-                    message: "Method not found".into(),
-                    data: None,
-                },
+        pub fn rpc_error(&self) -> RpcError {
+            let info = self.info();
 
-                // Invalid params - invalid method parameters
-                JsonRpcError::InvalidHex => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid hex encoding".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidAddress => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid address".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidScript => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid script".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidDescriptor(e) => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid descriptor".into(),
-                    data: Some(Value::String(e.to_string())),
-                },
-                JsonRpcError::InvalidVerbosityLevel => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid verbosity level".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidTimestamp => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid timestamp".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidMemInfoMode => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid meminfo mode".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidAddnodeCommand => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid addnode command".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidDisconnectNodeCommand => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid disconnectnode command".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidRescanVal => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid rescan values".into(),
-                    data: None,
-                },
-                JsonRpcError::InvalidParameterType(param) => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Invalid parameter type".into(),
-                    data: Some(Value::String(param.clone())),
-                },
-                JsonRpcError::MissingParameter(param) => RpcError {
-                    code: INVALID_METHOD_PARAMETERS,
-                    message: "Missing parameter".into(),
-                    data: Some(Value::String(param.clone())),
-                },
+            RpcError {
+                code: info.code,
+                message: info.message.into(),
+                data: self.data(),
+            }
+        }
+    }
 
-                // Internal error
-                JsonRpcError::ChainWorkOverflow => RpcError {
-                    code: INTERNAL_ERROR,
-                    message: "Chain work overflow".into(),
-                    data: None,
-                },
+    /// Renders a mempool rejection as a structured object instead of a single opaque string, so
+    /// tooling can branch on `reject_reason` rather than string-matching the English message.
+    ///
+    /// `AcceptToMempoolError` lives in `floresta_mempool`, outside this checkout, so this works
+    /// off the error's rendered [`Display`] message rather than its variants; once that type
+    /// grows its own `reject_reason`/numeric-context accessors, this should read those directly
+    /// instead of pattern-matching the message text.
+    fn mempool_reject_json(err: &AcceptToMempoolError) -> Value {
+        let message = err.to_string();
+        let reject_reason = classify_mempool_rejection(&message);
+
+        serde_json::json!({
+            "reject_reason": reject_reason,
+            "message": message,
+        })
+    }
 
-                // Server errors (implementation-defined: -32000 to -32099)
-                JsonRpcError::TxNotFound => RpcError {
-                    code: SERVER_ERROR_MAX, // -32099
-                    message: "Transaction not found".into(),
-                    data: None,
-                },
-                JsonRpcError::BlockNotFound => RpcError {
-                    code: SERVER_ERROR_MAX + 1, // -32098
-                    message: "Block not found".into(),
-                    data: None,
-                },
-                JsonRpcError::PeerNotFound => RpcError {
-                    code: SERVER_ERROR_MAX + 2, // -32097
-                    message: "Peer not found".into(),
-                    data: None,
-                },
-                JsonRpcError::NoAddressesToRescan => RpcError {
-                    code: SERVER_ERROR_MAX + 3, // -32096
-                    message: "No addresses to rescan".into(),
-                    data: None,
-                },
-                JsonRpcError::Wallet(msg) => RpcError {
-                    code: SERVER_ERROR_MAX + 4, // -32095
-                    message: "Wallet error".into(),
-                    data: Some(Value::String(msg.clone())),
-                },
-                JsonRpcError::MempoolAccept(msg) => RpcError {
-                    code: SERVER_ERROR_MAX + 5, // -32094
-                    message: "Mempool error:".into(),
-                    data: Some(Value::String(format!("{msg}"))),
-                },
-                JsonRpcError::InInitialBlockDownload => RpcError {
-                    code: SERVER_ERROR_MAX + 6, // -32093
-                    message: "Node is in initial block download".into(),
-                    data: None,
-                },
-                JsonRpcError::NoBlockFilters => RpcError {
-                    code: SERVER_ERROR_MAX + 7, // -32092
-                    message: "Block filters not available".into(),
-                    data: None,
-                },
-                JsonRpcError::Node(msg) => RpcError {
-                    code: SERVER_ERROR_MAX + 8, // -32091
-                    message: "Node error".into(),
-                    data: Some(Value::String(msg.clone())),
-                },
-                JsonRpcError::Chain => RpcError {
-                    code: SERVER_ERROR_MAX + 9, // -32090
-                    message: "Chain error".into(),
-                    data: None,
-                },
-                JsonRpcError::Filters(msg) => RpcError {
-                    code: SERVER_ERROR_MIN, // -32000
-                    message: "Filters error".into(),
-                    data: Some(Value::String(msg.clone())),
-                },
-            }
+    /// Maps a mempool rejection message onto one of Bitcoin Core's stable `reject_reason`
+    /// strings (see Core's `validation.cpp` `TxValidationResult` reasons), falling back to
+    /// `"reject-other"` for anything that doesn't match a known phrase.
+    fn classify_mempool_rejection(message: &str) -> &'static str {
+        let message = message.to_lowercase();
+
+        if message.contains("already in mempool") || message.contains("already known") {
+            "txn-already-in-mempool"
+        } else if message.contains("missing-inputs") || message.contains("missing input") {
+            "missing-inputs"
+        } else if message.contains("dust") {
+            "dust"
+        } else if message.contains("fee")
+            && (message.contains("insufficient")
+                || message.contains("below")
+                || message.contains("min relay"))
+        {
+            "insufficient-fee"
+        } else if message.contains("non-standard") || message.contains("nonstandard") {
+            "non-standard"
+        } else if message.contains("coinbase") {
+            "premature-spend-of-coinbase"
+        } else {
+            "reject-other"
         }
     }
 
     impl IntoResponse for JsonRpcError {
         fn into_response(self) -> axum::http::Response<axum::body::Body> {
-            Response::error(self.rpc_error(), Value::Null).into_response()
+            // Unlike `Response::into_response`, which always answers 400 for any JSON-RPC
+            // error object, this uses the variant's real `http_code()` - so e.g. a `Node(..)`
+            // failure correctly surfaces as 503 at the HTTP layer rather than 400.
+            let status = axum::http::StatusCode::from_u16(self.http_code())
+                .unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+            let body = Response::error(self.rpc_error(), Value::Null);
+
+            axum::http::Response::builder()
+                .status(status)
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
         }
     }
 
@@ -429,6 +722,94 @@ pub mod jsonrpc_interface {
             JsonRpcError::Wallet(e.to_string())
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use serde_json::json;
+
+        use super::*;
+        use crate::json_rpc::auth::AuthToken;
+        use crate::json_rpc::auth::Permission;
+
+        /// `token` mirrors the server's configured auth (`None` means auth is disabled, so every
+        /// request passes through unchecked); `presented_secret` mirrors whatever a caller put
+        /// in its `Authorization: Bearer <secret>` header.
+        fn dispatch(
+            body: Value,
+            token: Option<&AuthToken>,
+            presented_secret: Option<&str>,
+        ) -> BatchOutput {
+            dispatch_incoming(
+                body,
+                |method| match token {
+                    None => Ok(()),
+                    Some(token) => match presented_secret {
+                        Some(secret) => token.authorize(secret, method),
+                        None => Err(JsonRpcError::Unauthorized),
+                    },
+                },
+                |request| Response::success(json!(request.method), request.id),
+            )
+        }
+
+        fn single_response(output: BatchOutput) -> Response {
+            match output {
+                BatchOutput::Single(response) => response,
+                other => panic!("expected a single response, got {other:?}"),
+            }
+        }
+
+        #[test]
+        fn dispatch_allows_any_request_when_auth_is_disabled() {
+            let body = json!({"jsonrpc": "2.0", "method": "sendrawtransaction", "id": 1});
+            let response = single_response(dispatch(body, None, None));
+
+            assert_eq!(response.result.unwrap(), json!("sendrawtransaction"));
+            assert!(response.error.is_none());
+        }
+
+        #[test]
+        fn dispatch_rejects_request_with_no_secret_presented() {
+            let token = AuthToken::generate(Permission::Sign);
+            let body = json!({"jsonrpc": "2.0", "method": "getblockchaininfo", "id": 1});
+            let response = single_response(dispatch(body, Some(&token), None));
+
+            assert!(response.result.is_none());
+            assert_eq!(response.error.unwrap().code, JsonRpcError::Unauthorized.rpc_error().code);
+        }
+
+        #[test]
+        fn dispatch_rejects_request_with_the_wrong_secret() {
+            let token = AuthToken::generate(Permission::Sign);
+            let body = json!({"jsonrpc": "2.0", "method": "getblockchaininfo", "id": 1});
+            let response = single_response(dispatch(body, Some(&token), Some("not-the-secret")));
+
+            assert!(response.result.is_none());
+            assert_eq!(response.error.unwrap().code, JsonRpcError::Unauthorized.rpc_error().code);
+        }
+
+        #[test]
+        fn dispatch_rejects_under_scoped_token_without_running_the_handler() {
+            let token = AuthToken::generate(Permission::Read);
+            let body = json!({"jsonrpc": "2.0", "method": "sendrawtransaction", "id": 1});
+            let response =
+                single_response(dispatch(body, Some(&token), Some(token.secret())));
+
+            assert!(response.result.is_none());
+            assert_eq!(response.error.unwrap().code, JsonRpcError::Forbidden.rpc_error().code);
+        }
+
+        #[test]
+        fn dispatch_runs_the_handler_for_a_correctly_authenticated_and_scoped_token() {
+            let token = AuthToken::generate(Permission::Read);
+            let body = json!({"jsonrpc": "2.0", "method": "getblockchaininfo", "id": 1});
+            let response =
+                single_response(dispatch(body, Some(&token), Some(token.secret())));
+
+            assert_eq!(response.result.unwrap(), json!("getblockchaininfo"));
+            assert!(response.error.is_none());
+        }
+    }
 }
 #[derive(Deserialize, Serialize)]
 pub struct GetBlockchainInfoRes {
@@ -0,0 +1,177 @@
+//! WebSocket pub/sub subscriptions, modeled on the notification convention Ethereum light
+//! clients popularized by layering a `ws-rs` transport on top of a `jsonrpc-core` handler: a
+//! client calls `subscribe` with a topic string and gets a [`SubscriptionId`] back, then the
+//! server pushes [`Notification`] objects over the same connection whenever a matching event
+//! fires, shaped as `{"method": "<channel>_subscription", "params": {"subscription": <id>,
+//! "result": <payload>}}` - no `id` field, so a client can tell a push apart from a reply to one
+//! of its own requests.
+//!
+//! Supported topics: newly validated blocks (header + height), new accumulator roots, and
+//! descriptor-matched transactions found during sync - see [`Channel`] for the full list,
+//! including channels from earlier work that predate this topic set.
+//!
+//! This module only holds the channel taxonomy and the per-connection sink bookkeeping; the
+//! WebSocket upgrade itself and the node's block/mempool event sources that would feed
+//! [`SubscriptionRegistry::publish`] live in the axum handler, outside the files checked out in
+//! this tree.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::res::jsonrpc_interface::JsonRpcError;
+
+/// Identifies a single `subscribe` call, handed back to the client so it can later `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SubscriptionId(pub u64);
+
+impl Display for SubscriptionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The event channels a client may subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// A new block was connected to the best chain. Pushes the block's header and height.
+    BlockConnected,
+
+    /// A block was disconnected from the best chain during a reorg.
+    BlockDisconnected,
+
+    /// The utreexo accumulator gained a new set of roots.
+    NewRoots,
+
+    /// A transaction matching one of the wallet's watched descriptors was found during sync.
+    DescriptorMatch,
+
+    /// A new transaction entered the mempool.
+    MempoolTx,
+
+    /// A new compact filter became available.
+    NewFilter,
+}
+
+impl Channel {
+    /// The topic name a client passes to `subscribe`/`unsubscribe`.
+    pub const fn topic(&self) -> &'static str {
+        match self {
+            Channel::BlockConnected => "blockconnected",
+            Channel::BlockDisconnected => "blockdisconnected",
+            Channel::NewRoots => "newroots",
+            Channel::DescriptorMatch => "descriptormatch",
+            Channel::MempoolTx => "mempooltx",
+            Channel::NewFilter => "newfilter",
+        }
+    }
+
+    /// The `method` name notifications on this channel are pushed under, e.g.
+    /// `blockconnected_subscription`.
+    pub fn method_name(&self) -> String {
+        format!("{}_subscription", self.topic())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = JsonRpcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blockconnected" => Ok(Channel::BlockConnected),
+            "blockdisconnected" => Ok(Channel::BlockDisconnected),
+            "newroots" => Ok(Channel::NewRoots),
+            "descriptormatch" => Ok(Channel::DescriptorMatch),
+            "mempooltx" => Ok(Channel::MempoolTx),
+            "newfilter" => Ok(Channel::NewFilter),
+            other => Err(JsonRpcError::UnknownChannel(other.to_string())),
+        }
+    }
+}
+
+/// The `params` object of a pushed [`Notification`], pairing the subscriber's own
+/// [`SubscriptionId`] with the event payload, mirroring the `eth_subscription`-style envelope.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionParams {
+    pub subscription: SubscriptionId,
+    pub result: Value,
+}
+
+/// A notification pushed to a subscriber, with no `id`, so a client can tell it apart from a
+/// reply to one of its own requests.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub method: String,
+    pub params: SubscriptionParams,
+}
+
+impl Notification {
+    pub fn new(id: SubscriptionId, channel: Channel, result: Value) -> Self {
+        Self {
+            method: channel.method_name(),
+            params: SubscriptionParams {
+                subscription: id,
+                result,
+            },
+        }
+    }
+}
+
+/// Tracks every live subscription for a single WebSocket connection and fans event-channel
+/// publishes out to whichever of them are listening.
+///
+/// One registry is expected per connection: subscription ids are only meaningful within the
+/// connection that created them, mirroring how `unsubscribe` only ever needs to look one up
+/// locally rather than across every client the server is serving.
+#[derive(Default)]
+pub struct SubscriptionRegistry {
+    next_id: AtomicU64,
+    subscriptions: HashMap<SubscriptionId, (Channel, UnboundedSender<Notification>)>,
+}
+
+impl SubscriptionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new subscription on `channel`, returning the id the client should hold on to
+    /// for a later `unsubscribe`. Every [`Notification`] published on `channel` from here on is
+    /// sent through `sink`.
+    pub fn subscribe(&mut self, channel: Channel, sink: UnboundedSender<Notification>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subscriptions.insert(id, (channel, sink));
+        id
+    }
+
+    /// Removes a subscription. Returns [`JsonRpcError::SubscriptionNotFound`] if `id` doesn't
+    /// refer to one currently held by this registry (already removed, or never issued).
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> Result<(), JsonRpcError> {
+        self.subscriptions
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(JsonRpcError::SubscriptionNotFound)
+    }
+
+    /// Pushes `result` as a [`Notification`] to every subscription registered on `channel`, each
+    /// one carrying that subscriber's own id.
+    ///
+    /// A sink whose receiving end has been dropped (the connection went away without an explicit
+    /// `unsubscribe`) is pruned on the next publish to its own channel instead of treated as an
+    /// error, since the
+    /// WebSocket disconnect itself is the authoritative signal there, not this call.
+    pub fn publish(&mut self, channel: Channel, result: Value) {
+        self.subscriptions.retain(|id, (sub_channel, sink)| {
+            *sub_channel != channel || {
+                let notification = Notification::new(*id, channel, result.clone());
+                sink.send(notification).is_ok()
+            }
+        });
+    }
+}
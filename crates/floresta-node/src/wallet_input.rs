@@ -2,16 +2,197 @@
 
 use std::str::FromStr;
 
+use bitcoin::bip32::Fingerprint;
+use bitcoin::bip32::Xpub;
+use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Address;
 use bitcoin::Network;
+use miniscript::descriptor::DescriptorType;
+use miniscript::descriptor::KeyMap;
+use miniscript::policy::semantic::Policy;
+use miniscript::policy::Liftable;
 use miniscript::Descriptor;
 use miniscript::DescriptorPublicKey;
 use tracing::error;
 
+use floresta_watch_only::descriptor::WalletError;
+
 use crate::error::FlorestadError;
 use crate::slip132::generate_descriptor_from_xpub;
 use crate::slip132::is_xpub_mainnet;
 
+/// The output script type a [`DescriptorTemplate`] should derive, mirroring BDK's
+/// `descriptor::template` module (`Bip44`/`Bip49`/`Bip84`/`Bip86`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    /// BIP44: `pkh(..)`, legacy P2PKH addresses.
+    Legacy,
+
+    /// BIP49: `sh(wpkh(..))`, SegWit nested in P2SH.
+    NestedSegwit,
+
+    /// BIP84: `wpkh(..)`, native SegWit (P2WPKH).
+    NativeSegwit,
+
+    /// BIP86: `tr(..)`, Taproot key-path spend.
+    Taproot,
+}
+
+impl ScriptType {
+    /// The BIP purpose field associated with this script type, used in the origin path
+    /// `m/purpose'/coin_type'/account'`.
+    const fn purpose(self) -> u32 {
+        match self {
+            ScriptType::Legacy => 44,
+            ScriptType::NestedSegwit => 49,
+            ScriptType::NativeSegwit => 84,
+            ScriptType::Taproot => 86,
+        }
+    }
+}
+
+/// A request to build a descriptor for a bare xpub, with an explicit key origin.
+///
+/// Unlike [`parse_xpubs`], which relies entirely on SLIP-132 version bytes, a
+/// [`DescriptorTemplate`] lets the caller supply the origin information (master fingerprint
+/// and account index) that SLIP-132 can't encode, and a script type that SLIP-132 can't
+/// represent at all (Taproot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorTemplate {
+    /// The bare xpub, without any SLIP-132 prefix semantics attached to it.
+    pub xpub: String,
+
+    /// The fingerprint of the master key this xpub was derived from.
+    pub master_fingerprint: String,
+
+    /// The account index used in the origin path.
+    pub account: u32,
+
+    /// The script type to derive, selecting the output descriptor shape.
+    pub script_type: ScriptType,
+}
+
+/// Builds a canonical `<0;1>/*` multipath descriptor string for a [`DescriptorTemplate`].
+///
+/// The resulting descriptor carries a proper `[fingerprint/purpose'/coin'/account']` key
+/// origin, which lets the descriptor be re-imported elsewhere (e.g. a hardware wallet) without
+/// losing provenance.
+fn build_template_descriptor(
+    template: &DescriptorTemplate,
+    network: Network,
+) -> Result<String, FlorestadError> {
+    let xpub = Xpub::from_str(&template.xpub)
+        .map_err(|e| FlorestadError::InvalidTemplateXpub(template.xpub.clone(), e.to_string()))?;
+
+    let fingerprint = Fingerprint::from_str(&template.master_fingerprint).map_err(|e| {
+        FlorestadError::InvalidTemplateXpub(template.master_fingerprint.clone(), e.to_string())
+    })?;
+
+    let coin_type = match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    };
+
+    let origin = format!(
+        "[{fingerprint}/{}'/{coin_type}'/{}']",
+        template.script_type.purpose(),
+        template.account
+    );
+
+    Ok(match template.script_type {
+        ScriptType::Legacy => format!("pkh({origin}{xpub}/<0;1>/*)"),
+        ScriptType::NestedSegwit => format!("sh(wpkh({origin}{xpub}/<0;1>/*))"),
+        ScriptType::NativeSegwit => format!("wpkh({origin}{xpub}/<0;1>/*)"),
+        ScriptType::Taproot => format!("tr({origin}{xpub}/<0;1>/*)"),
+    })
+}
+
+/// Builds descriptors for a list of [`DescriptorTemplate`]s, producing both the receiving and
+/// change descriptors for each template (split via [`parse_descriptors`]).
+fn parse_templates(
+    templates: &[DescriptorTemplate],
+    network: Network,
+) -> Result<Vec<Descriptor<DescriptorPublicKey>>, FlorestadError> {
+    let mut descriptors = Vec::new();
+    for template in templates {
+        let descriptor = build_template_descriptor(template, network)?;
+        descriptors.extend(parse_descriptors(&[descriptor])?);
+    }
+    Ok(descriptors)
+}
+
+/// Parses descriptors that may contain private key material (an xprv or a WIF-encoded key),
+/// returning both the watch-only descriptors and a [`KeyMap`] holding any private keys found.
+///
+/// The [`KeyMap`] lets the wallet sign for these descriptors locally instead of only watching
+/// them, while still storing and using the very same public descriptor everywhere else.
+fn parse_signing_descriptors(
+    descriptors: &[String],
+) -> Result<(Vec<Descriptor<DescriptorPublicKey>>, KeyMap), FlorestadError> {
+    let secp = Secp256k1::new();
+    let mut parsed = Vec::new();
+    let mut keymap = KeyMap::new();
+
+    for descriptor in descriptors {
+        let (descriptor, map) = Descriptor::<DescriptorPublicKey>::parse_descriptor(&secp, descriptor)?;
+        descriptor.sanity_check()?;
+        keymap.extend(map);
+        parsed.extend(descriptor.into_single_descriptors()?);
+    }
+
+    Ok((parsed, keymap))
+}
+
+/// A summary of a descriptor's spending policy, surfaced at setup time so the user can confirm
+/// what they're about to watch (or sign for) before the wallet commits to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpendingPolicy {
+    /// The descriptor's output script shape (e.g. `Wsh`, `ShWpkh`, `Tr`).
+    pub(crate) descriptor_type: DescriptorType,
+
+    /// The number of signatures required to satisfy the top-level threshold, if the policy is
+    /// a simple `k`-of-`n` (or single-key) policy. `None` for anything more complex, such as
+    /// miniscript policies mixing timelocks or nested thresholds.
+    pub(crate) required_signatures: Option<usize>,
+
+    /// The total number of keys participating in the descriptor.
+    pub(crate) total_keys: usize,
+}
+
+/// Counts the number of key leaves present anywhere in a policy tree.
+fn count_keys(policy: &Policy<DescriptorPublicKey>) -> usize {
+    match policy {
+        Policy::Key(_) => 1,
+        Policy::Threshold(_, subs) => subs.iter().map(count_keys).sum(),
+        _ => 0,
+    }
+}
+
+/// Lifts a descriptor's policy and summarizes it into a [`SpendingPolicy`].
+fn describe_spending_policy(
+    descriptor: &Descriptor<DescriptorPublicKey>,
+) -> Result<SpendingPolicy, FlorestadError> {
+    let descriptor_type = descriptor.desc_type();
+    let policy = descriptor.lift().map_err(|e| {
+        FlorestadError::CouldNotSetupWallet(WalletError::UnsupportedDescriptorType(e.to_string()))
+    })?;
+
+    let total_keys = count_keys(&policy);
+    let required_signatures = match &policy {
+        Policy::Key(_) => Some(1),
+        Policy::Threshold(k, subs) if subs.iter().all(|sub| matches!(sub, Policy::Key(_))) => {
+            Some(*k)
+        }
+        _ => None,
+    };
+
+    Ok(SpendingPolicy {
+        descriptor_type,
+        required_signatures,
+        total_keys,
+    })
+}
+
 fn parse_xpubs(
     xpubs: &[String],
     network: Network,
@@ -39,6 +220,14 @@ fn parse_xpubs(
 pub(crate) struct InitialWalletSetup {
     pub(crate) descriptors: Vec<Descriptor<DescriptorPublicKey>>,
     pub(crate) addresses: Vec<Address>,
+
+    /// Private key material for any descriptor imported with an xprv or WIF key, allowing the
+    /// wallet to sign for those descriptors instead of only watching them.
+    pub(crate) keymap: KeyMap,
+
+    /// A spending-policy summary for each entry in `descriptors`, in the same order, so the
+    /// user can confirm what they're about to watch (or sign for) at setup time.
+    pub(crate) policies: Vec<SpendingPolicy>,
 }
 
 impl InitialWalletSetup {
@@ -48,11 +237,42 @@ impl InitialWalletSetup {
         addresses: &[String],
         network: Network,
         addresses_per_descriptor: u32,
+    ) -> Result<Self, FlorestadError> {
+        Self::build_with_templates(
+            xpubs,
+            initial_descriptors,
+            &[],
+            addresses,
+            &[],
+            network,
+            addresses_per_descriptor,
+        )
+    }
+
+    /// Same as [`Self::build`], additionally accepting bare-xpub [`DescriptorTemplate`]s
+    /// (origin-aware BIP44/49/84/86 derivation) and `signing_descriptors` (descriptors that
+    /// carry private key material, such as an xprv or a WIF key).
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_with_templates(
+        xpubs: &[String],
+        initial_descriptors: &[String],
+        signing_descriptors: &[String],
+        addresses: &[String],
+        templates: &[DescriptorTemplate],
+        network: Network,
+        addresses_per_descriptor: u32,
     ) -> Result<Self, FlorestadError> {
         let mut descriptors = parse_xpubs(xpubs, network)?;
         descriptors.extend(parse_descriptors(initial_descriptors)?);
+        descriptors.extend(parse_templates(templates, network)?);
+        let (signing_descriptors, keymap) = parse_signing_descriptors(signing_descriptors)?;
+        descriptors.extend(signing_descriptors);
         descriptors.sort();
         descriptors.dedup();
+        let policies = descriptors
+            .iter()
+            .map(describe_spending_policy)
+            .collect::<Result<Vec<_>, _>>()?;
         let mut addresses = addresses
             .iter()
             .flat_map(|address| match Address::from_str(address) {
@@ -77,6 +297,8 @@ impl InitialWalletSetup {
         Ok(Self {
             descriptors,
             addresses,
+            keymap,
+            policies,
         })
     }
 }
@@ -317,6 +539,103 @@ pub mod test {
         assert_eq!(w1_descriptor, w1_all);
     }
 
+    #[test]
+    fn test_build_template_descriptor() {
+        let template = DescriptorTemplate {
+            xpub: "xpub6CFy3kRXorC3NMTt8qrsY9ucUfxVLXyFQ49JSLm3iEG5gfAmWewYFzjNYFgRiCjoB9WWEuJQiyYGCdZvUTwPEUPL9pPabT8bkbiD9Po47XG".to_owned(),
+            master_fingerprint: "a5b13c0e".to_owned(),
+            account: 0,
+            script_type: ScriptType::NativeSegwit,
+        };
+
+        let descriptor = build_template_descriptor(&template, Network::Bitcoin).unwrap();
+        assert_eq!(
+            descriptor,
+            "wpkh([a5b13c0e/84'/0'/0']xpub6CFy3kRXorC3NMTt8qrsY9ucUfxVLXyFQ49JSLm3iEG5gfAmWewYFzjNYFgRiCjoB9WWEuJQiyYGCdZvUTwPEUPL9pPabT8bkbiD9Po47XG/<0;1>/*)"
+        );
+    }
+
+    #[test]
+    fn test_initial_wallet_build_with_template() {
+        let addresses_per_descriptor = 1;
+        let network = Network::Bitcoin;
+        let template = DescriptorTemplate {
+            xpub: "xpub6CFy3kRXorC3NMTt8qrsY9ucUfxVLXyFQ49JSLm3iEG5gfAmWewYFzjNYFgRiCjoB9WWEuJQiyYGCdZvUTwPEUPL9pPabT8bkbiD9Po47XG".to_owned(),
+            master_fingerprint: "a5b13c0e".to_owned(),
+            account: 0,
+            script_type: ScriptType::NativeSegwit,
+        };
+
+        let from_template = InitialWalletSetup::build_with_templates(
+            &[],
+            &[],
+            &[],
+            &[],
+            &[template],
+            network,
+            addresses_per_descriptor,
+        )
+        .unwrap();
+
+        let from_descriptor = InitialWalletSetup::build(&[], &[
+            "wpkh([a5b13c0e/84'/0'/0']xpub6CFy3kRXorC3NMTt8qrsY9ucUfxVLXyFQ49JSLm3iEG5gfAmWewYFzjNYFgRiCjoB9WWEuJQiyYGCdZvUTwPEUPL9pPabT8bkbiD9Po47XG/<0;1>/*)".to_owned()
+        ], &[], network, addresses_per_descriptor).unwrap();
+
+        assert_eq!(from_template, from_descriptor);
+    }
+
+    #[test]
+    fn test_parse_signing_descriptors() {
+        // A single-key wpkh descriptor carrying a WIF private key.
+        let (descriptors, keymap) = parse_signing_descriptors(&[
+            "wpkh(KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn)".to_owned(),
+        ])
+        .unwrap();
+
+        assert_eq!(descriptors.len(), 1);
+        assert_eq!(keymap.len(), 1);
+    }
+
+    #[test]
+    fn test_initial_wallet_build_with_signing_descriptor() {
+        let addresses_per_descriptor = 1;
+        let network = Network::Bitcoin;
+
+        let wallet = InitialWalletSetup::build_with_templates(
+            &[],
+            &[],
+            &["wpkh(KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn)".to_owned()],
+            &[],
+            &[],
+            network,
+            addresses_per_descriptor,
+        )
+        .unwrap();
+
+        assert_eq!(wallet.descriptors.len(), 1);
+        assert_eq!(wallet.keymap.len(), 1);
+    }
+
+    #[test]
+    fn test_describe_spending_policy_singlesig() {
+        let descriptor = Descriptor::<DescriptorPublicKey>::from_str(
+            "wpkh(xpub6CFy3kRXorC3NMTt8qrsY9ucUfxVLXyFQ49JSLm3iEG5gfAmWewYFzjNYFgRiCjoB9WWEuJQiyYGCdZvUTwPEUPL9pPabT8bkbiD9Po47XG/0/*)"
+        ).unwrap();
+        let policy = describe_spending_policy(&descriptor).unwrap();
+        assert_eq!(policy.required_signatures, Some(1));
+        assert_eq!(policy.total_keys, 1);
+    }
+
+    #[test]
+    fn test_describe_spending_policy_multisig() {
+        let descriptor = Descriptor::<DescriptorPublicKey>::from_str(
+            "wsh(sortedmulti(1,[6f826a6a/48'/0'/0'/2']xpub6DsY48BAsvEMTRPbeSTu9jZXqEsTKr5T86WbRbXHp2gEVCNR3hALnMorFawVwnnHMMfjbyY8We9B4beh1fxqhcv6kgSeLgQxeXDqv3DaW7m/0/*,[a5b13c0e/48'/0'/0'/2']xpub6Eqj1Hj3RezebC6cKiYYN2sAc1Wu33BWoaafnNgAbQwDkJdy7aXCYCmaMzb8rCpmh919UsehyV5Ywjo62hG4R2G2PGv4uqEDTUhYQw26BDJ/0/*))"
+        ).unwrap();
+        let policy = describe_spending_policy(&descriptor).unwrap();
+        assert_eq!(policy.required_signatures, Some(1));
+        assert_eq!(policy.total_keys, 2);
+    }
+
     #[test]
     fn test_initial_wallet_build_multisig_testnet() {
         use pretty_assertions::assert_eq;
@@ -0,0 +1,336 @@
+//! A conformance harness that replays a fixed set of RPC calls against both a running Floresta
+//! node and a reference Bitcoin Core node over the same block range, diffing the raw JSON
+//! results field-by-field.
+//!
+//! This is meant to catch regressions where a typed response (`RawTxJson`, `GetBlockRes`,
+//! `ScriptPubKeyJson`, ...) drifts from Core's schema, which today is only ever checked by hand.
+//! The harness works against raw [`Value`]s rather than those typed responses so it can flag a
+//! schema drift even when it doesn't yet have a Rust type to deserialize into.
+//!
+//! NOTE: this checkout has no crate-root `lib.rs` for `floresta-rpc` to declare `pub mod
+//! compare;` in, so this module isn't wired up anywhere yet. It only depends on the `jsonrpc`
+//! crate and `serde_json`, not on this crate's own (also absent from disk) `rpc_types` module.
+
+use std::fmt;
+
+use jsonrpc::Client;
+use serde_json::Value;
+
+/// One RPC call to replay against both nodes, with its parameters built from the height being
+/// checked (e.g. `getblockhash` takes the height directly, `getblock` takes the hash looked up
+/// at a prior height).
+pub struct Call {
+    pub method: String,
+    params: Box<dyn Fn(u32) -> Vec<Value> + Send + Sync>,
+}
+
+impl Call {
+    pub fn new(
+        method: impl Into<String>,
+        params: impl Fn(u32) -> Vec<Value> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            params: Box::new(params),
+        }
+    }
+}
+
+/// Selects which methods the harness is allowed to replay.
+///
+/// An entry prefixed with `!` denies that method; every other entry allows it. An empty filter
+/// allows every method. Deny entries always win, so `["!getblock"]` means "everything except
+/// `getblock`" without having to spell out every other method name.
+#[derive(Debug, Clone, Default)]
+pub struct MethodFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl MethodFilter {
+    pub fn parse(entries: &[impl AsRef<str>]) -> Self {
+        let mut allow = Vec::new();
+        let mut deny = Vec::new();
+
+        for entry in entries {
+            let entry = entry.as_ref();
+            match entry.strip_prefix('!') {
+                Some(method) => deny.push(method.to_string()),
+                None => allow.push(entry.to_string()),
+            }
+        }
+
+        Self { allow, deny }
+    }
+
+    fn permits(&self, method: &str) -> bool {
+        if self.deny.iter().any(|m| m == method) {
+            return false;
+        }
+
+        self.allow.is_empty() || self.allow.iter().any(|m| m == method)
+    }
+}
+
+/// Fields that are expected to differ between Floresta and Core because Floresta's response
+/// carries information Core's schema simply doesn't have (utreexo roots, leaf counts, ...),
+/// keyed by method name so e.g. `getblockchaininfo`'s `leaf_count`/`root_hashes` aren't flagged
+/// as a regression on every single run.
+#[derive(Debug, Clone, Default)]
+pub struct KnownDivergences {
+    ignored_fields: Vec<(String, String)>,
+}
+
+impl KnownDivergences {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ignore_field(mut self, method: impl Into<String>, field: impl Into<String>) -> Self {
+        self.ignored_fields.push((method.into(), field.into()));
+        self
+    }
+
+    /// The Floresta-only fields this harness ignores out of the box.
+    pub fn defaults() -> Self {
+        Self::new()
+            .ignore_field("getblockchaininfo", "leaf_count")
+            .ignore_field("getblockchaininfo", "root_hashes")
+    }
+
+    fn is_ignored(&self, method: &str, field: &str) -> bool {
+        self.ignored_fields
+            .iter()
+            .any(|(m, f)| m == method && f == field)
+    }
+}
+
+/// One field-level mismatch between the two nodes' responses to the same call. `path` is a
+/// dotted/indexed path into the response, e.g. `tx[0].vout[1].value`.
+#[derive(Debug, Clone)]
+pub struct FieldDiff {
+    pub path: String,
+    pub floresta: Value,
+    pub core: Value,
+}
+
+/// The outcome of replaying a single [`Call`] against both nodes.
+#[derive(Debug, Clone)]
+pub enum CallOutcome {
+    /// Both nodes returned results, and they matched once known divergences were ignored, or
+    /// both nodes returned an error.
+    Match,
+
+    /// Both nodes returned results, but they disagree on one or more fields.
+    Mismatch(Vec<FieldDiff>),
+
+    /// Exactly one of the two nodes returned an error for this call.
+    ErrorMismatch {
+        floresta: Option<String>,
+        core: Option<String>,
+    },
+}
+
+/// The result of replaying one [`Call`] at one height.
+#[derive(Debug, Clone)]
+pub struct CallReport {
+    pub height: u32,
+    pub method: String,
+    pub outcome: CallOutcome,
+}
+
+/// The full result of one harness run: every call that was actually replayed, in order.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub calls: Vec<CallReport>,
+}
+
+impl ConformanceReport {
+    /// The subset of replayed calls that didn't match.
+    pub fn failures(&self) -> impl Iterator<Item = &CallReport> {
+        self.calls
+            .iter()
+            .filter(|call| !matches!(call.outcome, CallOutcome::Match))
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.failures().next().is_none()
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for call in self.failures() {
+            match &call.outcome {
+                CallOutcome::Mismatch(diffs) => {
+                    writeln!(
+                        f,
+                        "height {} {}: {} field(s) differ",
+                        call.height,
+                        call.method,
+                        diffs.len()
+                    )?;
+                    for diff in diffs {
+                        writeln!(
+                            f,
+                            "  {}: floresta={} core={}",
+                            diff.path, diff.floresta, diff.core
+                        )?;
+                    }
+                }
+                CallOutcome::ErrorMismatch { floresta, core } => {
+                    writeln!(
+                        f,
+                        "height {} {}: error mismatch (floresta={floresta:?}, core={core:?})",
+                        call.height, call.method
+                    )?;
+                }
+                CallOutcome::Match => unreachable!("filtered out by `failures`"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replays a fixed set of [`Call`]s against a Floresta node and a reference Bitcoin Core node
+/// over the same block range, diffing their JSON responses field-by-field.
+pub struct ConformanceHarness {
+    floresta: Client,
+    core: Client,
+    filter: MethodFilter,
+    divergences: KnownDivergences,
+}
+
+impl ConformanceHarness {
+    pub fn new(floresta: Client, core: Client) -> Self {
+        Self {
+            floresta,
+            core,
+            filter: MethodFilter::default(),
+            divergences: KnownDivergences::defaults(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: MethodFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_divergences(mut self, divergences: KnownDivergences) -> Self {
+        self.divergences = divergences;
+        self
+    }
+
+    /// Replays every call in `calls` that the [`MethodFilter`] permits at every height in
+    /// `heights`, returning a report with one entry per call that was actually replayed.
+    pub fn run(&self, heights: impl IntoIterator<Item = u32>, calls: &[Call]) -> ConformanceReport {
+        let mut report = ConformanceReport::default();
+
+        for height in heights {
+            for call in calls {
+                if !self.filter.permits(&call.method) {
+                    continue;
+                }
+
+                let outcome = self.replay_one(height, call);
+                report.calls.push(CallReport {
+                    height,
+                    method: call.method.clone(),
+                    outcome,
+                });
+            }
+        }
+
+        report
+    }
+
+    fn replay_one(&self, height: u32, call: &Call) -> CallOutcome {
+        let args = jsonrpc::arg((call.params)(height));
+
+        let floresta_result = self.floresta.call::<Value>(&call.method, Some(&args));
+        let core_result = self.core.call::<Value>(&call.method, Some(&args));
+
+        match (floresta_result, core_result) {
+            (Ok(floresta), Ok(core)) => {
+                let mut diffs = Vec::new();
+                diff_json(&call.method, "", &floresta, &core, &self.divergences, &mut diffs);
+
+                if diffs.is_empty() {
+                    CallOutcome::Match
+                } else {
+                    CallOutcome::Mismatch(diffs)
+                }
+            }
+            (Ok(_), Err(core_err)) => CallOutcome::ErrorMismatch {
+                floresta: None,
+                core: Some(core_err.to_string()),
+            },
+            (Err(floresta_err), Ok(_)) => CallOutcome::ErrorMismatch {
+                floresta: Some(floresta_err.to_string()),
+                core: None,
+            },
+            // Both sides rejected the call the same way (e.g. an unsupported method) — not a
+            // conformance regression worth reporting.
+            (Err(_), Err(_)) => CallOutcome::Match,
+        }
+    }
+}
+
+fn diff_json(
+    method: &str,
+    path: &str,
+    floresta: &Value,
+    core: &Value,
+    divergences: &KnownDivergences,
+    diffs: &mut Vec<FieldDiff>,
+) {
+    match (floresta, core) {
+        (Value::Object(f_map), Value::Object(c_map)) => {
+            let mut keys: Vec<&String> = f_map.keys().chain(c_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                if divergences.is_ignored(method, key) {
+                    continue;
+                }
+
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+
+                match (f_map.get(key), c_map.get(key)) {
+                    (Some(f), Some(c)) => diff_json(method, &child_path, f, c, divergences, diffs),
+                    (f, c) => diffs.push(FieldDiff {
+                        path: child_path,
+                        floresta: f.cloned().unwrap_or(Value::Null),
+                        core: c.cloned().unwrap_or(Value::Null),
+                    }),
+                }
+            }
+        }
+        (Value::Array(f_items), Value::Array(c_items)) => {
+            if f_items.len() != c_items.len() {
+                diffs.push(FieldDiff {
+                    path: path.to_string(),
+                    floresta: floresta.clone(),
+                    core: core.clone(),
+                });
+                return;
+            }
+
+            for (i, (f, c)) in f_items.iter().zip(c_items.iter()).enumerate() {
+                diff_json(method, &format!("{path}[{i}]"), f, c, divergences, diffs);
+            }
+        }
+        _ if floresta != core => diffs.push(FieldDiff {
+            path: path.to_string(),
+            floresta: floresta.clone(),
+            core: core.clone(),
+        }),
+        _ => {}
+    }
+}
@@ -180,4 +180,45 @@ impl FlorestaRPC for Client {
     fn ping(&self) -> Result<()> {
         Ok(self.call("ping", None)?)
     }
+
+    fn subscribe(&self, topic: String) -> Result<u64> {
+        let args = arg([Value::String(topic)]);
+        Ok(self.call("subscribe", Some(&args))?)
+    }
+
+    fn unsubscribe(&self, subscription: u64) -> Result<bool> {
+        let args = arg([Value::Number(Number::from(subscription))]);
+        Ok(self.call("unsubscribe", Some(&args))?)
+    }
+
+    fn scan_blocks(
+        &self,
+        descriptors: Vec<String>,
+        start_height: u32,
+        stop_height: u32,
+    ) -> Result<Vec<BlockHash>> {
+        let args = arg([
+            serde_json::to_value(descriptors)
+                .expect("Unreachable, Vec<String> can be parsed into a json value"),
+            Value::Number(Number::from(start_height)),
+            Value::Number(Number::from(stop_height)),
+        ]);
+
+        Ok(self.call("scanblocks", Some(&args))?)
+    }
+
+    fn get_block_stats(
+        &self,
+        hash_or_height: Value,
+        stats: Option<Vec<String>>,
+    ) -> Result<GetBlockStatsRes> {
+        let stats = stats
+            .map(|stats| {
+                Value::Array(stats.into_iter().map(Value::String).collect())
+            })
+            .unwrap_or(Value::Null);
+
+        let args = arg([hash_or_height, stats]);
+        Ok(self.call("getblockstats", Some(&args))?)
+    }
 }
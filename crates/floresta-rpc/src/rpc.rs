@@ -134,4 +134,44 @@ pub trait FlorestaRPC {
     fn list_descriptors(&self) -> Result<Vec<String>>;
     /// Sends a ping to all peers, checking if they are still alive
     fn ping(&self) -> Result<()>;
+    /// Subscribes to a WebSocket notification topic
+    ///
+    /// `topic` is one of the channel names the node's pub/sub server recognizes, e.g.
+    /// `"blockconnected"` or `"newroots"`. Returns the subscription id the server will tag every
+    /// pushed notification with, which you pass back to [`FlorestaRPC::unsubscribe`] to stop
+    /// receiving them.
+    fn subscribe(&self, topic: String) -> Result<u64>;
+    /// Cancels a subscription previously returned by [`FlorestaRPC::subscribe`]
+    ///
+    /// Returns `true` once the subscription is removed; an id that isn't currently registered is
+    /// answered with an error.
+    fn unsubscribe(&self, subscription: u64) -> Result<bool>;
+    /// Scans a height range for blocks whose BIP158 filter matches any of `descriptors`
+    ///
+    /// This derives the candidate output scripts from each descriptor, then for every height in
+    /// `[start_height, stop_height]` loads that block's compact filter and checks it against the
+    /// derived scripts, the same matching `get_block_filter` callers would otherwise have to do
+    /// themselves one block at a time. Returns the hashes of the blocks that matched, so the
+    /// caller can fetch just those blocks instead of the whole range. Requires the node to have
+    /// been started with `blockfilters=1`.
+    fn scan_blocks(
+        &self,
+        descriptors: Vec<String>,
+        start_height: u32,
+        stop_height: u32,
+    ) -> Result<Vec<BlockHash>>;
+    /// Returns aggregate statistics for a fully-downloaded block
+    ///
+    /// `hash_or_height` may be either a block hash hex string or a height, the same flexibility
+    /// Bitcoin Core's `getblockstats` offers. `stats` selects which of the available fields to
+    /// compute and return; when `None`, defaults to every fee percentile in
+    /// `{10, 25, 50, 75, 90}` plus the rest of [`GetBlockStatsRes`]'s fields. Fee-derived fields
+    /// require every input's previous output to be resolvable through the same UTXO/accumulator
+    /// path `get_tx_out` uses, and fail with an error rather than reporting a zero fee if one
+    /// can't be (e.g. it was pruned away).
+    fn get_block_stats(
+        &self,
+        hash_or_height: Value,
+        stats: Option<Vec<String>>,
+    ) -> Result<GetBlockStatsRes>;
 }
@@ -1,6 +1,8 @@
 use core::fmt;
 use core::str::FromStr;
 
+use bitcoin::bip32::DerivationPath;
+use bitcoin::Address;
 use bitcoin::Network;
 use bitcoin::ScriptBuf;
 use floresta_common::impl_error_from;
@@ -9,9 +11,13 @@ use miniscript::Descriptor;
 use miniscript::DescriptorPublicKey;
 use miniscript::Error as MiniscriptError;
 
+mod checksum;
 mod slip132;
 
+pub use crate::descriptor::checksum::descriptor_checksum;
+pub use crate::descriptor::checksum::verify_checksum;
 use crate::descriptor::slip132::generate_descriptor_from_xpub;
+use crate::descriptor::slip132::generate_descriptor_from_xpub_and_origin;
 use crate::descriptor::slip132::is_xpub_mainnet;
 use crate::descriptor::slip132::Error as Slip132Error;
 
@@ -27,6 +33,37 @@ pub enum DescriptorError {
     MiniscriptError(MiniscriptError),
 
     DeriveDescriptorError(ConversionError),
+
+    /// The requested multipath branch index isn't one of the branches this descriptor expands to.
+    MultipathBranchNotFound {
+        /// The branch index that was requested.
+        branch: usize,
+        /// The number of branches the descriptor actually expands to.
+        branches: usize,
+    },
+
+    /// A descriptor's trailing `#...` checksum doesn't match the one computed from its body.
+    ChecksumMismatch {
+        /// The checksum computed from the descriptor's own body.
+        expected: String,
+        /// The checksum actually found in the descriptor string.
+        found: String,
+    },
+
+    /// A descriptor contains a character outside the BIP-380 checksum input charset.
+    InvalidChecksumCharacter(char),
+
+    /// The provided string isn't a valid base58 or bech32/bech32m address.
+    InvalidAddress(String),
+
+    /// The address doesn't belong to the expected network.
+    AddressNetworkMismatch(String),
+
+    /// The address's script doesn't match any of the standard script types this crate tracks.
+    UnsupportedScriptType(String),
+
+    /// A derived script isn't a standard script and can't be encoded as an address.
+    ScriptNotAddressable(String),
 }
 
 impl_error_from!(DescriptorError, Slip132Error, XpubParseError);
@@ -48,10 +85,109 @@ impl fmt::Display for DescriptorError {
             DescriptorError::DeriveDescriptorError(err) => {
                 write!(f, "Derive descriptor error: {}", err)
             }
+            DescriptorError::MultipathBranchNotFound { branch, branches } => write!(
+                f,
+                "Multipath branch {} not found: descriptor only has {} branch(es)",
+                branch, branches
+            ),
+            DescriptorError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Descriptor checksum mismatch: expected {}, found {}",
+                expected, found
+            ),
+            DescriptorError::InvalidChecksumCharacter(c) => write!(
+                f,
+                "Character '{}' is not part of the descriptor checksum charset",
+                c
+            ),
+            DescriptorError::InvalidAddress(address) => {
+                write!(f, "Invalid address: {}", address)
+            }
+            DescriptorError::AddressNetworkMismatch(address) => write!(
+                f,
+                "The inserted address does not operate in this network: {}",
+                address
+            ),
+            DescriptorError::UnsupportedScriptType(address) => write!(
+                f,
+                "Address {} doesn't resolve to a standard script type",
+                address
+            ),
+            DescriptorError::ScriptNotAddressable(script) => write!(
+                f,
+                "Script {} isn't a standard script and can't be encoded as an address",
+                script
+            ),
+        }
+    }
+}
+
+/// Wallet-level errors surfaced when pushing a descriptor into the watch-only wallet or checking
+/// it can fund a requested view, as distinct from a bare parse failure in [`DescriptorError`].
+/// Mirrors the shape of the domain errors BDK exposes on its own wallet (`InsufficientFunds`,
+/// `OutputBelowDustLimit`, `NoRecipients`, `ScriptDoesntHaveAddressForm`), so coin/UTXO-level
+/// problems become machine-inspectable instead of collapsing into a string.
+#[derive(Debug)]
+pub enum WalletError {
+    /// The descriptor string couldn't even be parsed.
+    InvalidDescriptor(DescriptorError),
+
+    /// This exact descriptor is already tracked by the wallet.
+    DuplicateDescriptor(String),
+
+    /// The descriptor's script type isn't one the wallet knows how to track.
+    UnsupportedDescriptorType(String),
+
+    /// Not enough confirmed balance to satisfy the requested view or spend.
+    InsufficientFunds {
+        /// Amount needed, in satoshis.
+        needed: u64,
+        /// Amount actually available, in satoshis.
+        available: u64,
+    },
+
+    /// A requested output is below the dust limit, so it wouldn't be economical to create.
+    OutputBelowDustLimit(usize),
+
+    /// No recipients were provided for a transaction that requires at least one.
+    NoRecipients,
+
+    /// The script doesn't correspond to any address form we can derive.
+    ScriptDoesntHaveAddressForm,
+}
+
+impl_error_from!(WalletError, DescriptorError, InvalidDescriptor);
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::InvalidDescriptor(err) => write!(f, "Invalid descriptor: {}", err),
+            WalletError::DuplicateDescriptor(descriptor) => {
+                write!(f, "Descriptor already tracked by this wallet: {}", descriptor)
+            }
+            WalletError::UnsupportedDescriptorType(descriptor) => write!(
+                f,
+                "Unsupported descriptor type, cannot track script for: {}",
+                descriptor
+            ),
+            WalletError::InsufficientFunds { needed, available } => write!(
+                f,
+                "Insufficient funds: needed {} sats, only {} available",
+                needed, available
+            ),
+            WalletError::OutputBelowDustLimit(index) => {
+                write!(f, "Output {} is below the dust limit", index)
+            }
+            WalletError::NoRecipients => write!(f, "Transaction has no recipients"),
+            WalletError::ScriptDoesntHaveAddressForm => {
+                write!(f, "Script doesn't have an address form")
+            }
         }
     }
 }
 
+impl std::error::Error for WalletError {}
+
 pub fn parse_xpub(xpub: &str, network: Network) -> Result<Vec<String>, DescriptorError> {
     // Check if the xpub network matches the expected network
     let is_mainnet = is_xpub_mainnet(xpub)?;
@@ -69,6 +205,29 @@ pub fn parse_xpub(xpub: &str, network: Network) -> Result<Vec<String>, Descripto
     ])
 }
 
+/// Like [`parse_xpub`], but selects the script type from `origin_path`'s BIP-44-style purpose
+/// field (see [`generate_descriptor_from_xpub_and_origin`]) instead of `xpub`'s own SLIP-132
+/// prefix. Taproot accounts (BIP-86, `m/86'/...`) can only be reached this way, since SLIP-132
+/// never defined dedicated version bytes for Taproot.
+pub fn parse_xpub_with_origin(
+    xpub: &str,
+    origin_path: &DerivationPath,
+    network: Network,
+) -> Result<Vec<String>, DescriptorError> {
+    let is_mainnet = is_xpub_mainnet(xpub)?;
+    if (is_mainnet && network != Network::Bitcoin) || (!is_mainnet && network == Network::Bitcoin) {
+        return Err(DescriptorError::XpubNetworkMismatch(xpub.to_string()));
+    }
+
+    let main_desc = generate_descriptor_from_xpub_and_origin(xpub, origin_path, false)?;
+    let change_desc = generate_descriptor_from_xpub_and_origin(xpub, origin_path, true)?;
+
+    Ok(vec![
+        Descriptor::<DescriptorPublicKey>::from_str(&main_desc)?.to_string(),
+        Descriptor::<DescriptorPublicKey>::from_str(&change_desc)?.to_string(),
+    ])
+}
+
 /// Takes an array of descriptors as `String`, performs sanity checks on each one
 /// and returns list of parsed descriptors.
 pub fn parse_descriptors(
@@ -96,6 +255,78 @@ pub fn parse_and_split_descriptor(
     Ok(descriptors)
 }
 
+/// Normalizes a descriptor to a stable canonical form: parses it, runs `sanity_check`, folds
+/// every hardened marker (`'` or `h`) onto `h`, lowercases hex key-origin fingerprints, and
+/// re-emits the result with a freshly computed checksum. Two descriptors that are textually
+/// different but semantically identical (e.g. `48h/0h/0h/2h` vs `48'/0'/0'/2'`) canonicalize to
+/// the same string, which is what deduplicating imported wallets by descriptor needs.
+pub fn canonicalize_descriptor(descriptor: &str) -> Result<String, DescriptorError> {
+    let parsed = Descriptor::<DescriptorPublicKey>::from_str(descriptor)?;
+    parsed.sanity_check()?;
+
+    let body = parsed.to_string();
+    let body = body.split('#').next().unwrap_or(&body);
+    let canonical_body = canonicalize_descriptor_body(body);
+
+    let checksum = descriptor_checksum(&canonical_body)?;
+    Ok(format!("{canonical_body}#{checksum}"))
+}
+
+/// Folds a descriptor's hardened markers onto `h` and lowercases the hex fingerprint inside each
+/// `[origin]` prefix, leaving everything else (base58/bech32 keys, derivation indices) untouched.
+fn canonicalize_descriptor_body(descriptor: &str) -> String {
+    let mut result = String::with_capacity(descriptor.len());
+    let mut in_origin = false;
+
+    for c in descriptor.chars() {
+        match c {
+            '[' => {
+                in_origin = true;
+                result.push(c);
+            }
+            ']' => {
+                in_origin = false;
+                result.push(c);
+            }
+            '\'' => result.push('h'),
+            c if in_origin && c.is_ascii_hexdigit() => result.push(c.to_ascii_lowercase()),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Like [`parse_and_split_descriptor`], but pairs each expanded descriptor with the multipath
+/// branch index it came from (e.g. `<0;1;2>` expands to branches `0`, `1` and `2`, in that
+/// order), instead of leaving callers to assume a fixed two-branch receive/change split.
+pub fn parse_and_split_descriptor_with_branches(
+    descriptor: &str,
+) -> Result<Vec<(usize, Descriptor<DescriptorPublicKey>)>, DescriptorError> {
+    let descriptors = parse_and_split_descriptor(descriptor)?;
+    Ok(descriptors.into_iter().enumerate().collect())
+}
+
+/// Derives addresses from a single multipath branch of a descriptor string.
+/// Splits the descriptor into its single-path branches and derives addresses from the one
+/// selected by `branch` (e.g. `branch: 2` for the third keychain in a `<0;1;2>` descriptor).
+pub fn derive_addresses_from_multipath(
+    descriptor: &str,
+    branch: usize,
+    index: u32,
+    quantity: u32,
+) -> Result<Vec<ScriptBuf>, DescriptorError> {
+    let descriptors = parse_and_split_descriptor(descriptor)?;
+    let branches = descriptors.len();
+
+    let desc = descriptors
+        .into_iter()
+        .nth(branch)
+        .ok_or(DescriptorError::MultipathBranchNotFound { branch, branches })?;
+
+    derive_addresses_from_parsed_descriptor(desc, index, quantity)
+}
+
 /// Derives addresses from a list of descriptors.
 /// Parses each descriptor, validates it, and derives the specified number of addresses
 /// starting from the given index.
@@ -131,6 +362,37 @@ pub fn derive_addresses_from_descriptor(
     Ok(addresses)
 }
 
+/// Derives scripts from a descriptor string and consensus-hex-encodes each one, so RPC consumers
+/// can request scripts as hex without re-deriving them, instead of relying on [`ScriptBuf`]'s
+/// `Display` impl, which renders disassembled opcodes rather than the raw bytes.
+pub fn derive_scripts_hex(
+    descriptor: &str,
+    index: u32,
+    quantity: u32,
+) -> Result<Vec<String>, DescriptorError> {
+    let scripts = derive_addresses_from_descriptor(descriptor, index, quantity)?;
+    Ok(scripts.iter().map(|script| format!("{:x}", script)).collect())
+}
+
+/// Derives scripts from a descriptor string and encodes each one as the [`Address`] it pays to,
+/// so RPC consumers can display ready-to-use addresses without re-deriving scripts themselves.
+pub fn derive_addresses(
+    descriptor: &str,
+    index: u32,
+    quantity: u32,
+    network: Network,
+) -> Result<Vec<String>, DescriptorError> {
+    let scripts = derive_addresses_from_descriptor(descriptor, index, quantity)?;
+    scripts
+        .iter()
+        .map(|script| {
+            Address::from_script(script, network)
+                .map(|address| address.to_string())
+                .map_err(|_| DescriptorError::ScriptNotAddressable(format!("{:x}", script)))
+        })
+        .collect()
+}
+
 /// Derives addresses from a parsed descriptor.
 /// Generates the specified number of addresses starting from the given index.
 pub fn derive_addresses_from_parsed_descriptor(
@@ -147,6 +409,66 @@ pub fn derive_addresses_from_parsed_descriptor(
     Ok(addresses)
 }
 
+/// The standard output script type a [`ScriptInfo`] was classified as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// Pay-to-Public-Key-Hash (legacy, base58).
+    P2pkh,
+
+    /// Pay-to-Script-Hash (base58), e.g. nested SegWit.
+    P2sh,
+
+    /// Pay-to-Witness-Public-Key-Hash (native SegWit v0).
+    P2wpkh,
+
+    /// Pay-to-Witness-Script-Hash (native SegWit v0).
+    P2wsh,
+
+    /// Pay-to-Taproot (native SegWit v1).
+    P2tr,
+}
+
+/// A standalone address's output script, classified by its standard script type. This is what
+/// the node matches against incoming blocks/BIP-158 filters for watch-only tracking.
+#[derive(Debug, Clone)]
+pub struct ScriptInfo {
+    /// The address's script pubkey.
+    pub script: ScriptBuf,
+
+    /// The script's standard type.
+    pub kind: ScriptKind,
+}
+
+/// Parses a standalone base58 (P2PKH/P2SH) or bech32/bech32m (P2WPKH/P2WSH/P2TR) address and
+/// classifies its script, so users can register individual addresses for balance/UTXO tracking
+/// the same way [`derive_addresses_from_descriptor`] registers a ranged descriptor's addresses.
+pub fn address_to_script_info(
+    address: &str,
+    network: Network,
+) -> Result<ScriptInfo, DescriptorError> {
+    let parsed = Address::from_str(address)
+        .map_err(|e| DescriptorError::InvalidAddress(e.to_string()))?
+        .require_network(network)
+        .map_err(|_| DescriptorError::AddressNetworkMismatch(address.to_string()))?;
+
+    let script = parsed.script_pubkey();
+    let kind = if script.is_p2pkh() {
+        ScriptKind::P2pkh
+    } else if script.is_p2sh() {
+        ScriptKind::P2sh
+    } else if script.is_p2wpkh() {
+        ScriptKind::P2wpkh
+    } else if script.is_p2wsh() {
+        ScriptKind::P2wsh
+    } else if script.is_p2tr() {
+        ScriptKind::P2tr
+    } else {
+        return Err(DescriptorError::UnsupportedScriptType(address.to_string()));
+    };
+
+    Ok(ScriptInfo { script, kind })
+}
+
 #[cfg(test)]
 mod test {
     use std::vec;
@@ -251,6 +573,166 @@ mod test {
         network: Network::Regtest,
     };
 
+    /// A BIP-86 (Taproot) test case: unlike [`TestCase`], the xpub's own SLIP-132 prefix doesn't
+    /// determine the script type, so the origin path carrying the `86'` purpose is needed too.
+    struct TaprootTestCase {
+        xpub: &'static str,
+        origin_path: &'static [u32],
+        main_descriptor: &'static str,
+        change_descriptor: &'static str,
+        main_address: &'static str,
+        change_address: &'static str,
+        main_script: &'static str,
+        change_script: &'static str,
+        network: Network,
+    }
+
+    const TEST_CASE_TR_XPUB: TaprootTestCase = TaprootTestCase {
+        xpub: "xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs",
+        origin_path: &[86, 0, 0],
+        main_descriptor: "tr(xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs/0/*)#lh42dtw2",
+        change_descriptor: "tr(xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs/1/*)#wrsts77j",
+        main_address: "bc1pa6x3ujrczu8e428jy03y97swt8ejudam7z6udkhparaec87htd3s5afqau",
+        change_address: "bc1pusz28yz2ddma0jj5kyayt34wnr8pjuwwt5c785lq4k3yjrqh29us952ruw",
+        main_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 ee8d1e4878170f9aa8f223e242fa0e59f32e37bbf0b5c6dae1e8fb9c1fd75b63",
+        change_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 e404a3904a6b77d7ca54b13a45c6ae98ce1971ce5d31e3d3e0ada2490c175179",
+        network: Network::Bitcoin,
+    };
+
+    const TEST_CASE_TR_TPUB: TaprootTestCase = TaprootTestCase {
+        xpub: "tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5",
+        origin_path: &[86, 1, 0],
+        main_descriptor: "tr(tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5/0/*)#6n9zlu0p",
+        change_descriptor: "tr(tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5/1/*)#t8qrzfle",
+        main_address: "tb1ph4f68ms3vfa83wynfaw3h80su3p5xjh7hx9l42z8phr99cnheq8qgnrlcd",
+        change_address: "tb1pxrqdn2h63lyytwphupqkays7eduuz33esf5gzxep5fap9c3teswsjeu599",
+        main_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 bd53a3ee11627a78b8934f5d1b9df0e443434afeb98bfaa8470dc652e277c80e",
+        change_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 30c0d9aafa8fc845b837e0416e921ecb79c146398268811b21a27a12e22bcc1d",
+        network: Network::Testnet,
+    };
+
+    const TEST_CASE_TR_TPUB_REGTEST: TaprootTestCase = TaprootTestCase {
+        xpub: "tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5",
+        origin_path: &[86, 1, 0],
+        main_descriptor: "tr(tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5/0/*)#6n9zlu0p",
+        change_descriptor: "tr(tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5/1/*)#t8qrzfle",
+        main_address: "bcrt1ph4f68ms3vfa83wynfaw3h80su3p5xjh7hx9l42z8phr99cnheq8q92fedh",
+        change_address: "bcrt1pxrqdn2h63lyytwphupqkays7eduuz33esf5gzxep5fap9c3teswslqkjsl",
+        main_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 bd53a3ee11627a78b8934f5d1b9df0e443434afeb98bfaa8470dc652e277c80e",
+        change_script: "OP_PUSHNUM_1 OP_PUSHBYTES_32 30c0d9aafa8fc845b837e0416e921ecb79c146398268811b21a27a12e22bcc1d",
+        network: Network::Regtest,
+    };
+
+    const TR_TEST_CASES: [&TaprootTestCase; 3] = [
+        &TEST_CASE_TR_XPUB,
+        &TEST_CASE_TR_TPUB,
+        &TEST_CASE_TR_TPUB_REGTEST,
+    ];
+
+    /// A three-branch `<0;1;2>` multipath descriptor, exercising an extra keychain beyond the
+    /// fixed receive/change split [`TestCase`] assumes.
+    const MULTIPATH_DESCRIPTOR: &str = "wpkh(xpub6CbPqb3FCEjaF4LnfMwdEAUxKhC6ZP1sJzGiMMz3mfmcjXdFPM9LB9S8HSChXW593am685964YZk8Hng1ekynqNWGRZfpo8PpDaUmyvQqvY/<0;1;2>/*)";
+    const MULTIPATH_BRANCH_2_DESCRIPTOR: &str = "wpkh(xpub6CbPqb3FCEjaF4LnfMwdEAUxKhC6ZP1sJzGiMMz3mfmcjXdFPM9LB9S8HSChXW593am685964YZk8Hng1ekynqNWGRZfpo8PpDaUmyvQqvY/2/*)#ft8s9ex8";
+    const MULTIPATH_BRANCH_2_ADDRESS: &str = "bc1qpjqrnnwf8383htfs6hmu4r2rqq2pcjlcexxard";
+    const MULTIPATH_BRANCH_2_SCRIPT: &str =
+        "OP_0 OP_PUSHBYTES_20 0c8039cdc93c4f1bad30d5f7ca8d4300141c4bf8";
+
+    #[test]
+    fn test_parse_and_split_descriptor_with_branches() {
+        let tagged = parse_and_split_descriptor_with_branches(MULTIPATH_DESCRIPTOR).unwrap();
+        assert_eq!(tagged.len(), 3);
+
+        let untagged = parse_and_split_descriptor(MULTIPATH_DESCRIPTOR).unwrap();
+        for (branch, descriptor) in tagged {
+            assert_eq!(descriptor.to_string(), untagged[branch].to_string());
+        }
+    }
+
+    #[test]
+    fn test_derive_addresses_from_multipath() {
+        let script_buff =
+            derive_addresses_from_multipath(MULTIPATH_DESCRIPTOR, 2, 0, 1).unwrap();
+        assert_eq!(script_buff.len(), 1);
+        assert_eq!(script_buff[0].to_string(), MULTIPATH_BRANCH_2_SCRIPT);
+
+        let descriptors = parse_and_split_descriptor(MULTIPATH_DESCRIPTOR).unwrap();
+        let branch_2 = descriptors[2].clone();
+        assert_eq!(branch_2.to_string(), MULTIPATH_BRANCH_2_DESCRIPTOR);
+        let address = branch_2
+            .at_derivation_index(0)
+            .unwrap()
+            .address(Network::Bitcoin)
+            .unwrap();
+        assert_eq!(address.to_string(), MULTIPATH_BRANCH_2_ADDRESS);
+    }
+
+    #[test]
+    fn test_derive_addresses_from_multipath_branch_out_of_range() {
+        let err = derive_addresses_from_multipath(MULTIPATH_DESCRIPTOR, 3, 0, 1).unwrap_err();
+        assert!(matches!(
+            err,
+            DescriptorError::MultipathBranchNotFound {
+                branch: 3,
+                branches: 3,
+            }
+        ));
+    }
+
+    fn tr_origin_path(tc: &TaprootTestCase) -> DerivationPath {
+        DerivationPath::from(
+            tc.origin_path
+                .iter()
+                .map(|index| bitcoin::bip32::ChildNumber::Hardened { index: *index })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_parse_xpub_with_origin_taproot() {
+        for &tc in &TR_TEST_CASES {
+            let origin_path = tr_origin_path(tc);
+            let descriptors_string =
+                parse_xpub_with_origin(tc.xpub, &origin_path, tc.network).unwrap();
+            assert_eq!(descriptors_string.len(), 2);
+            assert_eq!(descriptors_string[0], tc.main_descriptor);
+            assert_eq!(descriptors_string[1], tc.change_descriptor);
+
+            let descriptors = parse_descriptors(&descriptors_string).unwrap();
+            assert_eq!(descriptors.len(), 2);
+
+            let main_address = descriptors[0]
+                .clone()
+                .at_derivation_index(0)
+                .unwrap()
+                .address(tc.network)
+                .unwrap();
+            assert_eq!(main_address.to_string(), tc.main_address);
+
+            let change_address = descriptors[1]
+                .clone()
+                .at_derivation_index(0)
+                .unwrap()
+                .address(tc.network)
+                .unwrap();
+            assert_eq!(change_address.to_string(), tc.change_address);
+        }
+    }
+
+    #[test]
+    fn test_derive_addresses_from_descriptor_taproot() {
+        for &tc in &TR_TEST_CASES {
+            let main_script_buff =
+                derive_addresses_from_descriptor(tc.main_descriptor, 0, 1).unwrap();
+            assert_eq!(main_script_buff.len(), 1);
+            assert_eq!(main_script_buff[0].to_string(), tc.main_script);
+
+            let change_script_buff =
+                derive_addresses_from_descriptor(tc.change_descriptor, 0, 1).unwrap();
+            assert_eq!(change_script_buff.len(), 1);
+            assert_eq!(change_script_buff[0].to_string(), tc.change_script);
+        }
+    }
+
     const TEST_CASES: [&TestCase; 7] = [
         &TEST_CASE_XPUB,
         &TEST_CASE_YPUB,
@@ -358,6 +840,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_canonicalize_descriptor_unifies_hardened_marker_and_fingerprint_case() {
+        let xpub = "xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs";
+        let canonical = format!("pkh([aabbccdd/48h/0h/0h/2h]{xpub}/0/*)#y5dwq95x");
+
+        let h_form = format!("pkh([aabbccdd/48h/0h/0h/2h]{xpub}/0/*)");
+        let apostrophe_upper_form = format!("pkh([AABBCCDD/48'/0'/0'/2']{xpub}/0/*)");
+
+        assert_eq!(canonicalize_descriptor(&h_form).unwrap(), canonical);
+        assert_eq!(
+            canonicalize_descriptor(&apostrophe_upper_form).unwrap(),
+            canonical
+        );
+    }
+
     #[test]
     fn test_parse_and_split_descriptor_valid_cases() {
         for cases in TEST_CASES {
@@ -419,6 +916,25 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_derive_scripts_hex_valid_cases() {
+        for &tc in &TEST_CASES {
+            let scripts = derive_addresses_from_descriptor(tc.main_descriptor, 0, 1).unwrap();
+            let expected_hex = format!("{:x}", scripts[0]);
+
+            let hex = derive_scripts_hex(tc.main_descriptor, 0, 1).unwrap();
+            assert_eq!(hex, vec![expected_hex]);
+        }
+    }
+
+    #[test]
+    fn test_derive_addresses_valid_cases() {
+        for &tc in &TEST_CASES {
+            let addresses = derive_addresses(tc.main_descriptor, 0, 1, tc.network).unwrap();
+            assert_eq!(addresses, vec![tc.main_address.to_string()]);
+        }
+    }
+
     #[test]
     fn test_derive_addresses_from_parsed_descriptor_valid_cases() {
         for &tc in &TEST_CASES {
@@ -431,6 +947,49 @@ mod test {
         }
     }
 
+    fn expected_script_kind(default_descriptor: &str) -> ScriptKind {
+        if default_descriptor.starts_with("pkh(") {
+            ScriptKind::P2pkh
+        } else if default_descriptor.starts_with("sh(wpkh(") {
+            ScriptKind::P2sh
+        } else if default_descriptor.starts_with("wpkh(") {
+            ScriptKind::P2wpkh
+        } else {
+            panic!("add a case to expected_script_kind for {default_descriptor}")
+        }
+    }
+
+    #[test]
+    fn test_address_to_script_info_round_trips_test_cases() {
+        for &tc in &TEST_CASES {
+            let info = address_to_script_info(tc.main_address, tc.network).unwrap();
+            assert_eq!(info.script.to_string(), tc.main_script);
+            assert_eq!(info.kind, expected_script_kind(tc.default_descriptor));
+
+            let info = address_to_script_info(tc.change_address, tc.network).unwrap();
+            assert_eq!(info.script.to_string(), tc.change_script);
+        }
+
+        for &tc in &TR_TEST_CASES {
+            let info = address_to_script_info(tc.main_address, tc.network).unwrap();
+            assert_eq!(info.script.to_string(), tc.main_script);
+            assert_eq!(info.kind, ScriptKind::P2tr);
+        }
+    }
+
+    #[test]
+    fn test_address_to_script_info_rejects_invalid_address() {
+        let err = address_to_script_info("not an address", Network::Bitcoin).unwrap_err();
+        assert!(matches!(err, DescriptorError::InvalidAddress(_)));
+    }
+
+    #[test]
+    fn test_address_to_script_info_rejects_wrong_network() {
+        let err = address_to_script_info(TEST_CASE_XPUB.main_address, Network::Testnet)
+            .unwrap_err();
+        assert!(matches!(err, DescriptorError::AddressNetworkMismatch(_)));
+    }
+
     #[test]
     fn test_invalid_descriptor_parsing() {
         fn check(result: Result<Vec<Descriptor<DescriptorPublicKey>>, DescriptorError>) {
@@ -12,7 +12,11 @@ use core::fmt::Debug;
 
 use bitcoin::base58;
 use bitcoin::bip32;
+use bitcoin::bip32::ChildNumber;
+use bitcoin::bip32::DerivationPath;
+use bitcoin::bip32::Xpriv;
 use bitcoin::bip32::Xpub;
+use bitcoin::NetworkKind;
 
 /// Magical version bytes for xpub: bitcoin mainnet public key for P2PKH or P2SH
 pub const VERSION_MAGIC_XPUB: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
@@ -112,6 +116,22 @@ pub enum Error {
 
     /// No multisig support via xpub.
     NoSupportXpubMultisig,
+
+    /// multisig keys must all share the same prefix (Y/Z or U/V) and network.
+    MixedMultisigPrefixes,
+
+    /// multisig threshold must be between 1 and the number of keys, got {0}.
+    InvalidThreshold(usize),
+
+    /// SLIP-132 has no version bytes for Taproot keys.
+    NoSlip132EncodingForTaproot,
+
+    /// an account-level origin path's first segment isn't hardened, as BIP-44-style paths
+    /// require.
+    MissingHardenedPurpose,
+
+    /// origin path purpose {0}' isn't one this build recognizes.
+    UnrecognizedPurpose(u32),
 }
 
 impl fmt::Display for Error {
@@ -133,6 +153,136 @@ impl fmt::Display for Error {
             Error::InternalFailure => write!(f, "Internal failure"),
             Error::NoSupportXpriv => write!(f, "No support for xpriv keys"),
             Error::NoSupportXpubMultisig => write!(f, "No support for xpub multisig keys"),
+            Error::MixedMultisigPrefixes => {
+                write!(f, "Multisig keys must all share the same prefix and network")
+            }
+            Error::InvalidThreshold(threshold) => {
+                write!(f, "Invalid multisig threshold: {}", threshold)
+            }
+            Error::NoSlip132EncodingForTaproot => {
+                write!(f, "SLIP-132 has no version bytes for Taproot keys")
+            }
+            Error::MissingHardenedPurpose => {
+                write!(f, "Origin path's first segment must be a hardened purpose")
+            }
+            Error::UnrecognizedPurpose(purpose) => {
+                write!(f, "Unrecognized origin path purpose: {}'", purpose)
+            }
+        }
+    }
+}
+
+/// A decoded SLIP-132 version prefix: which chain and script type it's valid for, and whether
+/// it carries a public or private key. Consolidates the magic-byte knowledge that used to be
+/// re-matched separately in [`extract_slip132_prefix`], [`validate_slip132_prefix`],
+/// [`is_xpub_mainnet`], and [`generate_descriptor_from_xpub`] into a single lookup table. The
+/// private-key, multisig, Taproot and explicit-path helpers below still match the magic bytes
+/// directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyVersion([u8; 4]);
+
+impl KeyVersion {
+    /// Looks up `prefix` against the known SLIP-132 version bytes.
+    pub fn try_from_prefix(prefix: &[u8; 4]) -> Result<Self, Error> {
+        match *prefix {
+            VERSION_MAGIC_XPUB
+            | VERSION_MAGIC_XPRV
+            | VERSION_MAGIC_YPUB
+            | VERSION_MAGIC_YPRV
+            | VERSION_MAGIC_ZPUB
+            | VERSION_MAGIC_ZPRV
+            | VERSION_MAGIC_TPUB
+            | VERSION_MAGIC_TPRV
+            | VERSION_MAGIC_UPUB
+            | VERSION_MAGIC_UPRV
+            | VERSION_MAGIC_VPUB
+            | VERSION_MAGIC_VPRV
+            | VERSION_MAGIC_YPUB_MULTISIG
+            | VERSION_MAGIC_YPRV_MULTISIG
+            | VERSION_MAGIC_ZPUB_MULTISIG
+            | VERSION_MAGIC_ZPRV_MULTISIG
+            | VERSION_MAGIC_UPUB_MULTISIG
+            | VERSION_MAGIC_UPRV_MULTISIG
+            | VERSION_MAGIC_VPUB_MULTISIG
+            | VERSION_MAGIC_VPRV_MULTISIG => Ok(Self(*prefix)),
+
+            _ => Err(Error::UnknownSlip32Prefix),
+        }
+    }
+
+    /// True for a public-key (`*pub`) prefix.
+    pub fn is_pub(&self) -> bool {
+        !self.is_prv()
+    }
+
+    /// True for a private-key (`*prv`) prefix.
+    pub fn is_prv(&self) -> bool {
+        matches!(
+            self.0,
+            VERSION_MAGIC_XPRV
+                | VERSION_MAGIC_YPRV
+                | VERSION_MAGIC_ZPRV
+                | VERSION_MAGIC_TPRV
+                | VERSION_MAGIC_UPRV
+                | VERSION_MAGIC_VPRV
+                | VERSION_MAGIC_YPRV_MULTISIG
+                | VERSION_MAGIC_ZPRV_MULTISIG
+                | VERSION_MAGIC_UPRV_MULTISIG
+                | VERSION_MAGIC_VPRV_MULTISIG
+        )
+    }
+
+    /// True for one of the eight `Y`/`Z`/`U`/`V`-prefixed multisig variants.
+    pub fn is_multisig(&self) -> bool {
+        matches!(
+            self.0,
+            VERSION_MAGIC_YPUB_MULTISIG
+                | VERSION_MAGIC_YPRV_MULTISIG
+                | VERSION_MAGIC_ZPUB_MULTISIG
+                | VERSION_MAGIC_ZPRV_MULTISIG
+                | VERSION_MAGIC_UPUB_MULTISIG
+                | VERSION_MAGIC_UPRV_MULTISIG
+                | VERSION_MAGIC_VPUB_MULTISIG
+                | VERSION_MAGIC_VPRV_MULTISIG
+        )
+    }
+
+    /// The chain this prefix is valid on.
+    pub fn network(&self) -> NetworkKind {
+        match self.0 {
+            VERSION_MAGIC_XPUB
+            | VERSION_MAGIC_XPRV
+            | VERSION_MAGIC_YPUB
+            | VERSION_MAGIC_YPRV
+            | VERSION_MAGIC_ZPUB
+            | VERSION_MAGIC_ZPRV
+            | VERSION_MAGIC_YPUB_MULTISIG
+            | VERSION_MAGIC_YPRV_MULTISIG
+            | VERSION_MAGIC_ZPUB_MULTISIG
+            | VERSION_MAGIC_ZPRV_MULTISIG => NetworkKind::Main,
+
+            _ => NetworkKind::Test,
+        }
+    }
+
+    /// The script type this prefix is used for. Never returns [`KeyApplication::Taproot`]:
+    /// SLIP-132 has no dedicated version bytes for it.
+    pub fn application(&self) -> KeyApplication {
+        match self.0 {
+            VERSION_MAGIC_XPUB | VERSION_MAGIC_XPRV | VERSION_MAGIC_TPUB | VERSION_MAGIC_TPRV => {
+                KeyApplication::Legacy
+            }
+
+            VERSION_MAGIC_YPUB
+            | VERSION_MAGIC_YPRV
+            | VERSION_MAGIC_UPUB
+            | VERSION_MAGIC_UPRV
+            | VERSION_MAGIC_YPUB_MULTISIG
+            | VERSION_MAGIC_YPRV_MULTISIG
+            | VERSION_MAGIC_UPUB_MULTISIG
+            | VERSION_MAGIC_UPRV_MULTISIG => KeyApplication::NestedSegwit,
+
+            _ => KeyApplication::NativeSegwit,
         }
     }
 }
@@ -147,19 +297,21 @@ fn extract_slip132_prefix(s: &str) -> Result<[u8; 4], Error> {
     Ok(prefix)
 }
 
-fn validate_slip132_prefix(prefix: [u8; 4]) -> Result<(), Error> {
-    match prefix {
-        VERSION_MAGIC_XPUB | VERSION_MAGIC_YPUB | VERSION_MAGIC_ZPUB | VERSION_MAGIC_TPUB
-        | VERSION_MAGIC_UPUB | VERSION_MAGIC_VPUB => Ok(()),
+/// Like [`extract_slip132_prefix`], but for the six single-sig private-key prefixes
+/// (`xprv`/`yprv`/`zprv`/`tprv`/`uprv`/`vprv`). Kept separate from [`validate_slip132_prefix`] so
+/// that the public-key extraction path used by [`generate_descriptor_from_xpub`] and friends
+/// keeps rejecting a private key exactly as before; this only feeds the `FromSlip132` impl for
+/// [`Xpriv`].
+fn extract_slip132_prv_prefix(s: &str) -> Result<[u8; 4], Error> {
+    let data = base58::decode_check(s)?;
+    let mut prefix = [0u8; 4];
+    prefix.copy_from_slice(&data[0..4]);
 
+    match prefix {
         VERSION_MAGIC_XPRV | VERSION_MAGIC_YPRV | VERSION_MAGIC_ZPRV | VERSION_MAGIC_TPRV
-        | VERSION_MAGIC_UPRV | VERSION_MAGIC_VPRV => Err(Error::NoSupportXpriv),
+        | VERSION_MAGIC_UPRV | VERSION_MAGIC_VPRV => Ok(prefix),
 
-        VERSION_MAGIC_YPUB_MULTISIG
-        | VERSION_MAGIC_ZPUB_MULTISIG
-        | VERSION_MAGIC_UPUB_MULTISIG
-        | VERSION_MAGIC_VPUB_MULTISIG
-        | VERSION_MAGIC_YPRV_MULTISIG
+        VERSION_MAGIC_YPRV_MULTISIG
         | VERSION_MAGIC_ZPRV_MULTISIG
         | VERSION_MAGIC_UPRV_MULTISIG
         | VERSION_MAGIC_VPRV_MULTISIG => Err(Error::NoSupportXpubMultisig),
@@ -168,6 +320,101 @@ fn validate_slip132_prefix(prefix: [u8; 4]) -> Result<(), Error> {
     }
 }
 
+/// Like [`extract_slip132_prv_prefix`], but for the eight multisig prefixes (`Ypub`/`Zpub`/
+/// `Upub`/`Vpub` and their private counterparts). Only the public ones are accepted: this only
+/// feeds [`generate_multisig_descriptor_from_xpubs`], which has no use for a private key.
+fn extract_slip132_multisig_prefix(s: &str) -> Result<[u8; 4], Error> {
+    let data = base58::decode_check(s)?;
+    let mut prefix = [0u8; 4];
+    prefix.copy_from_slice(&data[0..4]);
+
+    match prefix {
+        VERSION_MAGIC_YPUB_MULTISIG
+        | VERSION_MAGIC_ZPUB_MULTISIG
+        | VERSION_MAGIC_UPUB_MULTISIG
+        | VERSION_MAGIC_VPUB_MULTISIG => Ok(prefix),
+
+        _ => Err(Error::UnknownSlip32Prefix),
+    }
+}
+
+/// Decodes a `Ypub`/`Zpub`/`Upub`/`Vpub` string (whose `prefix` must be one
+/// [`extract_slip132_multisig_prefix`] already validated) into a canonical [`Xpub`].
+fn xpub_from_multisig_str(s: &str, prefix: [u8; 4]) -> Result<Xpub, Error> {
+    let mut data = base58::decode_check(s)?;
+
+    let canonical = match prefix {
+        VERSION_MAGIC_YPUB_MULTISIG | VERSION_MAGIC_ZPUB_MULTISIG => VERSION_MAGIC_XPUB,
+        VERSION_MAGIC_UPUB_MULTISIG | VERSION_MAGIC_VPUB_MULTISIG => VERSION_MAGIC_TPUB,
+        _ => return Err(Error::UnknownSlip32Prefix),
+    };
+    data[0..4].copy_from_slice(&canonical);
+
+    Ok(Xpub::decode(&data)?)
+}
+
+/// Generates a `sortedmulti` multisig descriptor from several `Ypub`/`Zpub` (or testnet `Upub`/
+/// `Vpub`) strings. Z/V-prefixed keys produce a native P2WSH descriptor; Y/U-prefixed keys
+/// produce P2WSH wrapped in P2SH, matching how those prefixes are used for single-sig keys.
+///
+/// Every key must share the exact same prefix - mixing Y with Z, or mainnet with testnet, is
+/// rejected rather than silently picking one side. `threshold` must be at least 1 and at most
+/// `keys.len()`.
+pub fn generate_multisig_descriptor_from_xpubs(
+    keys: &[&str],
+    threshold: usize,
+    change: bool,
+) -> Result<String, Error> {
+    if threshold == 0 || threshold > keys.len() {
+        return Err(Error::InvalidThreshold(threshold));
+    }
+
+    let index = if change { 1 } else { 0 };
+
+    let prefixes = keys
+        .iter()
+        .map(|key| extract_slip132_multisig_prefix(key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let application = prefixes[0];
+    if prefixes.iter().any(|prefix| *prefix != application) {
+        return Err(Error::MixedMultisigPrefixes);
+    }
+
+    let key_exprs = keys
+        .iter()
+        .map(|key| xpub_from_multisig_str(key, application))
+        .map(|xpub| xpub.map(|xpub| format!("{xpub}/{index}/*")))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(",");
+
+    let sortedmulti = format!("sortedmulti({threshold},{key_exprs})");
+
+    match application {
+        VERSION_MAGIC_ZPUB_MULTISIG | VERSION_MAGIC_VPUB_MULTISIG => {
+            Ok(format!("wsh({sortedmulti})"))
+        }
+        VERSION_MAGIC_YPUB_MULTISIG | VERSION_MAGIC_UPUB_MULTISIG => {
+            Ok(format!("sh(wsh({sortedmulti}))"))
+        }
+        _ => Err(Error::UnknownSlip32Prefix),
+    }
+}
+
+fn validate_slip132_prefix(prefix: [u8; 4]) -> Result<(), Error> {
+    let version = KeyVersion::try_from_prefix(&prefix)?;
+
+    if version.is_multisig() {
+        return Err(Error::NoSupportXpubMultisig);
+    }
+
+    if version.is_prv() {
+        return Err(Error::NoSupportXpriv);
+    }
+
+    Ok(())
+}
+
 impl From<bip32::Error> for Error {
     fn from(err: bip32::Error) -> Self {
         match err {
@@ -219,6 +466,101 @@ impl FromSlip132 for Xpub {
     }
 }
 
+impl FromSlip132 for Xpriv {
+    /// Decodes a SLIP-132 `xprv`/`yprv`/`zprv`/`tprv`/`uprv`/`vprv` string into a standard BIP-32
+    /// [`Xpriv`]. BIP-32 only encodes mainnet vs. testnet, not which prefix produced the key, so
+    /// the returned [`Xpriv`]'s `network` field is the only place that distinction survives -
+    /// callers that need it can read `xpriv.network` instead of re-parsing `s`'s prefix.
+    fn from_slip132_str(s: &str) -> Result<Self, Error> {
+        let mut data = base58::decode_check(s)?;
+
+        let prefix: [u8; 4] = extract_slip132_prv_prefix(s)?;
+        let slice = match prefix {
+            VERSION_MAGIC_XPRV | VERSION_MAGIC_YPRV | VERSION_MAGIC_ZPRV => VERSION_MAGIC_XPRV,
+
+            VERSION_MAGIC_TPRV | VERSION_MAGIC_UPRV | VERSION_MAGIC_VPRV => VERSION_MAGIC_TPRV,
+
+            _ => return Err(Error::UnknownSlip32Prefix),
+        };
+        data[0..4].copy_from_slice(&slice);
+
+        let xpriv = Xpriv::decode(&data)?;
+
+        Ok(xpriv)
+    }
+}
+
+/// The script type a [`ToSlip132`] key should be encoded for, i.e. which version bytes to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyApplication {
+    /// P2PKH / P2SH: `xpub`/`xprv`, or `tpub`/`tprv` on testnet.
+    Legacy,
+    /// P2WPKH-in-P2SH: `ypub`/`yprv`, or `upub`/`uprv` on testnet.
+    NestedSegwit,
+    /// P2WPKH: `zpub`/`zprv`, or `vpub`/`vprv` on testnet.
+    NativeSegwit,
+    /// Single-key Taproot. SLIP-132 has no dedicated version bytes for this, so it's only
+    /// meaningful to [`generate_taproot_descriptor_from_xpub`], never to [`ToSlip132`].
+    Taproot,
+}
+
+/// Trait for re-encoding a standard BIP-32 extended key into its SLIP-132 y/z (or t/u/v) form.
+/// The mirror image of [`FromSlip132`].
+pub trait ToSlip132 {
+    /// Encodes `self` as a SLIP-132 string for the given `application` and `network`.
+    fn to_slip132_str(
+        &self,
+        application: KeyApplication,
+        network: NetworkKind,
+    ) -> Result<String, Error>;
+}
+
+impl ToSlip132 for Xpub {
+    fn to_slip132_str(
+        &self,
+        application: KeyApplication,
+        network: NetworkKind,
+    ) -> Result<String, Error> {
+        let version = match (application, network) {
+            (KeyApplication::Legacy, NetworkKind::Main) => VERSION_MAGIC_XPUB,
+            (KeyApplication::Legacy, NetworkKind::Test) => VERSION_MAGIC_TPUB,
+            (KeyApplication::NestedSegwit, NetworkKind::Main) => VERSION_MAGIC_YPUB,
+            (KeyApplication::NestedSegwit, NetworkKind::Test) => VERSION_MAGIC_UPUB,
+            (KeyApplication::NativeSegwit, NetworkKind::Main) => VERSION_MAGIC_ZPUB,
+            (KeyApplication::NativeSegwit, NetworkKind::Test) => VERSION_MAGIC_VPUB,
+            (KeyApplication::Taproot, _) => return Err(Error::NoSlip132EncodingForTaproot),
+        };
+
+        let mut data = self.encode();
+        data[0..4].copy_from_slice(&version);
+
+        Ok(base58::encode_check(&data))
+    }
+}
+
+impl ToSlip132 for Xpriv {
+    fn to_slip132_str(
+        &self,
+        application: KeyApplication,
+        network: NetworkKind,
+    ) -> Result<String, Error> {
+        let version = match (application, network) {
+            (KeyApplication::Legacy, NetworkKind::Main) => VERSION_MAGIC_XPRV,
+            (KeyApplication::Legacy, NetworkKind::Test) => VERSION_MAGIC_TPRV,
+            (KeyApplication::NestedSegwit, NetworkKind::Main) => VERSION_MAGIC_YPRV,
+            (KeyApplication::NestedSegwit, NetworkKind::Test) => VERSION_MAGIC_UPRV,
+            (KeyApplication::NativeSegwit, NetworkKind::Main) => VERSION_MAGIC_ZPRV,
+            (KeyApplication::NativeSegwit, NetworkKind::Test) => VERSION_MAGIC_VPRV,
+            (KeyApplication::Taproot, _) => return Err(Error::NoSlip132EncodingForTaproot),
+        };
+
+        let mut data = self.encode();
+        data[0..4].copy_from_slice(&version);
+
+        Ok(base58::encode_check(&data))
+    }
+}
+
 /// Generates a descriptor based on the provided xpub.
 /// The descriptor type is determined by the xpub's prefix:
 /// - P2PKH for xpub/tpub (Legacy addresses)
@@ -228,29 +570,144 @@ pub fn generate_descriptor_from_xpub(s: &str, change: bool) -> Result<String, Er
     let index = if change { 1 } else { 0 };
     let xpub = Xpub::from_slip132_str(s)?;
 
+    let prefix = extract_slip132_prefix(s)?;
+    let version = KeyVersion::try_from_prefix(&prefix)?;
+
+    match version.application() {
+        KeyApplication::Legacy => Ok(format!("pkh({xpub}/{index}/*)")),
+        KeyApplication::NestedSegwit => Ok(format!("sh(wpkh({xpub}/{index}/*))")),
+        KeyApplication::NativeSegwit => Ok(format!("wpkh({xpub}/{index}/*)")),
+        KeyApplication::Taproot => Err(Error::UnknownSlip32Prefix),
+    }
+}
+
+/// Generates a single-key Taproot (key-path-only) descriptor `tr(xpub/{idx}/*)` from a standard
+/// `xpub`/`tpub`. There are no dedicated SLIP-132 version bytes for Taproot, so unlike
+/// [`generate_descriptor_from_xpub`] this only accepts the bare x/tpub prefix - y/z/u/v keys keep
+/// routing to their own established script type via that function instead.
+pub fn generate_taproot_descriptor_from_xpub(s: &str, change: bool) -> Result<String, Error> {
+    let index = if change { 1 } else { 0 };
+    let xpub = Xpub::from_slip132_str(s)?;
+
     let prefix = extract_slip132_prefix(s)?;
 
     match prefix {
-        VERSION_MAGIC_XPUB | VERSION_MAGIC_TPUB => Ok(format!("pkh({xpub}/{index}/*)")),
-        VERSION_MAGIC_YPUB | VERSION_MAGIC_UPUB => Ok(format!("sh(wpkh({xpub}/{index}/*))")),
-        VERSION_MAGIC_ZPUB | VERSION_MAGIC_VPUB => Ok(format!("wpkh({xpub}/{index}/*)")),
+        VERSION_MAGIC_XPUB | VERSION_MAGIC_TPUB => Ok(format!("tr({xpub}/{index}/*)")),
 
         _ => Err(Error::UnknownSlip32Prefix),
     }
 }
 
-/// Checks if the xpub belongs to the mainnet based on its prefix.
-pub fn is_xpub_mainnet(s: &str) -> Result<bool, Error> {
+/// BIP-86 purpose field: single-key Taproot, `m/86'/...`.
+pub const BIP86_PURPOSE: u32 = 86;
+
+/// Reads the hardened purpose field off an account-level origin path, e.g. `86` from
+/// `m/86'/0'/0'`. This is the BIP-44-style convention
+/// [`generate_descriptor_from_xpub_and_origin`] uses to recognize Taproot accounts, since
+/// SLIP-132 itself has no dedicated prefix to read it from.
+fn purpose_from_origin(origin_path: &DerivationPath) -> Result<u32, Error> {
+    match origin_path.into_iter().next() {
+        Some(ChildNumber::Hardened { index }) => Ok(*index),
+        _ => Err(Error::MissingHardenedPurpose),
+    }
+}
+
+/// Generates a descriptor for `s`, recognizing the BIP-86 key-origin convention (`m/86'/...`)
+/// instead of inferring the script type from `s`'s own SLIP-132 prefix. This is the only way to
+/// reach a `tr()` descriptor from a bare `xpub`/`tpub` without calling
+/// [`generate_taproot_descriptor_from_xpub`] directly: SLIP-132 never defined version bytes for
+/// Taproot, so a wallet that only knows the xpub's origin path (e.g. from a
+/// `[fingerprint/86'/0'/0']xpub...` export) needs this to tell it apart from a legacy account at
+/// the same xpub prefix.
+///
+/// Purposes other than 86 are rejected with [`Error::UnrecognizedPurpose`] rather than silently
+/// falling back to another script type - callers that already know the xpub is, say, BIP-84
+/// should call [`generate_descriptor_from_xpub`] instead, which reads that off the `z`/`v` prefix
+/// directly.
+pub fn generate_descriptor_from_xpub_and_origin(
+    s: &str,
+    origin_path: &DerivationPath,
+    change: bool,
+) -> Result<String, Error> {
+    match purpose_from_origin(origin_path)? {
+        BIP86_PURPOSE => generate_taproot_descriptor_from_xpub(s, change),
+        purpose => Err(Error::UnrecognizedPurpose(purpose)),
+    }
+}
+
+/// Largest valid index for a non-hardened [`ChildNumber::Normal`]: the top bit of the 32-bit
+/// index is reserved to flag a hardened child, so a normal index tops out at `2^31 - 1`.
+const MAX_NORMAL_CHILD_INDEX: u32 = (1 << 31) - 1;
+
+/// Renders `path` as a `/i/j/...` string suitable for splicing right after an xpub in a
+/// descriptor. Public-key derivation can't cross a hardened step, so a hardened entry is
+/// rejected with [`Error::CannotDeriveFromHardenedKey`] rather than silently producing a
+/// descriptor nothing can actually derive from.
+fn derivation_path_str(path: &DerivationPath) -> Result<String, Error> {
+    let mut out = String::new();
+    for child in path {
+        match *child {
+            ChildNumber::Normal { index } if index <= MAX_NORMAL_CHILD_INDEX => {
+                out.push('/');
+                out.push_str(&index.to_string());
+            }
+            ChildNumber::Normal { index } => return Err(Error::InvalidChildNumber(index)),
+            ChildNumber::Hardened { .. } => return Err(Error::CannotDeriveFromHardenedKey),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Like [`generate_descriptor_from_xpub`], but appends an arbitrary relative `path` before the
+/// final `/*` wildcard instead of hardcoding a `0` or `1` branch. Lets callers express a
+/// non-standard layout (e.g. an xpub already exported at a hardened account level, or an
+/// intermediate account index) that the hardcoded `/{index}/*` form can't.
+pub fn generate_descriptor_from_xpub_with_path(
+    s: &str,
+    path: &DerivationPath,
+) -> Result<String, Error> {
+    let xpub = Xpub::from_slip132_str(s)?;
     let prefix = extract_slip132_prefix(s)?;
+    let path = derivation_path_str(path)?;
+
     match prefix {
-        VERSION_MAGIC_XPUB | VERSION_MAGIC_YPUB | VERSION_MAGIC_ZPUB => Ok(true),
+        VERSION_MAGIC_XPUB | VERSION_MAGIC_TPUB => Ok(format!("pkh({xpub}{path}/*)")),
+        VERSION_MAGIC_YPUB | VERSION_MAGIC_UPUB => Ok(format!("sh(wpkh({xpub}{path}/*))")),
+        VERSION_MAGIC_ZPUB | VERSION_MAGIC_VPUB => Ok(format!("wpkh({xpub}{path}/*)")),
 
-        VERSION_MAGIC_TPUB | VERSION_MAGIC_UPUB | VERSION_MAGIC_VPUB => Ok(false),
+        _ => Err(Error::UnknownSlip32Prefix),
+    }
+}
+
+/// Like [`generate_descriptor_from_xpub_with_path`], but emits the BIP-389 multipath form
+/// `/<0;1>/*` instead of a single `/*`, so one descriptor covers both the receive (`0`) and
+/// change (`1`) branches at once.
+pub fn generate_multipath_descriptor_from_xpub(
+    s: &str,
+    path: &DerivationPath,
+) -> Result<String, Error> {
+    let xpub = Xpub::from_slip132_str(s)?;
+    let prefix = extract_slip132_prefix(s)?;
+    let path = derivation_path_str(path)?;
+
+    match prefix {
+        VERSION_MAGIC_XPUB | VERSION_MAGIC_TPUB => Ok(format!("pkh({xpub}{path}/<0;1>/*)")),
+        VERSION_MAGIC_YPUB | VERSION_MAGIC_UPUB => Ok(format!("sh(wpkh({xpub}{path}/<0;1>/*))")),
+        VERSION_MAGIC_ZPUB | VERSION_MAGIC_VPUB => Ok(format!("wpkh({xpub}{path}/<0;1>/*)")),
 
         _ => Err(Error::UnknownSlip32Prefix),
     }
 }
 
+/// Checks if the xpub belongs to the mainnet based on its prefix.
+pub fn is_xpub_mainnet(s: &str) -> Result<bool, Error> {
+    let prefix = extract_slip132_prefix(s)?;
+    let version = KeyVersion::try_from_prefix(&prefix)?;
+
+    Ok(version.network() == NetworkKind::Main)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -373,4 +830,295 @@ mod test {
             assert_eq!(result, expect);
         }
     }
+
+    #[test]
+    fn test_xpriv_from_slip132_str_mainnet() {
+        for key in [XPRIV, YPRIV, ZPRIV] {
+            let xpriv = Xpriv::from_slip132_str(key).unwrap();
+            assert_eq!(xpriv.network, bitcoin::NetworkKind::Main);
+        }
+    }
+
+    #[test]
+    fn test_xpriv_from_slip132_str_testnet() {
+        for key in [TPRIV, UPRIV, VPRIV] {
+            let xpriv = Xpriv::from_slip132_str(key).unwrap();
+            assert_eq!(xpriv.network, bitcoin::NetworkKind::Test);
+        }
+    }
+
+    #[test]
+    fn test_xpriv_from_slip132_str_rejects_xpub() {
+        let result = Xpriv::from_slip132_str(XPUB);
+        assert_eq!(result.err().unwrap(), Error::UnknownSlip32Prefix);
+    }
+
+    #[test]
+    fn test_xpriv_from_slip132_str_rejects_multisig() {
+        for key in [YPRIV_MULTISIG, ZPRIV_MULTISIG, UPRIV_MULTISIG, VPRIV_MULTSIG] {
+            let result = Xpriv::from_slip132_str(key);
+            assert_eq!(result.err().unwrap(), Error::NoSupportXpubMultisig);
+        }
+    }
+
+    #[test]
+    fn test_xpub_to_slip132_str_round_trips() {
+        let cases = [
+            (XPUB, KeyApplication::Legacy, NetworkKind::Main),
+            (YPUB, KeyApplication::NestedSegwit, NetworkKind::Main),
+            (ZPUB, KeyApplication::NativeSegwit, NetworkKind::Main),
+            (TPUB, KeyApplication::Legacy, NetworkKind::Test),
+            (UPUB, KeyApplication::NestedSegwit, NetworkKind::Test),
+            (VPUB, KeyApplication::NativeSegwit, NetworkKind::Test),
+        ];
+
+        for (key, application, network) in cases {
+            let xpub = Xpub::from_slip132_str(key).unwrap();
+            assert_eq!(xpub.to_slip132_str(application, network).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_xpriv_to_slip132_str_round_trips() {
+        let cases = [
+            (XPRIV, KeyApplication::Legacy, NetworkKind::Main),
+            (YPRIV, KeyApplication::NestedSegwit, NetworkKind::Main),
+            (ZPRIV, KeyApplication::NativeSegwit, NetworkKind::Main),
+            (TPRIV, KeyApplication::Legacy, NetworkKind::Test),
+            (UPRIV, KeyApplication::NestedSegwit, NetworkKind::Test),
+            (VPRIV, KeyApplication::NativeSegwit, NetworkKind::Test),
+        ];
+
+        for (key, application, network) in cases {
+            let xpriv = Xpriv::from_slip132_str(key).unwrap();
+            assert_eq!(xpriv.to_slip132_str(application, network).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_generate_multisig_descriptor_from_xpubs() {
+        let zpub_xpub = xpub_from_multisig_str(
+            ZPUB_MULTISIG,
+            extract_slip132_multisig_prefix(ZPUB_MULTISIG).unwrap(),
+        )
+        .unwrap();
+
+        let descriptor =
+            generate_multisig_descriptor_from_xpubs(&[ZPUB_MULTISIG, ZPUB_MULTISIG], 2, false)
+                .unwrap();
+        assert_eq!(
+            descriptor,
+            format!("wsh(sortedmulti(2,{zpub_xpub}/0/*,{zpub_xpub}/0/*))")
+        );
+
+        let descriptor =
+            generate_multisig_descriptor_from_xpubs(&[YPUB_MULTISIG, YPUB_MULTISIG], 1, true)
+                .unwrap();
+        let ypub_xpub = xpub_from_multisig_str(
+            YPUB_MULTISIG,
+            extract_slip132_multisig_prefix(YPUB_MULTISIG).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            descriptor,
+            format!("sh(wsh(sortedmulti(1,{ypub_xpub}/1/*,{ypub_xpub}/1/*)))")
+        );
+
+        // mixed Y and Z prefixes are rejected
+        let result = generate_multisig_descriptor_from_xpubs(&[ZPUB_MULTISIG, YPUB_MULTISIG], 1, false);
+        assert_eq!(result.err().unwrap(), Error::MixedMultisigPrefixes);
+
+        // mixed mainnet and testnet prefixes are rejected
+        let result = generate_multisig_descriptor_from_xpubs(&[ZPUB_MULTISIG, UPUB_MULTISIG], 1, false);
+        assert_eq!(result.err().unwrap(), Error::MixedMultisigPrefixes);
+
+        // threshold out of range is rejected
+        let result = generate_multisig_descriptor_from_xpubs(&[ZPUB_MULTISIG], 0, false);
+        assert_eq!(result.err().unwrap(), Error::InvalidThreshold(0));
+
+        let result = generate_multisig_descriptor_from_xpubs(&[ZPUB_MULTISIG], 2, false);
+        assert_eq!(result.err().unwrap(), Error::InvalidThreshold(2));
+
+        // a singlesig xpub is rejected
+        let result = generate_multisig_descriptor_from_xpubs(&[XPUB], 1, false);
+        assert_eq!(result.err().unwrap(), Error::UnknownSlip32Prefix);
+    }
+
+    #[test]
+    fn test_generate_taproot_descriptor_from_xpub() {
+        let cases = &[
+            (XPUB, true, "tr(xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs/1/*)"),
+            (XPUB, false, "tr(xpub6CPimhNogJosVzpueNmrWEfSHc2YTXG1ZyE6TBV4Nx6UxZ7zKSGYv9hKxNjiFY5o1vz7QeZa2m6vQmyndDrkECk8cShWYWxe1gqa1xJEkgs/0/*)"),
+            (TPUB, false, "tr(tpubDC73PMTHeKDXnFwNFz8CLBy2VVx4D85WW2vbzwVLwCD9zkQ6Vj97muhLRTbKvmue1PyVQLwizvBW6v2SD1LnzbeuHnRsDYQZGE8urTZHMn5/0/*)"),
+        ];
+
+        for &(key, change, expect) in cases.iter() {
+            let result = generate_taproot_descriptor_from_xpub(key, change).unwrap();
+            assert_eq!(result, expect);
+        }
+    }
+
+    #[test]
+    fn test_generate_taproot_descriptor_from_xpub_rejects_ypub_zpub() {
+        for key in [YPUB, ZPUB, UPUB, VPUB] {
+            let result = generate_taproot_descriptor_from_xpub(key, false);
+            assert_eq!(result.err().unwrap(), Error::UnknownSlip32Prefix);
+        }
+    }
+
+    #[test]
+    fn test_to_slip132_str_rejects_taproot() {
+        let xpub = Xpub::from_slip132_str(XPUB).unwrap();
+        let result = xpub.to_slip132_str(KeyApplication::Taproot, NetworkKind::Main);
+        assert_eq!(result.err().unwrap(), Error::NoSlip132EncodingForTaproot);
+
+        let xpriv = Xpriv::from_slip132_str(XPRIV).unwrap();
+        let result = xpriv.to_slip132_str(KeyApplication::Taproot, NetworkKind::Main);
+        assert_eq!(result.err().unwrap(), Error::NoSlip132EncodingForTaproot);
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_with_path() {
+        let xpub = Xpub::from_slip132_str(XPUB).unwrap();
+        let path = DerivationPath::from(vec![ChildNumber::Normal { index: 7 }]);
+
+        let descriptor = generate_descriptor_from_xpub_with_path(XPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("pkh({xpub}/7/*)"));
+
+        let zpub = Xpub::from_slip132_str(ZPUB).unwrap();
+        let descriptor = generate_descriptor_from_xpub_with_path(ZPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("wpkh({zpub}/7/*)"));
+
+        let path = DerivationPath::from(vec![
+            ChildNumber::Normal { index: 0 },
+            ChildNumber::Normal { index: 12 },
+        ]);
+        let descriptor = generate_descriptor_from_xpub_with_path(XPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("pkh({xpub}/0/12/*)"));
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_with_path_rejects_hardened() {
+        let path = DerivationPath::from(vec![ChildNumber::Hardened { index: 0 }]);
+        let result = generate_descriptor_from_xpub_with_path(XPUB, &path);
+        assert_eq!(result.err().unwrap(), Error::CannotDeriveFromHardenedKey);
+    }
+
+    #[test]
+    fn test_generate_multipath_descriptor_from_xpub() {
+        let xpub = Xpub::from_slip132_str(XPUB).unwrap();
+        let path = DerivationPath::from(vec![]);
+
+        let descriptor = generate_multipath_descriptor_from_xpub(XPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("pkh({xpub}/<0;1>/*)"));
+
+        let ypub = Xpub::from_slip132_str(YPUB).unwrap();
+        let descriptor = generate_multipath_descriptor_from_xpub(YPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("sh(wpkh({ypub}/<0;1>/*))"));
+
+        let path = DerivationPath::from(vec![ChildNumber::Normal { index: 3 }]);
+        let descriptor = generate_multipath_descriptor_from_xpub(XPUB, &path).unwrap();
+        assert_eq!(descriptor, format!("pkh({xpub}/3/<0;1>/*)"));
+    }
+
+    #[test]
+    fn test_key_version_pub_prv_multisig() {
+        let cases = [
+            (VERSION_MAGIC_XPUB, true, false, false),
+            (VERSION_MAGIC_XPRV, false, true, false),
+            (VERSION_MAGIC_YPUB, true, false, false),
+            (VERSION_MAGIC_YPRV, false, true, false),
+            (VERSION_MAGIC_ZPUB_MULTISIG, true, false, true),
+            (VERSION_MAGIC_ZPRV_MULTISIG, false, true, true),
+        ];
+
+        for (prefix, is_pub, is_prv, is_multisig) in cases {
+            let version = KeyVersion::try_from_prefix(&prefix).unwrap();
+            assert_eq!(version.is_pub(), is_pub);
+            assert_eq!(version.is_prv(), is_prv);
+            assert_eq!(version.is_multisig(), is_multisig);
+        }
+    }
+
+    #[test]
+    fn test_key_version_network_and_application() {
+        let cases = [
+            (VERSION_MAGIC_XPUB, NetworkKind::Main, KeyApplication::Legacy),
+            (VERSION_MAGIC_TPUB, NetworkKind::Test, KeyApplication::Legacy),
+            (
+                VERSION_MAGIC_YPUB,
+                NetworkKind::Main,
+                KeyApplication::NestedSegwit,
+            ),
+            (
+                VERSION_MAGIC_UPUB,
+                NetworkKind::Test,
+                KeyApplication::NestedSegwit,
+            ),
+            (
+                VERSION_MAGIC_ZPUB,
+                NetworkKind::Main,
+                KeyApplication::NativeSegwit,
+            ),
+            (
+                VERSION_MAGIC_VPUB,
+                NetworkKind::Test,
+                KeyApplication::NativeSegwit,
+            ),
+        ];
+
+        for (prefix, network, application) in cases {
+            let version = KeyVersion::try_from_prefix(&prefix).unwrap();
+            assert_eq!(version.network(), network);
+            assert_eq!(version.application(), application);
+        }
+    }
+
+    #[test]
+    fn test_key_version_rejects_unknown_prefix() {
+        let result = KeyVersion::try_from_prefix(&[0x21, 0x21, 0x21, 0x21]);
+        assert_eq!(result.err().unwrap(), Error::UnknownSlip32Prefix);
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_and_origin_bip86() {
+        let origin_path = DerivationPath::from(vec![
+            ChildNumber::Hardened { index: 86 },
+            ChildNumber::Hardened { index: 0 },
+            ChildNumber::Hardened { index: 0 },
+        ]);
+
+        let descriptor = generate_descriptor_from_xpub_and_origin(XPUB, &origin_path, false).unwrap();
+        assert_eq!(
+            descriptor,
+            generate_taproot_descriptor_from_xpub(XPUB, false).unwrap()
+        );
+
+        let descriptor = generate_descriptor_from_xpub_and_origin(XPUB, &origin_path, true).unwrap();
+        assert_eq!(
+            descriptor,
+            generate_taproot_descriptor_from_xpub(XPUB, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_and_origin_rejects_other_purposes() {
+        let origin_path = DerivationPath::from(vec![ChildNumber::Hardened { index: 84 }]);
+        let result = generate_descriptor_from_xpub_and_origin(XPUB, &origin_path, false);
+        assert_eq!(result.err().unwrap(), Error::UnrecognizedPurpose(84));
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_and_origin_rejects_unhardened_purpose() {
+        let origin_path = DerivationPath::from(vec![ChildNumber::Normal { index: 86 }]);
+        let result = generate_descriptor_from_xpub_and_origin(XPUB, &origin_path, false);
+        assert_eq!(result.err().unwrap(), Error::MissingHardenedPurpose);
+    }
+
+    #[test]
+    fn test_generate_descriptor_from_xpub_and_origin_rejects_empty_path() {
+        let origin_path = DerivationPath::from(vec![]);
+        let result = generate_descriptor_from_xpub_and_origin(XPUB, &origin_path, false);
+        assert_eq!(result.err().unwrap(), Error::MissingHardenedPurpose);
+    }
 }
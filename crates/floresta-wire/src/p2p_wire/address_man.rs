@@ -2,14 +2,19 @@
 //! metadata. This module is very important in keeping our node protected against targeted
 //! attacks, like eclipse attacks.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::read_to_string;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
 use std::net::SocketAddr;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
@@ -26,9 +31,28 @@ use tracing::error;
 use tracing::info;
 use tracing::warn;
 
-/// How long we'll wait before trying to connect to a peer that failed
+use super::peer_store::PeerStore;
+
+/// The base delay we'll wait before retrying a peer that failed, before backoff is applied
 const RETRY_TIME: u64 = 10 * 60; // 10 minutes
 
+/// The maximum delay we'll ever wait before retrying a peer, no matter how many times it failed
+const MAX_RETRY_TIME: u64 = 6 * 60 * 60; // 6 hours
+
+/// Returns how long we should wait before retrying a peer whose failure state carries an
+/// attempt count of `attempts` (`0` on the first failure of a streak, see
+/// [`AddressMan::update_set_state`]), doubling the base [`RETRY_TIME`] on each further
+/// consecutive failure (capped at [`MAX_RETRY_TIME`]).
+///
+/// A fixed retry time lets a persistently dead or hostile peer keep occupying a reconnect slot
+/// at the same cadence as a peer that merely hiccupped once; exponential backoff lets us give up
+/// on it for longer while still retrying fresh failures quickly.
+fn backoff_duration(attempts: u32) -> u64 {
+    RETRY_TIME
+        .saturating_mul(1u64 << attempts.min(63))
+        .min(MAX_RETRY_TIME)
+}
+
 /// The minimum amount of addresses we need to have on the [`AddressMan`].
 const MIN_ADDRESSES: usize = 15;
 
@@ -42,9 +66,233 @@ const MIN_ADDRESSES_UTREEXO: usize = 2;
 /// and add it to the NeverTried bucket
 const ASSUME_STALE: u64 = 24 * 60 * 60; // 24 hours
 
+/// How often we're willing to re-resolve a given DNS seed, absent any urgent need for
+/// addresses. See [`AddressMan::maybe_refresh_seeds`].
+const SEED_REFRESH_INTERVAL: u64 = 5 * 60; // 5 minutes
+
 /// How many addresses we keep in our address manager
 const MAX_ADDRESSES: usize = 50_000;
 
+/// Upper bound on how many addresses a single [`AddressMan::push_addresses_from`] call will ever
+/// accept, regardless of how many are passed in.
+///
+/// Without this, one DNS-seed crawl or a single `addr`/`addrv2` gossip message from a peer could
+/// flood the table faster than [`AddressMan::prune_addresses`] can weed out stale entries,
+/// letting a single source dictate a large share of our view of the network. Bitcoin Core caps
+/// `addr`/`addrv2` messages at 1000 entries for the same reason, so we reuse that number here.
+const MAX_ADDRESSES_PER_PUSH: usize = 1000;
+
+/// The number of buckets used to group addresses we've never successfully connected to.
+///
+/// Bucketing addresses by network group (see [`network_group`]), rather than keeping a single
+/// flat pool, is how Bitcoin Core resists eclipse attacks: an attacker flooding us with
+/// addresses from a handful of IP ranges can only ever fill a handful of buckets.
+const NEW_BUCKET_COUNT: usize = 1024;
+
+/// The number of buckets used to group addresses we've successfully connected to before.
+const TRIED_BUCKET_COUNT: usize = 256;
+
+/// The number of slots in each new/tried bucket, mirroring Bitcoin Core's addrman.
+const BUCKET_SLOTS: usize = 64;
+
+/// The score floor every candidate gets, regardless of services or history.
+///
+/// Keeps an unexplored [`AddressState::NeverTried`] peer in the running for selection instead
+/// of starving it out entirely once we have richer, already-proven candidates: we still need to
+/// try new peers occasionally to discover which of them are any good.
+const SELECTION_FLOOR: f64 = 0.1;
+
+/// Default value for [`AddressMan::max_group_fraction`]: at most half of the outbound slots may
+/// come from a single diversity group.
+const DEFAULT_MAX_GROUP_FRACTION: f64 = 0.5;
+
+/// Scores a candidate address for weighted selection, taking the "preferable services get a
+/// boost" idea from parity-zcash's node table: each of our three bandwidth-saving services
+/// (UTREEXO, UTREEXO_FILTER, COMPACT_FILTERS) the peer advertises adds to its score, as does a
+/// recent, successful connection; repeated failures shrink it multiplicatively. See
+/// [`SELECTION_FLOOR`] for why the result never bottoms out at zero.
+fn score_address(address: &LocalAddress, now: u64) -> f64 {
+    const SERVICE_WEIGHT: f64 = 1.0;
+    const CONNECTED_BONUS: f64 = 3.0;
+    const TRIED_BONUS: f64 = 2.0;
+
+    let mut score = SELECTION_FLOOR;
+
+    for service in [
+        service_flags::UTREEXO.into(),
+        ServiceFlags::from(1 << 25), // UTREEXO_FILTER
+        ServiceFlags::COMPACT_FILTERS,
+    ] {
+        if address.services.has(service) {
+            score += SERVICE_WEIGHT;
+        }
+    }
+
+    match address.state {
+        AddressState::Connected => score += CONNECTED_BONUS,
+        AddressState::Tried(when) => {
+            let freshness = 1.0 - (now.saturating_sub(when) as f64 / ASSUME_STALE as f64).min(1.0);
+            score += TRIED_BONUS * freshness;
+        }
+        AddressState::Failed(_, attempts)
+        | AddressState::Timeout(_, attempts)
+        | AddressState::TimeoutAwaitingAddr(_, attempts)
+        | AddressState::TimeoutAwaitingBlock(_, attempts) => {
+            score /= f64::from(1u32 << attempts.min(30));
+        }
+        AddressState::NeverTried | AddressState::Banned(_) => {}
+        AddressState::ProtocolViolation(_)
+        | AddressState::BadVersion(_)
+        | AddressState::NotFullNode(_)
+        | AddressState::EvilNode(_) => {
+            score = 0.0;
+        }
+    }
+
+    score.max(SELECTION_FLOOR)
+}
+
+/// Picks an index into `scores` with probability proportional to its weight.
+///
+/// Returns `None` only if `scores` is empty or every weight is non-positive; in practice
+/// [`score_address`]'s floor means the latter never happens for real candidates.
+fn weighted_index(scores: &[f64]) -> Option<usize> {
+    let total: f64 = scores.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut pick = f64::from(rand::random::<u32>()) / f64::from(u32::MAX) * total;
+    for (idx, &score) in scores.iter().enumerate() {
+        if pick < score {
+            return Some(idx);
+        }
+        pick -= score;
+    }
+
+    scores.len().checked_sub(1)
+}
+
+/// Groups an address by its network, mirroring Bitcoin Core's `CNetAddr::GetGroup`.
+///
+/// Addresses sharing a group are assumed to be likely controlled by the same entity (e.g. the
+/// same /16 IPv4 range, or the same /32 IPv6 range), so we spread them across buckets instead
+/// of letting one group dominate our address table. Tor/I2P/CJDNS addresses each get their own
+/// group (the full key), since unlike an IP range, there's no cheaper way for one entity to
+/// control a whole neighborhood of them.
+fn network_group(address: &AddrV2) -> Vec<u8> {
+    match address {
+        AddrV2::Ipv4(ip) => {
+            let octets = ip.octets();
+            vec![1, octets[0], octets[1]]
+        }
+        AddrV2::Ipv6(ip) => {
+            let octets = ip.octets();
+            vec![2, octets[0], octets[1], octets[2], octets[3]]
+        }
+        AddrV2::TorV3(key) => {
+            let mut group = vec![3];
+            group.extend_from_slice(key);
+            group
+        }
+        AddrV2::I2p(key) => {
+            let mut group = vec![4];
+            group.extend_from_slice(key);
+            group
+        }
+        AddrV2::Cjdns(ip) => {
+            let mut group = vec![5];
+            group.extend_from_slice(&ip.octets());
+            group
+        }
+        AddrV2::TorV2(key) => {
+            let mut group = vec![6];
+            group.extend_from_slice(key);
+            group
+        }
+        AddrV2::Unknown(network, key) => {
+            let mut group = vec![0, *network];
+            group.extend_from_slice(key);
+            group
+        }
+    }
+}
+
+/// One range in a bundled IPv4-to-ASN table, used by [`AddressMan::group_for`] to group
+/// addresses by network operator instead of the cruder [`network_group`] (a single ASN can, and
+/// often does, span many /16s).
+#[derive(Clone)]
+struct AsnRange {
+    start: u32,
+    end: u32,
+    asn: u32,
+}
+
+/// Parses a bundled IP-to-ASN table, one range per line formatted as
+/// `<start_ipv4>,<end_ipv4>,<asn>` (the shape most public IP-to-ASN dumps already come in).
+///
+/// The table is entirely optional: a missing or unparseable file just yields an empty table, and
+/// [`AddressMan::group_for`] silently falls back to [`network_group`] for every address.
+fn load_asn_table(path: &str) -> Vec<AsnRange> {
+    let Ok(contents) = read_to_string(path) else {
+        return Vec::new();
+    };
+
+    let mut table: Vec<AsnRange> = contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',');
+            let start = fields.next()?.trim().parse::<Ipv4Addr>().ok()?;
+            let end = fields.next()?.trim().parse::<Ipv4Addr>().ok()?;
+            let asn = fields.next()?.trim().parse::<u32>().ok()?;
+
+            Some(AsnRange {
+                start: u32::from(start),
+                end: u32::from(end),
+                asn,
+            })
+        })
+        .collect();
+
+    table.sort_by_key(|range| range.start);
+    table
+}
+
+/// Combines a secret key with arbitrary byte segments into a single hash.
+///
+/// Every bucket/slot index derived from this is unpredictable to anyone who doesn't know our
+/// `secret_key`, so an attacker can't compute in advance which bucket/slot a given address (or
+/// one they control) will land in, and therefore can't deliberately collide with and evict our
+/// existing entries.
+fn keyed_hash(secret_key: u64, segments: &[&[u8]]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    secret_key.hash(&mut hasher);
+    for segment in segments {
+        segment.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// A stand-in for an address's raw bytes, used only to derive bucket/slot hashes - not a wire
+/// format.
+fn address_bytes(address: &AddrV2) -> Vec<u8> {
+    format!("{address:?}").into_bytes()
+}
+
+/// Collapses an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) into its real [`AddrV2::Ipv4`]
+/// form. Some peers and proxies hand us addresses in this shape; left alone, we'd treat the
+/// same peer as two different table entries and log the noisier IPv6 form for no benefit.
+fn canonicalize(address: AddrV2) -> AddrV2 {
+    if let AddrV2::Ipv6(ip) = address {
+        if let Some(ipv4) = ip.to_ipv4_mapped() {
+            return AddrV2::Ipv4(ipv4);
+        }
+    }
+
+    address
+}
+
 /// A type alias for a list of addresses to send to our peers
 type AddressToSend = Vec<(AddrV2, u64, ServiceFlags, u16)>;
 
@@ -67,8 +315,101 @@ pub enum AddressState {
     /// We are connected to this peer right now
     Connected,
 
-    /// We tried connecting, but failed
-    Failed(u64),
+    /// We tried connecting, but failed. The second field counts the consecutive failures after
+    /// the first one (`0` for a fresh failure), used to compute an exponential backoff (see
+    /// [`backoff_duration`]).
+    Failed(u64, u32),
+
+    /// The TCP connection itself timed out. Transient like [`Self::Failed`]: the second field
+    /// counts consecutive occurrences for backoff purposes.
+    Timeout(u64, u32),
+
+    /// We connected, but timed out waiting for a response to our `getaddr`.
+    TimeoutAwaitingAddr(u64, u32),
+
+    /// We connected, but timed out waiting for a requested block.
+    TimeoutAwaitingBlock(u64, u32),
+
+    /// The peer sent us a malformed or out-of-order message. Unlike the transient states above,
+    /// this doesn't carry an attempt count: we don't keep retrying a peer that broke protocol,
+    /// so there's no backoff to compute.
+    ProtocolViolation(u64),
+
+    /// The peer announced a protocol version we don't support.
+    BadVersion(u64),
+
+    /// The peer doesn't advertise the services we need (e.g. not a full node).
+    NotFullNode(u64),
+
+    /// The peer sent us something actively hostile (e.g. invalid blocks/proofs). The worst
+    /// rating a peer can have; see [`AddressMan::prune_addresses`].
+    EvilNode(u64),
+}
+
+impl AddressState {
+    /// A stable numeric tag for this variant, independent of its declaration order in this
+    /// enum. Unlike serde's default string tagging (which would still work fine for JSON), this
+    /// is what [`SqlitePeerStore`](super::peer_store::SqlitePeerStore) stores in its `state`
+    /// column, since that store doesn't go through serde for state transitions. Once assigned, a
+    /// tag must never be reused for a different variant, or existing rows would silently change
+    /// meaning after an upgrade.
+    pub fn to_num(&self) -> u8 {
+        match self {
+            AddressState::NeverTried => 0,
+            AddressState::Tried(_) => 1,
+            AddressState::Banned(_) => 2,
+            AddressState::Connected => 3,
+            AddressState::Failed(_, _) => 4,
+            AddressState::Timeout(_, _) => 5,
+            AddressState::TimeoutAwaitingAddr(_, _) => 6,
+            AddressState::TimeoutAwaitingBlock(_, _) => 7,
+            AddressState::ProtocolViolation(_) => 8,
+            AddressState::BadVersion(_) => 9,
+            AddressState::NotFullNode(_) => 10,
+            AddressState::EvilNode(_) => 11,
+        }
+    }
+
+    /// Reconstructs a state from a [`Self::to_num`] tag plus its `when`/`attempts` columns.
+    /// An unrecognized tag (e.g. after downgrading past a version that added a new variant)
+    /// falls back to [`Self::NeverTried`], the same fallback the JSON-backed stores already use
+    /// for an unrecognized state string.
+    pub fn from_num(tag: u8, when: u64, attempts: u32) -> Self {
+        match tag {
+            1 => AddressState::Tried(when),
+            2 => AddressState::Banned(when),
+            3 => AddressState::Connected,
+            4 => AddressState::Failed(when, attempts),
+            5 => AddressState::Timeout(when, attempts),
+            6 => AddressState::TimeoutAwaitingAddr(when, attempts),
+            7 => AddressState::TimeoutAwaitingBlock(when, attempts),
+            8 => AddressState::ProtocolViolation(when),
+            9 => AddressState::BadVersion(when),
+            10 => AddressState::NotFullNode(when),
+            11 => AddressState::EvilNode(when),
+            _ => AddressState::NeverTried,
+        }
+    }
+
+    /// Breaks this state down into the `(tag, when, attempts)` triple every on-disk
+    /// representation needs to round-trip it via [`Self::from_num`]. `when`/`attempts` are `0`
+    /// for variants that don't carry them.
+    pub(crate) fn parts(&self) -> (u8, u64, u32) {
+        let tag = self.to_num();
+        match *self {
+            AddressState::NeverTried | AddressState::Connected => (tag, 0, 0),
+            AddressState::Tried(when)
+            | AddressState::Banned(when)
+            | AddressState::ProtocolViolation(when)
+            | AddressState::BadVersion(when)
+            | AddressState::NotFullNode(when)
+            | AddressState::EvilNode(when) => (tag, when, 0),
+            AddressState::Failed(when, attempts)
+            | AddressState::Timeout(when, attempts)
+            | AddressState::TimeoutAwaitingAddr(when, attempts)
+            | AddressState::TimeoutAwaitingBlock(when, attempts) => (tag, when, attempts),
+        }
+    }
 }
 
 /// All the networks we might receive addresses for
@@ -81,6 +422,135 @@ pub enum ReachableNetworks {
     CJDNS,
 }
 
+/// A single IPv4 or IPv6 CIDR range, used to build [`IpFilter::Cidr`] rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpCidr {
+    V4 { network: Ipv4Addr, prefix_len: u8 },
+    V6 { network: Ipv6Addr, prefix_len: u8 },
+}
+
+impl IpCidr {
+    /// Returns whether `ip` falls inside this range.
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self, ip) {
+            (
+                IpCidr::V4 {
+                    network,
+                    prefix_len,
+                },
+                IpAddr::V4(ip),
+            ) => {
+                let mask = mask_for(*prefix_len, 32) as u32;
+                (u32::from(*ip) & mask) == (u32::from(*network) & mask)
+            }
+            (
+                IpCidr::V6 {
+                    network,
+                    prefix_len,
+                },
+                IpAddr::V6(ip),
+            ) => {
+                let mask = mask_for(*prefix_len, 128);
+                (u128::from(*ip) & mask) == (u128::from(*network) & mask)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Builds a bit mask with the top `prefix_len` bits set, out of `width` total bits.
+fn mask_for(prefix_len: u8, width: u32) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        u128::MAX << (width - u32::from(prefix_len).min(width))
+    }
+}
+
+/// An address is malformed as a CIDR range (e.g. `192.168.0.0/33` or missing the `/prefix`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseCidrError;
+
+impl fmt::Display for ParseCidrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid CIDR range")
+    }
+}
+
+impl std::error::Error for ParseCidrError {}
+
+impl FromStr for IpCidr {
+    type Err = ParseCidrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = s.split_once('/').ok_or(ParseCidrError)?;
+        let prefix_len: u8 = prefix_len.parse().map_err(|_| ParseCidrError)?;
+        let addr: IpAddr = addr.parse().map_err(|_| ParseCidrError)?;
+
+        match addr {
+            IpAddr::V4(network) => {
+                if prefix_len > 32 {
+                    return Err(ParseCidrError);
+                }
+                Ok(IpCidr::V4 {
+                    network,
+                    prefix_len,
+                })
+            }
+            IpAddr::V6(network) => {
+                if prefix_len > 128 {
+                    return Err(ParseCidrError);
+                }
+                Ok(IpCidr::V6 {
+                    network,
+                    prefix_len,
+                })
+            }
+        }
+    }
+}
+
+/// A coarse policy rule used to build `allow_ips`/`deny_ips` lists for [`AddressMan`].
+///
+/// `Public`/`Private` let an operator allow or deny whole classes of addresses without having
+/// to enumerate CIDR ranges (`Private` mirrors the ranges `AddressMan` already treats as
+/// non-routable: RFC 1918, link-local, CGNAT, documentation ranges, and so on).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFilter {
+    /// Matches every address
+    All,
+    /// Matches only publicly routable addresses
+    Public,
+    /// Matches only private/reserved address ranges
+    Private,
+    /// Matches addresses inside a specific CIDR range
+    Cidr(IpCidr),
+}
+
+impl IpFilter {
+    fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpFilter::All => true,
+            IpFilter::Public => AddressMan::is_routable_ip(ip),
+            IpFilter::Private => !AddressMan::is_routable_ip(ip),
+            IpFilter::Cidr(cidr) => cidr.contains(ip),
+        }
+    }
+}
+
+impl FromStr for IpFilter {
+    type Err = ParseCidrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(IpFilter::All),
+            "public" => Ok(IpFilter::Public),
+            "private" => Ok(IpFilter::Private),
+            cidr => cidr.parse().map(IpFilter::Cidr),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// How do we store peers locally
 pub struct LocalAddress {
@@ -96,12 +566,21 @@ pub struct LocalAddress {
     port: u16,
     /// Random id for this peer
     pub id: usize,
+    /// A diversity group for peer-selection purposes: the ASN this address belongs to, if
+    /// known from a bundled IP-to-ASN table, otherwise the cheap [`network_group`] fallback.
+    ///
+    /// Distinct from the bucketing [`network_group`] used by [`AddressMan`]'s new/tried tables:
+    /// this one is about not connecting to too many peers behind the same network operator at
+    /// once (see [`AddressMan::get_address_to_connect`]), not about table eviction resistance.
+    /// Populated by [`AddressMan::group_for`] when an address is added to the table; empty
+    /// until then.
+    asn_group: Vec<u8>,
 }
 
 impl From<AddrV2> for LocalAddress {
     fn from(value: AddrV2) -> Self {
         LocalAddress {
-            address: value,
+            address: canonicalize(value),
             last_connected: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
@@ -110,6 +589,7 @@ impl From<AddrV2> for LocalAddress {
             services: ServiceFlags::NONE,
             port: 8333,
             id: rand::random::<usize>(),
+            asn_group: Vec::new(),
         }
     }
 }
@@ -117,12 +597,13 @@ impl From<AddrV2> for LocalAddress {
 impl From<AddrV2Message> for LocalAddress {
     fn from(value: AddrV2Message) -> Self {
         LocalAddress {
-            address: value.addr,
+            address: canonicalize(value.addr),
             last_connected: value.time.into(),
             state: AddressState::NeverTried,
             services: value.services,
             port: value.port,
             id: rand::random::<usize>(),
+            asn_group: Vec::new(),
         }
     }
 }
@@ -144,6 +625,7 @@ impl TryFrom<&str> for LocalAddress {
             SocketAddr::V4(ipv4) => AddrV2::Ipv4(*ipv4.ip()),
             SocketAddr::V6(ipv6) => AddrV2::Ipv6(*ipv6.ip()),
         };
+        let ip = canonicalize(ip);
 
         Ok(LocalAddress::new(
             ip,
@@ -177,6 +659,7 @@ impl LocalAddress {
             services,
             port,
             id,
+            asn_group: Vec::new(),
         }
     }
 
@@ -211,13 +694,92 @@ impl LocalAddress {
     pub fn get_address(&self) -> AddrV2 {
         self.address.clone()
     }
+
+    /// Returns a view of this address suitable for logging.
+    ///
+    /// By default the host/IP is redacted, showing only the network class and port, so that
+    /// log output and `peers.json` stay safe to attach to bug reports. Pass `reveal: true` to
+    /// print the address in full (see [`AddressMan::set_log_full_addresses`]).
+    pub fn loggable(&self, reveal: bool) -> RedactedAddress<'_> {
+        RedactedAddress {
+            address: &self.address,
+            port: self.port,
+            reveal,
+        }
+    }
+
+    /// Returns the services advertised by this peer, as of our last encounter with it.
+    pub fn get_services(&self) -> ServiceFlags {
+        self.services
+    }
+
+    /// Returns our current local state for this peer, as defined in [`AddressState`].
+    pub fn get_state(&self) -> AddressState {
+        self.state.clone()
+    }
+
+    /// Returns the last time we successfully connected to this peer, as a UNIX timestamp.
+    ///
+    /// Only meaningful if [`Self::get_state`] is [`AddressState::Tried`] or
+    /// [`AddressState::Connected`].
+    pub fn get_last_connected(&self) -> u64 {
+        self.last_connected
+    }
+
+    /// Overwrites our local state for this peer, as defined in [`AddressState`].
+    pub fn set_state(&mut self, state: AddressState) {
+        self.state = state;
+    }
+
+    /// Returns this address's ASN/network diversity group. See the field's doc comment.
+    pub fn get_asn_group(&self) -> &[u8] {
+        &self.asn_group
+    }
+}
+
+/// Formats a peer address for logs, masking the host/IP unless explicitly told to reveal it.
+///
+/// This mirrors the privacy-logging approach other node implementations take: we still show
+/// the network class and port (useful for debugging connectivity issues), but never the IP,
+/// onion address or I2P destination a user is actually talking to. Build one with
+/// [`LocalAddress::loggable`].
+pub struct RedactedAddress<'a> {
+    address: &'a AddrV2,
+    port: u16,
+    reveal: bool,
+}
+
+impl fmt::Display for RedactedAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.reveal {
+            return write!(f, "{:?}:{}", self.address, self.port);
+        }
+
+        match self.address {
+            AddrV2::Ipv4(_) => write!(f, "ipv4:xxx.xxx.xxx.xxx:{}", self.port),
+            AddrV2::Ipv6(_) => write!(f, "ipv6:[redacted]:{}", self.port),
+            AddrV2::TorV2(_) => write!(f, "torv2:<redacted>.onion:{}", self.port),
+            AddrV2::TorV3(_) => write!(f, "torv3:<redacted>.onion:{}", self.port),
+            AddrV2::I2p(_) => write!(f, "i2p:<redacted>.b32.i2p:{}", self.port),
+            AddrV2::Cjdns(_) => write!(f, "cjdns:[redacted]:{}", self.port),
+            AddrV2::Unknown(network, _) => {
+                write!(f, "unknown({network}):<redacted>:{}", self.port)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RedactedAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
 }
 
 #[derive(Clone)]
 /// A module that keeps track of known addresses and chooses addresses that our node can connect
 pub struct AddressMan {
     /// A map of all peers we know, mapping the address id to the actual address.
-    addresses: HashMap<usize, LocalAddress>,
+    pub(crate) addresses: HashMap<usize, LocalAddress>,
 
     /// All indexes of "good" addresses
     ///
@@ -241,6 +803,75 @@ pub struct AddressMan {
 
     /// The networks we can reach
     reachable_networks: HashSet<ReachableNetworks>,
+
+    /// Addresses we've never successfully connected to, bucketed by [`network_group`] and
+    /// slotted within each bucket, mirroring Bitcoin Core's addrman "new" table. See
+    /// [`Self::new_bucket_for`].
+    new_buckets: Vec<[Option<usize>; BUCKET_SLOTS]>,
+
+    /// Addresses we've successfully connected to before, bucketed by [`network_group`] and
+    /// slotted within each bucket, mirroring Bitcoin Core's addrman "tried" table. See
+    /// [`Self::tried_bucket_for`].
+    tried_buckets: Vec<[Option<usize>; BUCKET_SLOTS]>,
+
+    /// A key private to this [`AddressMan`], mixed into every bucket/slot hash.
+    ///
+    /// Without it, an attacker who knows our bucketing scheme could compute in advance which
+    /// bucket/slot an address of their choosing lands in, and deliberately collide with (and
+    /// evict) our existing entries. Randomly generated on [`Self::new`] and meant to be
+    /// persisted alongside peers.json so the layout survives restarts.
+    secret_key: u64,
+
+    /// Whether peer-address logging should show the full host/IP instead of a redacted form.
+    ///
+    /// Defaults to `false`, so log output and bug reports don't leak which peers a user
+    /// connects to. Operators can opt in with [`Self::set_log_full_addresses`].
+    log_full_addresses: bool,
+
+    /// Ids of peers the operator has pinned as trusted.
+    ///
+    /// Reserved peers are always eligible in [`Self::get_address_to_connect`] regardless of
+    /// their [`AddressState`] (unless already connected), are exempt from [`Self::prune_addresses`], and never drop out of
+    /// `good_addresses`.
+    reserved_peers: HashSet<usize>,
+
+    /// If non-empty, an address is only ever considered reachable if it matches at least one
+    /// of these rules. Checked in [`Self::is_net_reachable`], alongside `deny_ips`.
+    allow_ips: Vec<IpFilter>,
+
+    /// An address matching any of these rules is never considered reachable, even if it also
+    /// matches `allow_ips`.
+    deny_ips: Vec<IpFilter>,
+
+    /// If set, [`Self::get_address_to_connect`] only ever returns reserved peers. Useful for
+    /// locked-down deployments that should only ever talk to explicitly pinned peers.
+    reserved_only: bool,
+
+    /// The backing store used to incrementally persist peers, if any.
+    ///
+    /// When unset, persistence falls back to the all-at-once [`Self::dump_peers`]/
+    /// [`Self::start_addr_man`] JSON snapshot. When set via [`Self::set_peer_store`], every
+    /// state transition (connect/fail/ban) is written through immediately instead of waiting
+    /// for the next full dump, see [`PeerStore`].
+    peer_store: Option<Arc<dyn PeerStore + Send + Sync>>,
+
+    /// The last time (UNIX timestamp) we resolved each DNS seed, keyed by [`DnsSeed::seed`].
+    ///
+    /// Drives [`Self::maybe_refresh_seeds`]'s scheduling.
+    seed_last_resolved: HashMap<String, u64>,
+
+    /// An optional bundled IP-to-ASN table, loaded by [`Self::start_addr_man`] and consulted by
+    /// [`Self::group_for`]. Empty (and therefore a no-op) unless a table file is present.
+    asn_table: Vec<AsnRange>,
+
+    /// The largest fraction of the outbound slots [`Self::get_address_to_connect`] is willing to
+    /// fill from a single diversity group, in `(0.0, 1.0]`.
+    ///
+    /// Defaults to [`DEFAULT_MAX_GROUP_FRACTION`]. Configurable via
+    /// [`Self::set_max_group_fraction`] since how aggressively to spread connections is a
+    /// deployment choice: a node with very few outbound slots may need a looser cap just to find
+    /// enough diverse peers, while one with many slots can afford to be stricter.
+    max_group_fraction: f64,
 }
 
 impl AddressMan {
@@ -259,132 +890,631 @@ impl AddressMan {
             peers_by_service: HashMap::new(),
             max_size: max_size.unwrap_or(MAX_ADDRESSES),
             reachable_networks,
+            new_buckets: vec![[None; BUCKET_SLOTS]; NEW_BUCKET_COUNT],
+            tried_buckets: vec![[None; BUCKET_SLOTS]; TRIED_BUCKET_COUNT],
+            secret_key: rand::random(),
+            log_full_addresses: false,
+            reserved_peers: HashSet::new(),
+            allow_ips: Vec::new(),
+            deny_ips: Vec::new(),
+            reserved_only: false,
+            peer_store: None,
+            seed_last_resolved: HashMap::new(),
+            asn_table: Vec::new(),
+            max_group_fraction: DEFAULT_MAX_GROUP_FRACTION,
         }
     }
 
-    /// Add a new address to our list of known address
-    pub fn push_addresses(&mut self, addresses: &[LocalAddress]) {
-        for address in addresses {
-            let id = address.id;
-            // don't add addresses that don't have the minimum required services
-            if !address.services.has(ServiceFlags::WITNESS)
-                | !address.services.has(ServiceFlags::NETWORK_LIMITED)
-            {
-                continue;
-            }
+    /// Plugs in a [`PeerStore`] to incrementally persist peers as their state changes.
+    ///
+    /// Without one, persistence is all-at-once: [`Self::dump_peers`]/[`Self::start_addr_man`]
+    /// (de)serialize the whole peer table as a single `peers.json` snapshot. With one set, every
+    /// call to [`Self::push_addresses`], [`Self::update_set_state`] and
+    /// [`Self::update_set_service_flag`] also writes the affected peer through the store, so a
+    /// single connect/fail/ban doesn't require rewriting every other known peer.
+    pub fn set_peer_store(&mut self, store: Arc<dyn PeerStore + Send + Sync>) {
+        self.peer_store = Some(store);
+    }
 
-            // don't unreachable addresses
-            match address.address {
-                AddrV2::Ipv4(ipv4) => {
-                    if !Self::is_routable_ipv4(&ipv4) {
-                        continue;
-                    }
-                }
+    /// Writes `address` through the configured [`PeerStore`], if any, logging (but not
+    /// propagating) any failure — persistence is best-effort and must never block peer
+    /// selection.
+    fn persist_upsert(&self, address: &LocalAddress) {
+        let Some(store) = &self.peer_store else {
+            return;
+        };
 
-                AddrV2::Ipv6(ipv6) => {
-                    if !Self::is_routable_ipv6(&ipv6) {
-                        continue;
-                    }
-                }
+        if let Err(e) = store.upsert(address) {
+            warn!(
+                "Failed to persist peer {}: {e}",
+                address.loggable(self.log_full_addresses)
+            );
+        }
+    }
 
-                _ => {}
-            }
+    /// Removes a peer from the configured [`PeerStore`], if any, logging (but not propagating)
+    /// any failure — same best-effort semantics as [`Self::persist_upsert`].
+    fn persist_delete(&self, id: usize) {
+        let Some(store) = &self.peer_store else {
+            return;
+        };
 
-            // don't add addresses from networks we can't reach
-            if !self.is_net_reachable(address) {
-                continue;
-            }
+        if let Err(e) = store.delete(id) {
+            warn!("Failed to delete peer {id} from store: {e}");
+        }
+    }
 
-            if !address.is_routable() {
-                continue;
-            }
+    /// Writes a single state transition through the configured [`PeerStore`], if any.
+    fn persist_transition(&self, idx: usize, state: &AddressState) {
+        let Some(store) = &self.peer_store else {
+            return;
+        };
 
-            // don't add duplicate addresses
-            if self
-                .addresses
-                .values()
-                .any(|x| x.address == address.address)
-            {
-                continue;
+        let result = match *state {
+            AddressState::Tried(when) => store.mark_tried(idx, when),
+            AddressState::Banned(when) => store.mark_banned(idx, when),
+            AddressState::Failed(when, attempts) => store.mark_failed(idx, when, attempts),
+            AddressState::Timeout(when, attempts)
+            | AddressState::TimeoutAwaitingAddr(when, attempts)
+            | AddressState::TimeoutAwaitingBlock(when, attempts) => {
+                store.mark_state(idx, state.to_num(), when, attempts)
             }
+            AddressState::ProtocolViolation(when)
+            | AddressState::BadVersion(when)
+            | AddressState::NotFullNode(when)
+            | AddressState::EvilNode(when) => store.mark_state(idx, state.to_num(), when, 0),
+            AddressState::Connected | AddressState::NeverTried => self
+                .addresses
+                .get(&idx)
+                .map(|address| store.upsert(address))
+                .unwrap_or(Ok(())),
+        };
 
-            if let std::collections::hash_map::Entry::Vacant(e) = self.addresses.entry(id) {
-                e.insert(address.clone());
-                if Self::is_good_peer(address) {
-                    self.good_addresses.push(id);
-                }
-
-                self.push_if_has_service(address, service_flags::UTREEXO.into());
-                self.push_if_has_service(address, ServiceFlags::NONE); // this means any peer
-                self.push_if_has_service(address, ServiceFlags::COMPACT_FILTERS);
-            }
+        if let Err(e) = result {
+            warn!("Failed to persist state transition for peer {idx}: {e}");
         }
+    }
 
-        // Open up space by pruning old addresses
-        self.prune_addresses();
+    /// Opts in to logging peer addresses in full instead of redacted.
+    ///
+    /// Off by default; only enable this for local debugging, since it defeats the purpose of
+    /// [`RedactedAddress`] and makes log output/bug reports reveal which peers we talk to.
+    pub fn set_log_full_addresses(&mut self, log_full_addresses: bool) {
+        self.log_full_addresses = log_full_addresses;
     }
 
-    /// Check if we can reach this address based on our reachable networks
-    fn is_net_reachable(&self, address: &LocalAddress) -> bool {
-        match address.address {
-            AddrV2::Ipv4(_) => self.reachable_networks.contains(&ReachableNetworks::IPv4),
-            AddrV2::Ipv6(_) => self.reachable_networks.contains(&ReachableNetworks::IPv6),
-            AddrV2::TorV3(_) => self.reachable_networks.contains(&ReachableNetworks::TorV3),
-            AddrV2::I2p(_) => self.reachable_networks.contains(&ReachableNetworks::I2P),
-            _ => false,
-        }
+    /// Configures the allow-list of IP ranges/classes.
+    ///
+    /// An address matching a rule here is always reachable (barring `deny_ips`), even if it's
+    /// one the hard-coded martian/private filter would otherwise reject - e.g. an operator on a
+    /// private/regtest network can pass `IpFilter::Private` or an `IpFilter::Cidr` covering
+    /// RFC1918 to let those ranges through. If non-empty, an address that matches none of these
+    /// rules is never considered reachable.
+    pub fn set_allow_ips(&mut self, allow_ips: Vec<IpFilter>) {
+        self.allow_ips = allow_ips;
     }
 
-    /// Remove addresses that we last heard of, until we are under the limit
-    /// of addresses to keep.
-    fn prune_addresses(&mut self) {
-        let excess = self.addresses.len().saturating_sub(self.max_size);
-        if excess == 0 {
-            return;
-        }
+    /// Configures the deny-list of IP ranges/classes.
+    ///
+    /// An address matching any rule here is never considered reachable, even if it also
+    /// matches `allow_ips`.
+    pub fn set_deny_ips(&mut self, deny_ips: Vec<IpFilter>) {
+        self.deny_ips = deny_ips;
+    }
 
-        let mut oldest_ids: Vec<_> = self
-            .addresses
-            .iter()
-            .map(|(&id, addr)| (id, addr.last_connected))
-            .collect();
+    /// Restricts [`Self::get_address_to_connect`] to only ever return reserved peers.
+    ///
+    /// Useful for locked-down deployments that should only ever talk to explicitly pinned
+    /// peers.
+    pub fn set_reserved_only(&mut self, reserved_only: bool) {
+        self.reserved_only = reserved_only;
+    }
 
-        oldest_ids.sort_by_key(|&(_, last_connected)| last_connected);
+    /// Sets the largest fraction of outbound slots [`Self::get_address_to_connect`] may fill
+    /// from a single diversity group, clamped to `(0.0, 1.0]`.
+    ///
+    /// A node flooded with addresses from one network group (the cheap half of an eclipse
+    /// attack) should still only ever be allowed to occupy this share of our outbound slots,
+    /// regardless of how much of the address book it manages to take over.
+    pub fn set_max_group_fraction(&mut self, max_group_fraction: f64) {
+        self.max_group_fraction = max_group_fraction.clamp(f64::MIN_POSITIVE, 1.0);
+    }
 
-        for (oldest_id, _) in oldest_ids.into_iter().take(excess) {
-            self.addresses.remove(&oldest_id);
-            self.good_addresses.retain(|&x| x != oldest_id);
-            for peers in self.good_peers_by_service.values_mut() {
-                peers.retain(|&x| x != oldest_id);
-            }
-            for peers in self.peers_by_service.values_mut() {
-                peers.retain(|&x| x != oldest_id);
-            }
-        }
+    /// The maximum number of outbound connections allowed to share one diversity group out of
+    /// `max_outgoing_peers` total slots, given [`Self::max_group_fraction`].
+    ///
+    /// Always at least 1, so a single-slot node (or a too-strict fraction) can still connect to
+    /// somebody instead of locking itself out entirely.
+    fn group_cap(&self, max_outgoing_peers: usize) -> usize {
+        ((max_outgoing_peers as f64 * self.max_group_fraction).floor() as usize).max(1)
     }
 
-    /// Return addresses from the [`AddressMan`] filtered by their [`ServiceFlags`].
-    fn get_addresses_by_service(&self, service: ServiceFlags) -> Vec<LocalAddress> {
-        self.good_peers_by_service
-            .get(&service)
-            .map(|peer_ids| {
-                peer_ids
-                    .iter()
-                    .filter_map(|id| self.addresses.get(id).cloned())
-                    .collect()
-            })
-            .unwrap_or_default()
+    /// Adds a trusted, pinned peer.
+    ///
+    /// Unlike [`Self::push_addresses`], this bypasses the usual routability/service/IP-policy
+    /// filtering (an operator explicitly vouching for a peer overrides the usual eclipse
+    /// defenses), and the peer becomes always eligible in [`Self::get_address_to_connect`],
+    /// exempt from [`Self::prune_addresses`], and never drops out of `good_addresses`.
+    pub fn add_reserved_peer(&mut self, address: LocalAddress) {
+        let id = address.id;
+        self.reserved_peers.insert(id);
+        self.addresses.insert(id, address);
+
+        if !self.good_addresses.contains(&id) {
+            self.good_addresses.push(id);
+        }
     }
 
-    /// Check if we have enough addresses on the address manager.
-    #[rustfmt::skip]
-    pub fn enough_addresses(&self) -> bool {
-        if self.good_addresses.len() < MIN_ADDRESSES{
+    /// Checks `allow_ips`/`deny_ips`, falling back to the hard-coded martian/private/special-use
+    /// filter ([`Self::is_routable_ip`]), for whether `ip` should be considered reachable.
+    ///
+    /// `deny_ips` always wins, even over an `allow_ips` match. Otherwise, an explicit
+    /// `allow_ips` match is reachable regardless of [`Self::is_routable_ip`] - this is what lets
+    /// an operator on a private/regtest network permit RFC1918 (etc.) ranges that would
+    /// otherwise be rejected outright. Past that, if `allow_ips` is non-empty it acts as a
+    /// strict allowlist (anything not matching it is unreachable); if it's empty, reachability
+    /// falls back to [`Self::is_routable_ip`], our usual public-only default.
+    fn ip_policy_allows(&self, ip: &IpAddr) -> bool {
+        if self.deny_ips.iter().any(|rule| rule.matches(ip)) {
             return false;
         }
 
-        if self.get_addresses_by_service(ServiceFlags::COMPACT_FILTERS).len() < MIN_ADDRESSES_CBF {
-            return false;
+        if !self.allow_ips.is_empty() {
+            return self.allow_ips.iter().any(|rule| rule.matches(ip));
+        }
+
+        Self::is_routable_ip(ip)
+    }
+
+    /// Returns the next reserved peer we aren't already connected to, if any.
+    fn next_reserved_peer(&self) -> Option<(usize, LocalAddress)> {
+        self.reserved_peers.iter().find_map(|&id| {
+            let address = self.addresses.get(&id)?;
+            if matches!(address.state, AddressState::Connected) {
+                return None;
+            }
+
+            Some((id, address.clone()))
+        })
+    }
+
+    /// Removes `id` from `good_addresses` and every service-indexed good-peer list, unless
+    /// it's a reserved peer (those never drop out of rotation).
+    fn evict_from_good(&mut self, id: usize) {
+        if self.reserved_peers.contains(&id) {
+            return;
+        }
+
+        self.good_addresses.retain(|&x| x != id);
+        for peers in self.good_peers_by_service.values_mut() {
+            peers.retain(|&x| x != id);
+        }
+    }
+
+    /// Returns the next consecutive-attempt count for `idx` transitioning into the
+    /// transient-failure state tagged `next_tag` (see [`AddressState::to_num`]): one more than
+    /// its current count if it's already in that same state, or `0` if this is a fresh failure
+    /// of that kind. See [`Self::update_set_state`].
+    fn next_attempt_count(&self, idx: usize, next_tag: u8) -> u32 {
+        let Some(previous) = self.addresses.get(&idx).map(|addr| &addr.state) else {
+            return 0;
+        };
+
+        if previous.to_num() != next_tag {
+            return 0;
+        }
+
+        match previous {
+            AddressState::Failed(_, attempts)
+            | AddressState::Timeout(_, attempts)
+            | AddressState::TimeoutAwaitingAddr(_, attempts)
+            | AddressState::TimeoutAwaitingBlock(_, attempts) => attempts + 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns `address`'s peer-selection diversity group: the ASN it belongs to, looked up in
+    /// the bundled [`Self::asn_table`] if one was loaded, otherwise the cheaper [`network_group`]
+    /// fallback.
+    ///
+    /// This is distinct from the bucketing `network_group` used to place addresses in the
+    /// new/tried tables: it's about not connecting to too many simultaneous outbound peers
+    /// behind the same network operator (see [`Self::get_address_to_connect`]), not about table
+    /// eviction resistance.
+    fn group_for(&self, address: &AddrV2) -> Vec<u8> {
+        if let AddrV2::Ipv4(ip) = address {
+            let ip = u32::from(*ip);
+            let hit = self
+                .asn_table
+                .binary_search_by(|range| {
+                    if ip < range.start {
+                        std::cmp::Ordering::Greater
+                    } else if ip > range.end {
+                        std::cmp::Ordering::Less
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                })
+                .ok();
+
+            if let Some(idx) = hit {
+                let mut group = vec![10];
+                group.extend_from_slice(&self.asn_table[idx].asn.to_be_bytes());
+                return group;
+            }
+        }
+
+        network_group(address)
+    }
+
+    /// Returns the "new" bucket `addr` (as relayed to us by `src`) would be placed in.
+    ///
+    /// Mirrors Bitcoin Core's addrman: `src`'s group picks one of 64 "source buckets", which is
+    /// then combined with `addr`'s own group to spread `addr` across [`NEW_BUCKET_COUNT`]
+    /// buckets. Folding in `src` means addresses relayed to us by different peers land in
+    /// different buckets even if the addresses themselves are in the same group, so a single
+    /// malicious peer can't steer everything it tells us into one bucket.
+    fn new_bucket_for(&self, addr: &AddrV2, src: &AddrV2) -> usize {
+        let src_group_bucket = keyed_hash(self.secret_key, &[&network_group(src)]) % 64;
+        let bucket = keyed_hash(
+            self.secret_key,
+            &[&network_group(addr), &src_group_bucket.to_le_bytes()],
+        );
+
+        (bucket % NEW_BUCKET_COUNT as u64) as usize
+    }
+
+    /// Returns the "tried" bucket a given address would be placed in.
+    ///
+    /// Mirrors Bitcoin Core's addrman: `addr`'s group picks one of 8 "group buckets", which is
+    /// then combined with the group again to spread `addr` across [`TRIED_BUCKET_COUNT`]
+    /// buckets.
+    fn tried_bucket_for(&self, addr: &AddrV2) -> usize {
+        let group = network_group(addr);
+        let group_bucket = keyed_hash(self.secret_key, &[&group]) % 8;
+        let bucket = keyed_hash(self.secret_key, &[&group, &group_bucket.to_le_bytes()]);
+
+        (bucket % TRIED_BUCKET_COUNT as u64) as usize
+    }
+
+    /// Returns the slot within `bucket` that `addr` would occupy.
+    fn slot_for(&self, bucket: usize, addr: &AddrV2) -> usize {
+        let slot = keyed_hash(
+            self.secret_key,
+            &[&bucket.to_le_bytes(), &address_bytes(addr)],
+        );
+
+        (slot % BUCKET_SLOTS as u64) as usize
+    }
+
+    /// Whether `address` is "terrible" enough that a bucket/slot collision should evict it in
+    /// favor of a new entry: one that's failed to connect, been banned, or gone stale (we once
+    /// connected to it, but not within [`ASSUME_STALE`]).
+    fn is_terrible(address: &LocalAddress, now: u64) -> bool {
+        if matches!(
+            address.state,
+            AddressState::Failed(_, _)
+                | AddressState::Banned(_)
+                | AddressState::Timeout(_, _)
+                | AddressState::TimeoutAwaitingAddr(_, _)
+                | AddressState::TimeoutAwaitingBlock(_, _)
+                | AddressState::ProtocolViolation(_)
+                | AddressState::BadVersion(_)
+                | AddressState::NotFullNode(_)
+                | AddressState::EvilNode(_)
+        ) {
+            return true;
+        }
+
+        address.last_connected != 0 && now.saturating_sub(address.last_connected) > ASSUME_STALE
+    }
+
+    /// Removes `id` from every new-table bucket/slot it occupies, if any.
+    ///
+    /// We don't track which `src` an entry was originally bucketed under, so unlike insertion,
+    /// removal has to scan every bucket; this is only done on relatively rare events (pruning,
+    /// banning, promotion to tried), not on the hot selection path.
+    fn remove_from_new_buckets(&mut self, id: usize) {
+        for bucket in self.new_buckets.iter_mut() {
+            for slot in bucket.iter_mut() {
+                if *slot == Some(id) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Removes `id` from every tried-table bucket/slot it occupies, if any. See
+    /// [`Self::remove_from_new_buckets`].
+    fn remove_from_tried_buckets(&mut self, id: usize) {
+        for bucket in self.tried_buckets.iter_mut() {
+            for slot in bucket.iter_mut() {
+                if *slot == Some(id) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Places `id` into its tried bucket/slot, evicting the incumbent if it's "terrible"
+    /// (demoting it back to the new table), or leaving `id` unplaced (but still promoted to
+    /// [`AddressState::Tried`]) if the incumbent is still worth keeping. See
+    /// [`Self::push_addresses_from`] for the analogous collision handling on the new table.
+    fn insert_into_tried(&mut self, id: usize) {
+        let Some(address) = self.addresses.get(&id) else {
+            return;
+        };
+
+        let bucket = self.tried_bucket_for(&address.address);
+        let slot = self.slot_for(bucket, &address.address);
+
+        if let Some(incumbent_id) = self.tried_buckets[bucket][slot] {
+            if incumbent_id == id {
+                return;
+            }
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let evict = self
+                .addresses
+                .get(&incumbent_id)
+                .map(|incumbent| Self::is_terrible(incumbent, now))
+                .unwrap_or(true);
+
+            if !evict {
+                return;
+            }
+
+            self.tried_buckets[bucket][slot] = None;
+            if let Some(incumbent) = self.addresses.get_mut(&incumbent_id) {
+                incumbent.state = AddressState::NeverTried;
+                let group = incumbent.address.clone();
+                let new_bucket = self.new_bucket_for(&group, &group);
+                let new_slot = self.slot_for(new_bucket, &group);
+                self.new_buckets[new_bucket][new_slot] = Some(incumbent_id);
+                self.persist_transition(incumbent_id, &AddressState::NeverTried);
+            }
+        }
+
+        self.tried_buckets[bucket][slot] = Some(id);
+    }
+
+    /// Add a new address to our list of known addresses, as if we learned about each of them
+    /// from the address itself (i.e. `src == address`).
+    ///
+    /// This is the right default for addresses we already trust the source of (our own seeds,
+    /// fixed addresses, a peers.json/PeerStore reload). For addresses relayed to us by another
+    /// peer, use [`Self::push_addresses_from`] instead, so the relaying peer is folded into the
+    /// new-bucket placement (see [`Self::new_bucket_for`]).
+    pub fn push_addresses(&mut self, addresses: &[LocalAddress]) {
+        if addresses.len() > MAX_ADDRESSES_PER_PUSH {
+            warn!(
+                "Dropping {} addresses from a single push (cap is {MAX_ADDRESSES_PER_PUSH})",
+                addresses.len() - MAX_ADDRESSES_PER_PUSH
+            );
+        }
+
+        for address in addresses.iter().take(MAX_ADDRESSES_PER_PUSH) {
+            let src = address.address.clone();
+            self.push_addresses_from(std::slice::from_ref(address), &src);
+        }
+    }
+
+    /// Add a new address to our list of known addresses, as relayed to us by `src`.
+    pub fn push_addresses_from(&mut self, addresses: &[LocalAddress], src: &AddrV2) {
+        if addresses.len() > MAX_ADDRESSES_PER_PUSH {
+            warn!(
+                "Dropping {} addresses from a single push (cap is {MAX_ADDRESSES_PER_PUSH})",
+                addresses.len() - MAX_ADDRESSES_PER_PUSH
+            );
+        }
+
+        for address in addresses.iter().take(MAX_ADDRESSES_PER_PUSH) {
+            // We don't know anything about how to group or dial an address of an unrecognized
+            // network, so there's nothing useful we could do with it; drop it outright instead
+            // of silently rewriting it into a fabricated address further down the pipeline.
+            if matches!(address.address, AddrV2::Unknown(_, _)) {
+                continue;
+            }
+
+            // Canonicalize up front (e.g. collapse an IPv4-mapped `::ffff:a.b.c.d` IPv6 address
+            // into plain `a.b.c.d`), so every check and the dedupe below see the same identity
+            // for a host regardless of which form it was relayed to us in.
+            let mut address = address.clone();
+            address.address = canonicalize(address.address);
+
+            let id = address.id;
+            // don't add addresses that don't have the minimum required services
+            if !address.services.has(ServiceFlags::WITNESS)
+                | !address.services.has(ServiceFlags::NETWORK_LIMITED)
+            {
+                continue;
+            }
+
+            // don't add addresses from networks we can't reach, or that our IP policy
+            // (`allow_ips`/`deny_ips`) rejects
+            if !self.is_net_reachable(&address) {
+                continue;
+            }
+
+            // don't add duplicate addresses (by canonical address + port)
+            if self
+                .addresses
+                .values()
+                .any(|x| x.address == address.address && x.port == address.port)
+            {
+                continue;
+            }
+
+            if let std::collections::hash_map::Entry::Vacant(e) = self.addresses.entry(id) {
+                let bucket = self.new_bucket_for(&address.address, src);
+                let slot = self.slot_for(bucket, &address.address);
+
+                // If the slot is already occupied, only evict the incumbent (making room for
+                // the new address) if it's "terrible"; otherwise the new address is dropped.
+                // This is what actually bounds an attacker's ability to fill our table: they
+                // can't just evict good entries by flooding us with addresses.
+                if let Some(incumbent_id) = self.new_buckets[bucket][slot] {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+
+                    let evict = self
+                        .addresses
+                        .get(&incumbent_id)
+                        .map(|incumbent| Self::is_terrible(incumbent, now))
+                        .unwrap_or(true);
+
+                    if !evict {
+                        continue;
+                    }
+
+                    self.addresses.remove(&incumbent_id);
+                    self.evict_from_good(incumbent_id);
+                    for peers in self.peers_by_service.values_mut() {
+                        peers.retain(|&x| x != incumbent_id);
+                    }
+                    self.persist_delete(incumbent_id);
+                }
+
+                address.asn_group = self.group_for(&address.address);
+
+                e.insert(address.clone());
+                self.new_buckets[bucket][slot] = Some(id);
+                if Self::is_good_peer(&address) {
+                    self.good_addresses.push(id);
+                }
+
+                self.push_if_has_service(&address, service_flags::UTREEXO.into());
+                self.push_if_has_service(&address, ServiceFlags::NONE); // this means any peer
+                self.push_if_has_service(&address, ServiceFlags::COMPACT_FILTERS);
+                self.persist_upsert(&address);
+            }
+        }
+
+        // Open up space by pruning old addresses
+        self.prune_addresses();
+    }
+
+    /// Check if we can reach this address based on our reachable networks and IP policy.
+    fn is_net_reachable(&self, address: &LocalAddress) -> bool {
+        let network_reachable = match address.address {
+            AddrV2::Ipv4(_) => self.reachable_networks.contains(&ReachableNetworks::IPv4),
+            AddrV2::Ipv6(_) => self.reachable_networks.contains(&ReachableNetworks::IPv6),
+            AddrV2::TorV3(_) => self.reachable_networks.contains(&ReachableNetworks::TorV3),
+            AddrV2::I2p(_) => self.reachable_networks.contains(&ReachableNetworks::I2P),
+            AddrV2::Cjdns(_) => self.reachable_networks.contains(&ReachableNetworks::CJDNS),
+            _ => false,
+        };
+
+        if !network_reachable {
+            return false;
+        }
+
+        // The IP policy (and the hard-coded martian/private filter it can override) only makes
+        // sense for addresses that resolve to an actual IP.
+        match address.address {
+            AddrV2::Ipv4(ip) => self.ip_policy_allows(&IpAddr::V4(ip)),
+            AddrV2::Ipv6(ip) => self.ip_policy_allows(&IpAddr::V6(ip)),
+            _ => true,
+        }
+    }
+
+    /// Remove our least-valuable addresses, until we are under `max_size`.
+    ///
+    /// `Connected`/`Tried` peers and [`Self::reserved_peers`] (anchors) are always kept.
+    /// Everything else is evicted in tiers, worst first: `ProtocolViolation`/`EvilNode` (actively
+    /// hostile), then `Banned`, then `BadVersion`/`NotFullNode` (useless but not hostile), then
+    /// `Failed`/`Timeout*` past their backoff window (still worth a retry later, so only once
+    /// expired), then `NeverTried` ordered by oldest `last_connected`. We only fall through to
+    /// the next tier once the previous one is exhausted, so a banned or misbehaving peer is
+    /// always evicted before any never-tried one.
+    fn prune_addresses(&mut self) {
+        let excess = self.addresses.len().saturating_sub(self.max_size);
+        if excess == 0 {
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let evictable = |id: &usize, addr: &LocalAddress| -> Option<u64> {
+            if self.reserved_peers.contains(id) {
+                return None;
+            }
+
+            match addr.state {
+                AddressState::Connected | AddressState::Tried(_) => None,
+                AddressState::ProtocolViolation(_) | AddressState::EvilNode(_) => Some(0),
+                AddressState::Banned(_) => Some(1),
+                AddressState::BadVersion(_) | AddressState::NotFullNode(_) => Some(2),
+                AddressState::Failed(when, attempts)
+                | AddressState::Timeout(when, attempts)
+                | AddressState::TimeoutAwaitingAddr(when, attempts)
+                | AddressState::TimeoutAwaitingBlock(when, attempts) => {
+                    if when + backoff_duration(attempts) < now {
+                        Some(3)
+                    } else {
+                        None
+                    }
+                }
+                AddressState::NeverTried => Some(4),
+            }
+        };
+
+        let mut candidates: Vec<_> = self
+            .addresses
+            .iter()
+            .filter_map(|(&id, addr)| {
+                evictable(&id, addr).map(|tier| (tier, addr.last_connected, id))
+            })
+            .collect();
+
+        candidates.sort();
+
+        for (_, _, id) in candidates.into_iter().take(excess) {
+            self.addresses.remove(&id);
+            self.good_addresses.retain(|&x| x != id);
+            for peers in self.good_peers_by_service.values_mut() {
+                peers.retain(|&x| x != id);
+            }
+            for peers in self.peers_by_service.values_mut() {
+                peers.retain(|&x| x != id);
+            }
+            self.remove_from_new_buckets(id);
+            self.remove_from_tried_buckets(id);
+            self.persist_delete(id);
+        }
+    }
+
+    /// Return addresses from the [`AddressMan`] filtered by their [`ServiceFlags`].
+    fn get_addresses_by_service(&self, service: ServiceFlags) -> Vec<LocalAddress> {
+        self.good_peers_by_service
+            .get(&service)
+            .map(|peer_ids| {
+                peer_ids
+                    .iter()
+                    .filter_map(|id| self.addresses.get(id).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check if we have enough addresses on the address manager.
+    #[rustfmt::skip]
+    pub fn enough_addresses(&self) -> bool {
+        if self.good_addresses.len() < MIN_ADDRESSES{
+            return false;
+        }
+
+        if self.get_addresses_by_service(ServiceFlags::COMPACT_FILTERS).len() < MIN_ADDRESSES_CBF {
+            return false;
         }
 
         if self.get_addresses_by_service(service_flags::UTREEXO.into()).len() < MIN_ADDRESSES_UTREEXO {
@@ -486,6 +1616,15 @@ impl AddressMan {
         true
     }
 
+    /// Classifies any [`IpAddr`] (v4 or v6) as publicly routable or not; used by [`IpFilter`]'s
+    /// `Public`/`Private` rules.
+    fn is_routable_ip(ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => Self::is_routable_ipv4(ip),
+            IpAddr::V6(ip) => Self::is_routable_ipv6(ip),
+        }
+    }
+
     fn push_if_has_service(&mut self, address: &LocalAddress, service: ServiceFlags) {
         if address.services.has(service) {
             if Self::is_good_peer(address) {
@@ -520,7 +1659,12 @@ impl AddressMan {
         addresses
     }
 
-    fn do_lookup(host: &str, default_port: u16, socks5: Option<SocketAddr>) -> Vec<LocalAddress> {
+    fn do_lookup(
+        host: &str,
+        default_port: u16,
+        socks5: Option<SocketAddr>,
+        log_full_addresses: bool,
+    ) -> Vec<LocalAddress> {
         let ips = match socks5 {
             Some(proxy) => {
                 debug!("Performing DNS lookup for host: {host}, using SOCKS5 proxy: {proxy}");
@@ -549,6 +1693,10 @@ impl AddressMan {
         let mut addresses = Vec::new();
         for ip in ips {
             if let Ok(ip) = LocalAddress::try_from(format!("{ip}:{default_port}").as_str()) {
+                debug!(
+                    "Resolved peer address from {host}: {}",
+                    ip.loggable(log_full_addresses)
+                );
                 addresses.push(ip);
             }
         }
@@ -560,13 +1708,14 @@ impl AddressMan {
         seed: &DnsSeed,
         default_port: u16,
         socks5: Option<SocketAddr>,
+        log_full_addresses: bool,
     ) -> Result<Vec<LocalAddress>, std::io::Error> {
         let mut addresses = Vec::new();
 
         // ask for utreexo peers (if filtering is available)
         if seed.filters.has(service_flags::UTREEXO.into()) {
             let host = format!("x1000000.{}", seed.seed);
-            let _addresses = Self::do_lookup(&host, default_port, socks5);
+            let _addresses = Self::do_lookup(&host, default_port, socks5, log_full_addresses);
             let _addresses = _addresses.into_iter().map(|mut x| {
                 x.services =
                     ServiceFlags::NETWORK | service_flags::UTREEXO.into() | ServiceFlags::WITNESS;
@@ -579,7 +1728,7 @@ impl AddressMan {
         // ask for compact filter peers (if filtering is available)
         if seed.filters.has(ServiceFlags::COMPACT_FILTERS) {
             let host = format!("x49.{}", seed.seed);
-            let _addresses = Self::do_lookup(&host, default_port, socks5);
+            let _addresses = Self::do_lookup(&host, default_port, socks5, log_full_addresses);
             let _addresses = _addresses.into_iter().map(|mut x| {
                 x.services =
                     ServiceFlags::COMPACT_FILTERS | ServiceFlags::NETWORK | ServiceFlags::WITNESS;
@@ -592,7 +1741,7 @@ impl AddressMan {
         // ask for any peer (if filtering is available)
         if seed.filters.has(ServiceFlags::WITNESS) {
             let host = format!("x9.{}", seed.seed);
-            let _addresses = Self::do_lookup(&host, default_port, socks5);
+            let _addresses = Self::do_lookup(&host, default_port, socks5, log_full_addresses);
             let _addresses = _addresses.into_iter().map(|mut x| {
                 x.services = ServiceFlags::NETWORK | ServiceFlags::WITNESS;
                 x
@@ -603,7 +1752,7 @@ impl AddressMan {
 
         // ask for any peer (if filtering isn't available)
         if seed.filters == ServiceFlags::NONE {
-            let _addresses = Self::do_lookup(seed.seed, default_port, socks5);
+            let _addresses = Self::do_lookup(seed.seed, default_port, socks5, log_full_addresses);
             let _addresses = _addresses.into_iter().map(|mut x| {
                 x.services = ServiceFlags::NETWORK | ServiceFlags::WITNESS;
                 x
@@ -621,15 +1770,43 @@ impl AddressMan {
     /// If no peers are known with the required service bit, we may return a random peer.
     /// Service bits are learned from DNS seeds or peer gossip and may be outdated or
     /// inaccurate, so we sometimes try random peers expecting they might implement the service.
+    ///
+    /// `connected_groups` should list the diversity group (see [`LocalAddress::get_asn_group`])
+    /// of every peer we're currently connected to, one entry per connection, so our outbound set
+    /// spreads across distinct network operators instead of clustering behind whichever one is
+    /// cheapest to flood us with addresses from. Pass an empty slice to select without this
+    /// constraint.
+    ///
+    /// `max_outgoing_peers` is the total number of outbound slots we're filling; together with
+    /// [`Self::max_group_fraction`] it bounds how many of `connected_groups`' entries are allowed
+    /// to share a single group before candidates from that group stop being preferred.
     pub fn get_address_to_connect(
         &mut self,
         required_service: ServiceFlags,
         feeler: bool,
+        connected_groups: &[Vec<u8>],
+        max_outgoing_peers: usize,
     ) -> Option<(usize, LocalAddress)> {
         if self.addresses.is_empty() {
             return None;
         }
 
+        // Reserved peers we aren't already connected to are always eligible, regardless of
+        // their current state, so that
+        // operators in locked-down deployments can keep a node talking to trusted peers no
+        // matter what the regular eviction/backoff logic below would otherwise decide.
+        if let Some(reserved) = self.next_reserved_peer() {
+            debug!(
+                "Selected reserved peer: {}",
+                reserved.1.loggable(self.log_full_addresses)
+            );
+            return Some(reserved);
+        }
+
+        if self.reserved_only {
+            return None;
+        }
+
         // Feeler connection are used to test if a peer is still alive, we don't care about
         // the features it supports or even if it's a valid peer. The only thing we care about
         // is that we haven't banned it.
@@ -645,16 +1822,34 @@ impl AddressMan {
                 return None;
             }
 
+            debug!(
+                "Selected feeler peer: {}",
+                address.loggable(self.log_full_addresses)
+            );
             return Some((*peer, address));
         };
 
+        let group_cap = self.group_cap(max_outgoing_peers);
         for _ in 0..10 {
             let (id, peer) = self
                 .get_address_by_service(required_service)
-                .or_else(|| self.get_random_address(required_service))?;
+                .filter(|(_, peer)| {
+                    let group_count = connected_groups
+                        .iter()
+                        .filter(|group| **group == peer.asn_group)
+                        .count();
+                    group_count < group_cap
+                })
+                .or_else(|| {
+                    self.get_random_address(required_service, connected_groups, max_outgoing_peers)
+                })?;
 
             match peer.state {
                 AddressState::NeverTried | AddressState::Tried(_) => {
+                    debug!(
+                        "Selected peer to connect: {}",
+                        peer.loggable(self.log_full_addresses)
+                    );
                     return Some((id, peer));
                 }
 
@@ -663,7 +1858,7 @@ impl AddressMan {
                     continue;
                 }
 
-                AddressState::Banned(when) | AddressState::Failed(when) => {
+                AddressState::Banned(when) => {
                     let now = SystemTime::now()
                         .duration_since(UNIX_EPOCH)
                         .unwrap()
@@ -673,11 +1868,32 @@ impl AddressMan {
                         return Some((id, peer));
                     }
 
-                    if let Some(peers) = self.good_peers_by_service.get_mut(&required_service) {
-                        peers.retain(|&x| x != id)
+                    self.evict_from_good(id);
+                }
+
+                AddressState::Failed(when, attempts)
+                | AddressState::Timeout(when, attempts)
+                | AddressState::TimeoutAwaitingAddr(when, attempts)
+                | AddressState::TimeoutAwaitingBlock(when, attempts) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+
+                    if when + backoff_duration(attempts) < now {
+                        return Some((id, peer));
                     }
 
-                    self.good_addresses.retain(|&x| x != id);
+                    self.evict_from_good(id);
+                }
+
+                // Terminal ratings: we don't retry these on a timer, only `prune_addresses`
+                // eventually evicts them in favor of better candidates.
+                AddressState::ProtocolViolation(_)
+                | AddressState::BadVersion(_)
+                | AddressState::NotFullNode(_)
+                | AddressState::EvilNode(_) => {
+                    self.evict_from_good(id);
                 }
             }
         }
@@ -690,51 +1906,169 @@ impl AddressMan {
             .addresses
             .values()
             .cloned()
-            .map(Into::<DiskLocalAddress>::into)
+            .filter_map(|address| DiskLocalAddress::try_from(address).ok())
             .collect::<Vec<_>>();
         let peers = serde_json::to_string(&peers);
         if let Ok(peers) = peers {
             std::fs::write(datadir.to_owned() + "/peers.json", peers)?;
         }
+
+        // Persist the secret key alongside peers.json so bucket/slot placement survives a
+        // restart; bucket contents themselves don't need persisting, since they're a pure
+        // function of the known addresses plus this key. Losing the key is harmless (we just
+        // generate a fresh one and re-bucket); a failed write is still returned as an error.
+        std::fs::write(Self::secret_key_path(datadir), self.secret_key.to_string())?;
+
         Ok(())
     }
 
-    /// Dumps the connected utreexo peers to a file on dir `datadir/anchors.json` in json format `
-    /// inputs are the directory to save the file and the list of ids of the connected utreexo peers
-    pub fn dump_utreexo_peers(&self, datadir: &str, peers_id: &[usize]) -> std::io::Result<()> {
-        let addresses: Vec<DiskLocalAddress> = peers_id
-            .iter()
-            .filter_map(|id| Some(self.addresses.get(id)?.to_owned().into()))
-            .collect();
-        let addresses: Result<String, serde_json::Error> = serde_json::to_string(&addresses);
-        if let Ok(addresses) = addresses {
-            std::fs::write(datadir.to_owned() + "/anchors.json", addresses)?;
-        }
-        Ok(())
+    /// Path to the file that persists [`Self::secret_key`]. See [`Self::dump_peers`].
+    fn secret_key_path(datadir: &str) -> String {
+        format!("{datadir}/addrman_secret")
     }
 
-    fn get_address_by_service(&self, service: ServiceFlags) -> Option<(usize, LocalAddress)> {
-        let peers = self.good_peers_by_service.get(&service)?;
-        if peers.is_empty() {
-            return None;
+    /// Writes the whole peer table to `datadir/peers.bin`, a compact fixed-width binary format.
+    ///
+    /// Unlike [`Self::dump_peers`], this round-trips through a single file with no companion
+    /// `addrman_secret`: the header carries [`Self::secret_key`] directly, and each entry is a
+    /// fixed [`BINARY_RECORD_SIZE`]-byte record instead of a JSON object, so a table with hundreds
+    /// of thousands of entries is cheap to hold in memory and to rewrite on every save. Like
+    /// `dnsseed-rust`, the new table is written to a temp file and renamed into place, so a crash
+    /// mid-write leaves either the previous snapshot or the complete new one on disk, never a
+    /// truncated one.
+    ///
+    /// We deliberately don't persist bucket/slot placement: as with `dump_peers`, it's a pure
+    /// function of each address plus `secret_key`, both of which we do persist, so there's
+    /// nothing to gain from writing it out and keeping it in sync.
+    pub fn flush_to_disk(&self, datadir: &str) -> std::io::Result<()> {
+        let mut buf = Vec::with_capacity(BINARY_HEADER_SIZE + self.addresses.len() * BINARY_RECORD_SIZE);
+        buf.extend_from_slice(&BINARY_SNAPSHOT_MAGIC);
+        buf.push(BINARY_SNAPSHOT_VERSION);
+        buf.extend_from_slice(&self.secret_key.to_le_bytes());
+
+        for address in self.addresses.values() {
+            if let Some(record) = encode_binary_address(address) {
+                buf.extend_from_slice(&record);
+            }
         }
 
-        let idx = rand::random::<usize>() % peers.len();
-        let utreexo_peer = peers.get(idx)?;
+        let tmp_path = format!("{datadir}/peers.bin.tmp");
+        std::fs::write(&tmp_path, &buf)?;
+        std::fs::rename(&tmp_path, format!("{datadir}/peers.bin"))
+    }
+
+    /// Loads a snapshot written by [`Self::flush_to_disk`], restoring [`Self::secret_key`] and
+    /// pushing every decoded address through [`Self::push_addresses`]. Returns the number of
+    /// addresses decoded. A missing, truncated, or version-mismatched file is reported as an
+    /// `io::Error` rather than panicking, mirroring how [`Self::start_addr_man`] treats a missing
+    /// `peers.json`.
+    pub fn load_from_disk(&mut self, datadir: &str) -> std::io::Result<usize> {
+        let bytes = std::fs::read(format!("{datadir}/peers.bin"))?;
+
+        if bytes.len() < BINARY_HEADER_SIZE || bytes[0..4] != BINARY_SNAPSHOT_MAGIC[..] {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "peers.bin: not a recognized snapshot",
+            ));
+        }
+        if bytes[4] != BINARY_SNAPSHOT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "peers.bin: unsupported snapshot version",
+            ));
+        }
+
+        if (bytes.len() - BINARY_HEADER_SIZE) % BINARY_RECORD_SIZE != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "peers.bin: truncated snapshot",
+            ));
+        }
+
+        self.secret_key = u64::from_le_bytes(bytes[5..BINARY_HEADER_SIZE].try_into().unwrap());
+
+        let peers: Vec<LocalAddress> = bytes[BINARY_HEADER_SIZE..]
+            .chunks_exact(BINARY_RECORD_SIZE)
+            .filter_map(decode_binary_address)
+            .collect();
+
+        let count = peers.len();
+        self.push_addresses(&peers);
+
+        Ok(count)
+    }
+
+    /// Dumps the connected utreexo peers to a file on dir `datadir/anchors.json` in json format `
+    /// inputs are the directory to save the file and the list of ids of the connected utreexo peers
+    pub fn dump_utreexo_peers(&self, datadir: &str, peers_id: &[usize]) -> std::io::Result<()> {
+        let addresses: Vec<DiskLocalAddress> = peers_id
+            .iter()
+            .filter_map(|id| DiskLocalAddress::try_from(self.addresses.get(id)?.to_owned()).ok())
+            .collect();
+        let addresses: Result<String, serde_json::Error> = serde_json::to_string(&addresses);
+        if let Ok(addresses) = addresses {
+            std::fs::write(datadir.to_owned() + "/anchors.json", addresses)?;
+        }
+        Ok(())
+    }
+
+    /// Picks a known-good peer advertising `service`, weighted by [`score_address`] rather than
+    /// uniformly at random.
+    fn get_address_by_service(&self, service: ServiceFlags) -> Option<(usize, LocalAddress)> {
+        let peers = self.good_peers_by_service.get(&service)?;
+        if peers.is_empty() {
+            return None;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let scores: Vec<f64> = peers
+            .iter()
+            .map(|id| {
+                self.addresses
+                    .get(id)
+                    .map_or(0.0, |address| score_address(address, now))
+            })
+            .collect();
+
+        let idx = weighted_index(&scores)?;
+        let utreexo_peer = peers.get(idx)?;
 
         Some((*utreexo_peer, self.addresses.get(utreexo_peer)?.to_owned()))
     }
 
     pub fn start_addr_man(&mut self, datadir: String) -> Vec<LocalAddress> {
-        let persisted_peers = read_to_string(format!("{datadir}/peers.json"))
-            .map(|seeds| serde_json::from_str::<Vec<DiskLocalAddress>>(&seeds));
+        // Load the persisted secret key, if any, before bucketing any addresses below, so
+        // addresses land in the same buckets they occupied before the restart.
+        if let Ok(key) = read_to_string(Self::secret_key_path(&datadir)) {
+            if let Ok(key) = key.trim().parse::<u64>() {
+                self.secret_key = key;
+            }
+        }
 
-        if let Ok(Ok(peers)) = persisted_peers {
-            let peers = peers
-                .into_iter()
-                .map(Into::<LocalAddress>::into)
-                .collect::<Vec<_>>();
+        // Load the bundled IP-to-ASN table, if any, before pushing any addresses below, so every
+        // address gets its `asn_group` populated from it instead of the `network_group` fallback.
+        self.asn_table = load_asn_table(&format!("{datadir}/asn_table.csv"));
+
+        // Prefer the configured PeerStore, if any: it may hold more up-to-date state than a
+        // stale peers.json snapshot (see Self::set_peer_store).
+        let persisted_peers = match &self.peer_store {
+            Some(store) => store.load_all().map_err(|e| {
+                warn!("Failed to load peers from the configured PeerStore: {e}");
+            }),
+            None => read_to_string(format!("{datadir}/peers.json"))
+                .map_err(|_| ())
+                .and_then(|seeds| {
+                    serde_json::from_str::<Vec<DiskLocalAddress>>(&seeds)
+                        .map_err(|_| ())
+                        .map(|peers| peers.into_iter().map(Into::<LocalAddress>::into).collect())
+                }),
+        };
 
+        if let Ok(peers) = persisted_peers {
             self.push_addresses(&peers);
         }
 
@@ -774,34 +2108,130 @@ impl AddressMan {
                         address.state = AddressState::NeverTried;
                     }
                 }
-                AddressState::Failed(failed_time) => {
-                    if failed_time + RETRY_TIME < now {
+                AddressState::Failed(failed_time, attempts)
+                | AddressState::Timeout(failed_time, attempts)
+                | AddressState::TimeoutAwaitingAddr(failed_time, attempts)
+                | AddressState::TimeoutAwaitingBlock(failed_time, attempts) => {
+                    if failed_time + backoff_duration(attempts) < now {
                         address.state = AddressState::NeverTried;
                     }
                 }
-                AddressState::Connected | AddressState::NeverTried => {}
+                // Terminal ratings: unlike the transient states above, these don't expire on
+                // their own. A peer that broke protocol or sent us something hostile stays
+                // rated that way until evicted (see `prune_addresses`) rather than being quietly
+                // given another chance.
+                AddressState::Connected
+                | AddressState::NeverTried
+                | AddressState::ProtocolViolation(_)
+                | AddressState::BadVersion(_)
+                | AddressState::NotFullNode(_)
+                | AddressState::EvilNode(_) => {}
             }
         }
+
+        self.prune_addresses();
     }
 
-    /// Attempt to find one random peer that advertises the required service
+    /// Returns which of `seeds` are due for re-resolution at `now` (a UNIX timestamp), so the
+    /// address table keeps growing via DNS after the initial seed crawl instead of only through
+    /// gossip.
+    ///
+    /// Also runs [`Self::rearrange_buckets`], so a seed-refresh tick doubles as the point where
+    /// addresses we haven't heard from within [`ASSUME_STALE`] get actively demoted back to
+    /// [`AddressState::NeverTried`], instead of only happening lazily.
+    ///
+    /// Seeds are normally due once every [`SEED_REFRESH_INTERVAL`], but if we don't have
+    /// [`Self::enough_addresses`], or a service class (CBF/Utreexo) has fallen below its
+    /// `MIN_ADDRESSES_*` threshold, every seed is considered due immediately.
+    ///
+    /// Seeds returned here are marked as resolved as of `now`, as if the caller had already
+    /// re-resolved them - callers are expected to actually do so right after.
+    pub fn maybe_refresh_seeds<'a>(&mut self, seeds: &'a [DnsSeed], now: u64) -> Vec<&'a DnsSeed> {
+        self.rearrange_buckets();
+
+        let urgent = !self.enough_addresses()
+            || self
+                .get_addresses_by_service(ServiceFlags::COMPACT_FILTERS)
+                .len()
+                < MIN_ADDRESSES_CBF
+            || self
+                .get_addresses_by_service(service_flags::UTREEXO.into())
+                .len()
+                < MIN_ADDRESSES_UTREEXO;
+
+        let interval = if urgent { 0 } else { SEED_REFRESH_INTERVAL };
+
+        let mut due = Vec::new();
+        for seed in seeds {
+            let last_resolved = self.seed_last_resolved.get(seed.seed).copied().unwrap_or(0);
+
+            if now.saturating_sub(last_resolved) >= interval {
+                self.seed_last_resolved.insert(seed.seed.to_string(), now);
+                due.push(seed);
+            }
+        }
+
+        due
+    }
+
+    /// The periodic job a node should run to keep discovering peers via DNS: resolves whichever
+    /// of `seeds` [`Self::maybe_refresh_seeds`] says are due, then merges every address found
+    /// into the table via [`Self::push_addresses`].
+    ///
+    /// Callers (e.g. a node's maintenance timer) are expected to invoke this on a regular tick
+    /// with a fresh `now`; `maybe_refresh_seeds`'s own interval/urgency logic decides whether any
+    /// given tick actually does network I/O. A seed whose lookup fails contributes no addresses
+    /// but doesn't stop the rest from being tried - same "log and move on" treatment
+    /// [`Self::do_lookup`] already gives individual lookup failures.
+    pub fn refresh_seeds(
+        &mut self,
+        seeds: &[DnsSeed],
+        now: u64,
+        default_port: u16,
+        socks5: Option<SocketAddr>,
+        log_full_addresses: bool,
+    ) {
+        let due = self.maybe_refresh_seeds(seeds, now);
+
+        for seed in &due {
+            match Self::get_seeds_from_dns(seed, default_port, socks5, log_full_addresses) {
+                Ok(addresses) => self.push_addresses(&addresses),
+                Err(e) => warn!("DNS seed lookup for {} failed: {e}", seed.seed),
+            }
+        }
+    }
+
+    /// Attempt to find one peer that advertises the required service, chosen with probability
+    /// proportional to [`score_address`] rather than uniformly, so a reliable peer we keep
+    /// reconnecting to is picked far more often than a never-tried or repeatedly-failing one.
     ///
     /// If we cannot find a peer that advertises the required service, we return any peer
     /// that we have in our list of known peers. Luckily, either we'll connect to a peer that has
     /// this but we didn't know, or one of those peers will give us useful addresses.
-    fn try_with_service(&self, service: ServiceFlags) -> Option<(usize, LocalAddress)> {
+    ///
+    /// `connected_groups` lists the diversity groups (see [`LocalAddress::get_asn_group`]) of
+    /// peers we're already connected to, one entry per connection. Candidates whose group has
+    /// already reached [`Self::group_cap`] are avoided, so our outbound set spreads across
+    /// network operators; if every eligible candidate would exceed the cap, it's dropped and any
+    /// eligible candidate is used.
+    fn try_with_service(
+        &self,
+        service: ServiceFlags,
+        connected_groups: &[Vec<u8>],
+        max_outgoing_peers: usize,
+    ) -> Option<(usize, LocalAddress)> {
         if let Some(peers) = self.peers_by_service.get(&service) {
             let peers = peers
                 .iter()
                 .filter(|&x| {
                     if let Some(address) = self.addresses.get(x) {
-                        if let AddressState::Failed(when) = address.state {
+                        if let AddressState::Failed(when, attempts) = address.state {
                             let now = SystemTime::now()
                                 .duration_since(UNIX_EPOCH)
                                 .unwrap()
                                 .as_secs();
 
-                            if (when + RETRY_TIME) < now {
+                            if (when + backoff_duration(attempts)) < now {
                                 return true;
                             }
                         }
@@ -818,35 +2248,192 @@ impl AddressMan {
                 return None;
             }
 
-            let idx = rand::random::<usize>() % peers.len();
-            let utreexo_peer = peers.get(idx)?;
+            let group_cap = self.group_cap(max_outgoing_peers);
+            let diverse = peers
+                .iter()
+                .copied()
+                .filter(|&x| {
+                    self.addresses.get(x).is_some_and(|address| {
+                        let group_count = connected_groups
+                            .iter()
+                            .filter(|group| **group == address.asn_group)
+                            .count();
+                        group_count < group_cap
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let pool = if diverse.is_empty() { &peers } else { &diverse };
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let scores: Vec<f64> = pool
+                .iter()
+                .map(|&&id| {
+                    self.addresses
+                        .get(&id)
+                        .map_or(0.0, |address| score_address(address, now))
+                })
+                .collect();
+
+            let idx = weighted_index(&scores)?;
+            let utreexo_peer = pool.get(idx)?;
             return Some((**utreexo_peer, self.addresses.get(utreexo_peer)?.to_owned()));
         }
 
         None
     }
 
-    fn get_random_address(&self, service: ServiceFlags) -> Option<(usize, LocalAddress)> {
+    /// Returns a random known address, preferring one whose diversity group hasn't yet reached
+    /// its share of `max_outgoing_peers`. See [`Self::try_with_service`].
+    fn get_random_address(
+        &self,
+        service: ServiceFlags,
+        connected_groups: &[Vec<u8>],
+        max_outgoing_peers: usize,
+    ) -> Option<(usize, LocalAddress)> {
         if self.addresses.is_empty() {
             return None;
         }
 
-        if let Some(address) = self.try_with_service(service) {
+        if let Some(address) = self.try_with_service(service, connected_groups, max_outgoing_peers)
+        {
             return Some(address);
         }
 
-        // if we can't find a peer that advertises the required service, get any peer
+        // if we can't find a peer that advertises the required service, fall back to picking
+        // any peer from the bucketed new/tried tables
+        if let Some(address) =
+            self.select_bucketed_address(connected_groups, max_outgoing_peers)
+        {
+            return Some(address);
+        }
+
+        // the diversity constraint exhausted the pool (or there's nothing bucketed yet); fall
+        // back to picking any known peer at all, regardless of group.
         let idx = rand::random::<usize>() % self.addresses.len();
         let peer = self.addresses.keys().nth(idx)?;
 
         Some((*peer, self.addresses.get(peer)?.to_owned()))
     }
 
+    /// Picks a random address out of the bucketed new/tried tables, mirroring Bitcoin Core's
+    /// addrman selection: flip a coin between the two tables (skewing towards whichever one is
+    /// non-empty if the other is empty), pick a uniformly random bucket/slot, and accept what we
+    /// find there with a probability that decays with its consecutive failure count. This is
+    /// what actually bounds an attacker's advantage from flooding us with addresses: since
+    /// bucket/slot placement is keyed (see [`Self::secret_key`]) and eviction only ever claims
+    /// "terrible" entries, they can at best win a proportional share of slots, not all of them.
+    ///
+    /// Candidates whose diversity group has already reached [`Self::group_cap`] among
+    /// `connected_groups` are skipped, so repeated calls (one per outbound slot we're filling)
+    /// tend to spread across network operators; if 100 tries can't turn up a candidate under the
+    /// cap, this gives up (the caller falls back to an unconstrained pick) rather than looping
+    /// indefinitely.
+    fn select_bucketed_address(
+        &self,
+        connected_groups: &[Vec<u8>],
+        max_outgoing_peers: usize,
+    ) -> Option<(usize, LocalAddress)> {
+        let group_cap = self.group_cap(max_outgoing_peers);
+        let new_count = self
+            .new_buckets
+            .iter()
+            .flatten()
+            .filter(|slot| slot.is_some())
+            .count();
+        let tried_count = self
+            .tried_buckets
+            .iter()
+            .flatten()
+            .filter(|slot| slot.is_some())
+            .count();
+
+        if new_count == 0 && tried_count == 0 {
+            return None;
+        }
+
+        for _ in 0..100 {
+            let use_tried = if new_count == 0 {
+                true
+            } else if tried_count == 0 {
+                false
+            } else {
+                rand::random::<bool>()
+            };
+
+            let table = if use_tried {
+                &self.tried_buckets
+            } else {
+                &self.new_buckets
+            };
+
+            let bucket = rand::random::<usize>() % table.len();
+            let slot = rand::random::<usize>() % BUCKET_SLOTS;
+
+            let Some(id) = table[bucket][slot] else {
+                continue;
+            };
+            let Some(address) = self.addresses.get(&id) else {
+                continue;
+            };
+
+            let attempts = match address.state {
+                AddressState::Failed(_, attempts) => attempts,
+                _ => 0,
+            };
+
+            // Accept unconditionally the first time; each consecutive failure halves the
+            // acceptance probability, so a persistently failing entry still occupying a slot
+            // gets picked less and less often without needing to be evicted outright.
+            let group_count = connected_groups
+                .iter()
+                .filter(|group| **group == address.asn_group)
+                .count();
+
+            let accept_probability = 1.0 / f64::from(1u32 << attempts.min(30));
+            if f64::from(rand::random::<u32>()) / f64::from(u32::MAX) < accept_probability
+                && group_count < group_cap
+            {
+                return Some((id, address.clone()));
+            }
+        }
+
+        None
+    }
+
     /// Updates the state of an address
     pub fn update_set_state(&mut self, idx: usize, state: AddressState) -> &mut Self {
+        if matches!(state, AddressState::Tried(_) | AddressState::Connected) {
+            self.remove_from_new_buckets(idx);
+            self.insert_into_tried(idx);
+        }
+
+        // Consecutive occurrences of any transient-failure kind accumulate the backoff; any
+        // other transition (including switching to a *different* transient-failure kind)
+        // resets the count.
+        let state = match state {
+            AddressState::Failed(when, _) => {
+                AddressState::Failed(when, self.next_attempt_count(idx, state.to_num()))
+            }
+            AddressState::Timeout(when, _) => {
+                AddressState::Timeout(when, self.next_attempt_count(idx, state.to_num()))
+            }
+            AddressState::TimeoutAwaitingAddr(when, _) => {
+                AddressState::TimeoutAwaitingAddr(when, self.next_attempt_count(idx, state.to_num()))
+            }
+            AddressState::TimeoutAwaitingBlock(when, _) => {
+                AddressState::TimeoutAwaitingBlock(when, self.next_attempt_count(idx, state.to_num()))
+            }
+            other => other,
+        };
+
         match state {
             AddressState::Banned(_) => {
-                self.good_addresses.retain(|&x| x != idx);
+                self.evict_from_good(idx);
             }
             AddressState::Tried(_) => {
                 if !self.good_addresses.contains(&idx) {
@@ -861,7 +2448,7 @@ impl AddressMan {
                 }
             }
             AddressState::NeverTried => {
-                self.good_addresses.retain(|&x| x != idx);
+                self.evict_from_good(idx);
             }
             AddressState::Connected => {
                 self.addresses.entry(idx).and_modify(|addr| {
@@ -875,18 +2462,24 @@ impl AddressMan {
                     self.good_addresses.push(idx);
                 }
             }
-            AddressState::Failed(_) => {
-                self.good_addresses.retain(|&x| x != idx);
-                for peers in self.good_peers_by_service.values_mut() {
-                    peers.retain(|&x| x != idx);
-                }
+            AddressState::Failed(_, _)
+            | AddressState::Timeout(_, _)
+            | AddressState::TimeoutAwaitingAddr(_, _)
+            | AddressState::TimeoutAwaitingBlock(_, _)
+            | AddressState::ProtocolViolation(_)
+            | AddressState::BadVersion(_)
+            | AddressState::NotFullNode(_)
+            | AddressState::EvilNode(_) => {
+                self.evict_from_good(idx);
             }
         }
 
         if let Some(address) = self.addresses.get_mut(&idx) {
-            address.state = state;
+            address.state = state.clone();
         };
 
+        self.persist_transition(idx, &state);
+
         self
     }
 
@@ -943,7 +2536,10 @@ impl AddressMan {
     /// Updates the service flags after we receive a version message
     pub fn update_set_service_flag(&mut self, idx: usize, flags: ServiceFlags) -> &mut Self {
         // if this peer turns out to not have the minimum required services, we remove it
-        if !flags.has(ServiceFlags::NETWORK) || !flags.has(ServiceFlags::WITNESS) {
+        // (reserved peers are exempt: we keep them around regardless of advertised services)
+        if (!flags.has(ServiceFlags::NETWORK) || !flags.has(ServiceFlags::WITNESS))
+            && !self.reserved_peers.contains(&idx)
+        {
             self.addresses.remove(&idx);
             for peers in self.peers_by_service.values_mut() {
                 peers.retain(|&x| x != idx);
@@ -954,6 +2550,7 @@ impl AddressMan {
                 .values_mut()
                 .for_each(|peers| peers.retain(|&x| x != idx));
 
+            self.persist_delete(idx);
             return self;
         }
 
@@ -962,6 +2559,10 @@ impl AddressMan {
         }
 
         self.update_peer_services_buckets(idx);
+        if let Some(address) = self.addresses.get(&idx) {
+            self.persist_upsert(address);
+        }
+
         self
     }
 
@@ -1010,19 +2611,25 @@ pub struct DiskLocalAddress {
     id: Option<usize>,
 }
 
-impl From<LocalAddress> for DiskLocalAddress {
-    fn from(value: LocalAddress) -> Self {
-        let address = match value.address {
+impl TryFrom<LocalAddress> for DiskLocalAddress {
+    /// [`Address`] has no variant for an unrecognized `AddrV2::Unknown` network, so there's
+    /// nothing faithful we could persist for one. Rather than fabricating a bogus loopback
+    /// entry (as this used to do), conversion simply fails and the caller drops the address
+    /// instead of writing it to disk.
+    type Error = ();
+
+    fn try_from(value: LocalAddress) -> Result<Self, Self::Error> {
+        let address = match canonicalize(value.address) {
             AddrV2::Ipv4(ip) => Address::V4(ip),
             AddrV2::Ipv6(ip) => Address::V6(ip),
             AddrV2::Cjdns(ip) => Address::Cjdns(ip),
             AddrV2::I2p(ip) => Address::I2p(ip),
             AddrV2::TorV2(ip) => Address::OnionV2(ip),
             AddrV2::TorV3(ip) => Address::OnionV3(ip),
-            AddrV2::Unknown(_, _) => Address::V4(Ipv4Addr::LOCALHOST),
+            AddrV2::Unknown(_, _) => return Err(()),
         };
 
-        DiskLocalAddress {
+        Ok(DiskLocalAddress {
             address,
             last_connected: value.last_connected,
             state: if value.state == AddressState::Connected {
@@ -1038,7 +2645,7 @@ impl From<LocalAddress> for DiskLocalAddress {
             services: value.services.to_u64(),
             port: value.port,
             id: Some(value.id),
-        }
+        })
     }
 }
 impl From<DiskLocalAddress> for LocalAddress {
@@ -1059,6 +2666,7 @@ impl From<DiskLocalAddress> for LocalAddress {
             services,
             port: value.port,
             id: value.id.unwrap_or_else(rand::random::<usize>),
+            asn_group: Vec::new(),
         }
     }
 }
@@ -1079,6 +2687,137 @@ pub enum Address {
     I2p([u8; 32]),
 }
 
+/// Magic bytes identifying an [`AddressMan::flush_to_disk`] snapshot, checked by
+/// [`AddressMan::load_from_disk`].
+const BINARY_SNAPSHOT_MAGIC: [u8; 4] = *b"FADB";
+
+/// Version of the [`AddressMan::flush_to_disk`]/[`AddressMan::load_from_disk`] record layout.
+/// Bump this if the layout ever changes, so a snapshot from an older build is rejected outright
+/// instead of being misparsed.
+const BINARY_SNAPSHOT_VERSION: u8 = 1;
+
+/// Size, in bytes, of a [`AddressMan::flush_to_disk`] file's header: magic + version +
+/// `secret_key`.
+const BINARY_HEADER_SIZE: usize = 4 + 1 + 8;
+
+/// The widest address payload we store ([`Address::OnionV3`]/[`Address::I2p`], both 32 bytes);
+/// narrower variants are zero-padded to this width so every record has the same size.
+const BINARY_ADDR_SIZE: usize = 32;
+
+/// Fixed size, in bytes, of one encoded address record: a tag byte identifying the [`Address`]
+/// variant, its (padded) bytes, port, services, last_connected, and the state tag/when/attempts
+/// triple from [`AddressState::parts`].
+const BINARY_RECORD_SIZE: usize = 1 + BINARY_ADDR_SIZE + 2 + 8 + 8 + 1 + 8 + 4 + 8;
+
+/// Encodes `address` as a fixed-width [`BINARY_RECORD_SIZE`]-byte record for
+/// [`AddressMan::flush_to_disk`]. Returns `None` for an address [`DiskLocalAddress::try_from`]
+/// can't represent (an unrecognized `AddrV2::Unknown` network), the same case `dump_peers`
+/// silently drops.
+fn encode_binary_address(address: &LocalAddress) -> Option<[u8; BINARY_RECORD_SIZE]> {
+    let disk = DiskLocalAddress::try_from(address.clone()).ok()?;
+
+    let mut addr_bytes = [0u8; BINARY_ADDR_SIZE];
+    let tag: u8 = match disk.address {
+        Address::V4(ip) => {
+            addr_bytes[..4].copy_from_slice(&ip.octets());
+            0
+        }
+        Address::V6(ip) => {
+            addr_bytes[..16].copy_from_slice(&ip.octets());
+            1
+        }
+        Address::OnionV2(key) => {
+            addr_bytes[..10].copy_from_slice(&key);
+            2
+        }
+        Address::OnionV3(key) => {
+            addr_bytes.copy_from_slice(&key);
+            3
+        }
+        Address::Cjdns(ip) => {
+            addr_bytes[..16].copy_from_slice(&ip.octets());
+            4
+        }
+        Address::I2p(key) => {
+            addr_bytes.copy_from_slice(&key);
+            5
+        }
+    };
+
+    let (state_tag, state_when, state_attempts) = disk.state.parts();
+
+    let mut buf = [0u8; BINARY_RECORD_SIZE];
+    let mut offset = 0;
+
+    buf[offset] = tag;
+    offset += 1;
+    buf[offset..offset + BINARY_ADDR_SIZE].copy_from_slice(&addr_bytes);
+    offset += BINARY_ADDR_SIZE;
+    buf[offset..offset + 2].copy_from_slice(&disk.port.to_le_bytes());
+    offset += 2;
+    buf[offset..offset + 8].copy_from_slice(&disk.services.to_le_bytes());
+    offset += 8;
+    buf[offset..offset + 8].copy_from_slice(&disk.last_connected.to_le_bytes());
+    offset += 8;
+    buf[offset] = state_tag;
+    offset += 1;
+    buf[offset..offset + 8].copy_from_slice(&state_when.to_le_bytes());
+    offset += 8;
+    buf[offset..offset + 4].copy_from_slice(&state_attempts.to_le_bytes());
+    offset += 4;
+    buf[offset..offset + 8].copy_from_slice(&(disk.id.unwrap_or_default() as u64).to_le_bytes());
+
+    Some(buf)
+}
+
+/// Decodes one [`BINARY_RECORD_SIZE`]-byte record written by [`encode_binary_address`]. Returns
+/// `None` for an unrecognized address tag (e.g. a newer build's snapshot read by an older one),
+/// the same fallback [`AddressState::from_num`] uses for an unrecognized state tag.
+fn decode_binary_address(bytes: &[u8]) -> Option<LocalAddress> {
+    let tag = bytes[0];
+    let addr_bytes = &bytes[1..1 + BINARY_ADDR_SIZE];
+    let mut offset = 1 + BINARY_ADDR_SIZE;
+
+    let port = u16::from_le_bytes(bytes[offset..offset + 2].try_into().ok()?);
+    offset += 2;
+    let services = u64::from_le_bytes(bytes[offset..offset + 8].try_into().ok()?);
+    offset += 8;
+    let last_connected = u64::from_le_bytes(bytes[offset..offset + 8].try_into().ok()?);
+    offset += 8;
+    let state_tag = bytes[offset];
+    offset += 1;
+    let state_when = u64::from_le_bytes(bytes[offset..offset + 8].try_into().ok()?);
+    offset += 8;
+    let state_attempts = u32::from_le_bytes(bytes[offset..offset + 4].try_into().ok()?);
+    offset += 4;
+    let id = u64::from_le_bytes(bytes[offset..offset + 8].try_into().ok()?);
+
+    let address = match tag {
+        0 => AddrV2::Ipv4(Ipv4Addr::new(
+            addr_bytes[0],
+            addr_bytes[1],
+            addr_bytes[2],
+            addr_bytes[3],
+        )),
+        1 => AddrV2::Ipv6(Ipv6Addr::from(<[u8; 16]>::try_from(&addr_bytes[..16]).ok()?)),
+        2 => AddrV2::TorV2(<[u8; 10]>::try_from(&addr_bytes[..10]).ok()?),
+        3 => AddrV2::TorV3(<[u8; 32]>::try_from(addr_bytes).ok()?),
+        4 => AddrV2::Cjdns(Ipv6Addr::from(<[u8; 16]>::try_from(&addr_bytes[..16]).ok()?)),
+        5 => AddrV2::I2p(<[u8; 32]>::try_from(addr_bytes).ok()?),
+        _ => return None,
+    };
+
+    Some(LocalAddress {
+        address,
+        last_connected,
+        state: AddressState::from_num(state_tag, state_when, state_attempts),
+        services: ServiceFlags::from(services),
+        port,
+        id: id as usize,
+        asn_group: Vec::new(),
+    })
+}
+
 /// Simple implementation of a DNS-over-HTTPS (DoH) lookup routed through the SOCKS5 proxy
 pub mod dns_proxy {
     use std::net::IpAddr;
@@ -1086,15 +2825,18 @@ pub mod dns_proxy {
     use std::sync::Arc;
     use std::time::Duration;
 
+    use rand::seq::SliceRandom;
     use rustls::crypto;
     use serde::Deserialize;
+    use tracing::debug;
     use ureq::tls::TlsConfig;
     use ureq::tls::TlsProvider;
     use ureq::Agent;
     use ureq::Proxy;
 
+    /// JSON format shared by the DoH providers we know about (Google and Cloudflare both speak
+    /// this schema; see [Google's docs](https://developers.google.com/speed/public-dns/docs/doh/json#dns_response_in_json)).
     #[derive(Deserialize)]
-    /// JSON format from [Google's DoH API](https://developers.google.com/speed/public-dns/docs/doh/json#dns_response_in_json)
     struct DnsResponse {
         /// We only care about the "Answer" array
         #[serde(rename = "Answer")]
@@ -1111,14 +2853,115 @@ pub mod dns_proxy {
         record_type: u8,
     }
 
-    /// Lookup `host` by DNS-over-HTTPS (DoH) through a SOCKS5 proxy. Returns both A (IPv4)
-    /// and AAAA (IPv6) records. Only Google sees the actual DNS query but doesn't learn our IP.
+    /// One DoH provider's request shape and response parsing, kept independent of the others so
+    /// a change to one provider's API (a header it requires, a quirk in its JSON) can't affect
+    /// how we talk to the rest. See [`lookup_host_via_proxy`] for how providers are tried.
+    trait DohProvider {
+        /// Name used only for logging.
+        fn name(&self) -> &'static str;
+
+        /// Performs a single query for `record_type` (1=A, 28=AAAA), returning the IPs found in
+        /// the response's Answer section.
+        fn query(
+            &self,
+            agent: &Agent,
+            host: &str,
+            record_type: u8,
+        ) -> Result<Vec<IpAddr>, ureq::Error>;
+    }
+
+    /// [Google's DoH JSON API](https://developers.google.com/speed/public-dns/docs/doh/json).
+    struct GoogleDoh;
+
+    impl DohProvider for GoogleDoh {
+        fn name(&self) -> &'static str {
+            "dns.google"
+        }
+
+        fn query(
+            &self,
+            agent: &Agent,
+            host: &str,
+            record_type: u8,
+        ) -> Result<Vec<IpAddr>, ureq::Error> {
+            let url = format!("https://dns.google/resolve?name={host}&type={record_type}");
+            let mut response = agent.get(&url).call()?;
+            let dns_response: DnsResponse = response.body_mut().read_json()?;
+            Ok(answers_to_ips(dns_response, record_type))
+        }
+    }
+
+    /// [Cloudflare's DoH JSON API](https://developers.cloudflare.com/1.1.1.1/encryption/dns-over-https/make-api-requests/dns-json/).
+    /// Its response shape matches Google's, but it only returns JSON when explicitly asked for
+    /// it via the `accept` header (otherwise it answers in DNS wire format).
+    struct CloudflareDoh;
+
+    impl DohProvider for CloudflareDoh {
+        fn name(&self) -> &'static str {
+            "cloudflare-dns.com"
+        }
+
+        fn query(
+            &self,
+            agent: &Agent,
+            host: &str,
+            record_type: u8,
+        ) -> Result<Vec<IpAddr>, ureq::Error> {
+            let url =
+                format!("https://cloudflare-dns.com/dns-query?name={host}&type={record_type}");
+            let mut response = agent
+                .get(&url)
+                .header("accept", "application/dns-json")
+                .call()?;
+            let dns_response: DnsResponse = response.body_mut().read_json()?;
+            Ok(answers_to_ips(dns_response, record_type))
+        }
+    }
+
+    /// Filters a response's Answer section down to entries matching `record_type` (sanity) and
+    /// parses their "data" field into an [`IpAddr`], silently skipping anything unparsable.
+    fn answers_to_ips(dns_response: DnsResponse, record_type: u8) -> Vec<IpAddr> {
+        dns_response
+            .answers
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.record_type == record_type)
+            .filter_map(|entry| entry.data.parse().ok())
+            .collect()
+    }
+
+    /// Queries both A and AAAA records from a single provider, merging and deduplicating the
+    /// result.
+    fn query_provider(
+        provider: &dyn DohProvider,
+        agent: &Agent,
+        host: &str,
+    ) -> Result<Vec<IpAddr>, ureq::Error> {
+        let mut all_ips = Vec::new();
+        for record_type in [1u8, 28u8] {
+            let mut ips = provider.query(agent, host, record_type)?;
+            all_ips.append(&mut ips);
+        }
+
+        all_ips.sort();
+        all_ips.dedup();
+        Ok(all_ips)
+    }
+
+    /// Lookup `host` by DNS-over-HTTPS (DoH) through a SOCKS5 proxy. Tries our known providers
+    /// (currently Google and Cloudflare) in randomized order and returns the first one that
+    /// yields any A/AAAA records, so a single censored or compromised provider doesn't leave us
+    /// with no DNS at all. If every provider errors outright, the last error is returned; if
+    /// every provider answers but none has a record for `host`, an empty list is returned.
+    ///
+    /// Only the chosen provider ever sees the actual DNS query, and the proxy only ever sees a
+    /// TLS handshake to it, not our real IP.
     pub fn lookup_host_via_proxy(
         host: &str,
         proxy_addr: SocketAddr,
     ) -> Result<Vec<IpAddr>, ureq::Error> {
-        // Note: ureq does not implement "socks5h://", so this will resolve "dns.google" locally,
-        // but the Bitcoin DNS query remains encrypted. Only Google can see the query contents.
+        // Note: ureq does not implement "socks5h://", so this will resolve the provider's
+        // hostname locally, but the Bitcoin DNS query remains encrypted end-to-end to it.
         let proxy = Proxy::new(&format!("socks5://{proxy_addr}"))?;
 
         let crypto = Arc::new(crypto::aws_lc_rs::default_provider());
@@ -1134,37 +2977,26 @@ pub mod dns_proxy {
             .build()
             .into();
 
-        // We will perform two queries in sequence: type=1 (A) and type=28 (AAAA).
-        let mut all_ips = Vec::new();
-        for record_type in [1u8, 28u8] {
-            let mut ips = query(&agent, host, record_type)?;
-            all_ips.append(&mut ips);
-        }
-
-        Ok(all_ips)
-    }
-
-    // Helper function that performs a single DoH query for the given record_type.
-    fn query(agent: &Agent, host: &str, record_type: u8) -> Result<Vec<IpAddr>, ureq::Error> {
-        // Construct the DoH URL for the JSON API:
-        // https://developers.google.com/speed/public-dns/docs/secure-transports
-        let url = format!("https://dns.google/resolve?name={host}&type={record_type}");
-
-        // Send a GET over HTTPS. The proxy will only see Google's address and the TLS handshake.
-        let mut response = agent.get(&url).call()?;
-        let dns_response: DnsResponse = response.body_mut().read_json()?;
-
-        let answers = dns_response.answers.unwrap_or_default();
-
-        // Filter by record_type (sanity) and parse each "data" field into an IpAddr.
-        let mut result = Vec::new();
-        for entry in answers.into_iter().filter(|e| e.record_type == record_type) {
-            if let Ok(ip) = entry.data.parse() {
-                result.push(ip);
+        let mut providers: Vec<Box<dyn DohProvider>> =
+            vec![Box::new(GoogleDoh), Box::new(CloudflareDoh)];
+        providers.shuffle(&mut rand::thread_rng());
+
+        let mut last_err = None;
+        for provider in &providers {
+            match query_provider(provider.as_ref(), &agent, host) {
+                Ok(ips) if !ips.is_empty() => return Ok(ips),
+                Ok(_) => debug!("DoH provider {} has no records for {host}", provider.name()),
+                Err(e) => {
+                    debug!("DoH provider {} failed for {host}: {e}", provider.name());
+                    last_err = Some(e);
+                }
             }
         }
 
-        Ok(result)
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(Vec::new()),
+        }
     }
 }
 
@@ -1174,6 +3006,7 @@ mod test {
     use std::io::Read;
     use std::io::{self};
     use std::net::Ipv4Addr;
+    use std::sync::Arc;
     use std::time::SystemTime;
     use std::time::UNIX_EPOCH;
 
@@ -1187,8 +3020,12 @@ mod test {
     use serde::Deserialize;
     use serde::Serialize;
 
+    use super::super::peer_store::PeerStoreError;
     use super::AddressState;
+    use super::IpCidr;
+    use super::IpFilter;
     use super::LocalAddress;
+    use super::PeerStore;
     use crate::address_man::AddressMan;
     use crate::address_man::ReachableNetworks;
 
@@ -1237,6 +3074,7 @@ mod test {
                 services: ServiceFlags::from(seed.services),
                 port: seed.port,
                 id: rng.gen(),
+                asn_group: Vec::new(),
             };
             addresses.push(local_address);
         }
@@ -1332,19 +3170,19 @@ mod test {
         assert!(!address_man.get_addresses_to_send().is_empty());
 
         assert!(address_man
-            .get_address_to_connect(ServiceFlags::default(), true)
+            .get_address_to_connect(ServiceFlags::default(), true, &[], 8)
             .is_some());
 
         assert!(address_man
-            .get_address_to_connect(ServiceFlags::default(), false)
+            .get_address_to_connect(ServiceFlags::default(), false, &[], 8)
             .is_some());
 
         assert!(address_man
-            .get_address_to_connect(ServiceFlags::NONE, false)
+            .get_address_to_connect(ServiceFlags::NONE, false, &[], 8)
             .is_some());
 
         assert!(address_man
-            .get_address_to_connect(service_flags::UTREEXO.into(), false)
+            .get_address_to_connect(service_flags::UTREEXO.into(), false, &[], 8)
             .is_some());
 
         assert!(!AddressMan::get_net_seeds(Network::Signet).is_empty());
@@ -1356,6 +3194,7 @@ mod test {
             &get_chain_dns_seeds(Network::Signet)[0],
             8333,
             None, // No proxy
+            false,
         ));
 
         address_man.rearrange_buckets();
@@ -1470,6 +3309,7 @@ mod test {
             services: ServiceFlags::default(),
             port: 8333,
             id: 0,
+            asn_group: Vec::new(),
         }));
 
         assert!(address_man.is_net_reachable(&LocalAddress {
@@ -1479,6 +3319,7 @@ mod test {
             services: ServiceFlags::default(),
             port: 8333,
             id: 0,
+            asn_group: Vec::new(),
         }));
 
         assert!(!address_man.is_net_reachable(&LocalAddress {
@@ -1488,6 +3329,7 @@ mod test {
             services: ServiceFlags::default(),
             port: 8333,
             id: 0,
+            asn_group: Vec::new(),
         }));
 
         assert!(!address_man.is_net_reachable(&LocalAddress {
@@ -1497,6 +3339,7 @@ mod test {
             services: ServiceFlags::default(),
             port: 8333,
             id: 0,
+            asn_group: Vec::new(),
         }));
     }
 
@@ -1510,6 +3353,7 @@ mod test {
             services: ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED,
             port: 8333,
             id: 0,
+            asn_group: Vec::new(),
         };
 
         let v4_with_witness = LocalAddress {
@@ -1519,6 +3363,7 @@ mod test {
             services: ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED | ServiceFlags::WITNESS,
             port: 8333,
             id: 1,
+            asn_group: Vec::new(),
         };
 
         let v6_with_witness = LocalAddress {
@@ -1528,6 +3373,7 @@ mod test {
             services: ServiceFlags::NETWORK_LIMITED | ServiceFlags::NETWORK | ServiceFlags::WITNESS,
             port: 8333,
             id: 2,
+            asn_group: Vec::new(),
         };
 
         let v4_not_routable = LocalAddress {
@@ -1537,6 +3383,7 @@ mod test {
             services: ServiceFlags::NETWORK_LIMITED | ServiceFlags::NETWORK | ServiceFlags::WITNESS,
             port: 8333,
             id: 3,
+            asn_group: Vec::new(),
         };
 
         let v6_not_routable = LocalAddress {
@@ -1546,6 +3393,7 @@ mod test {
             services: ServiceFlags::NETWORK_LIMITED | ServiceFlags::NETWORK | ServiceFlags::WITNESS,
             port: 8333,
             id: 4,
+            asn_group: Vec::new(),
         };
 
         let onion = LocalAddress {
@@ -1559,6 +3407,7 @@ mod test {
             services: ServiceFlags::NETWORK_LIMITED | ServiceFlags::NETWORK | ServiceFlags::WITNESS,
             port: 8333,
             id: 5,
+            asn_group: Vec::new(),
         };
 
         let addresses = vec![
@@ -1598,6 +3447,154 @@ mod test {
         assert_ne!(address_man.addresses.len(), addresses.len());
     }
 
+    #[test]
+    fn test_prune_addresses_evicts_worst_tier_first() {
+        let mut address_man = AddressMan::new(Some(2), &[]);
+
+        let connected = LocalAddress {
+            address: AddrV2::Ipv4("12.146.182.45".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::Connected,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+        let never_tried = LocalAddress {
+            address: AddrV2::Ipv4("13.146.182.45".parse().unwrap()),
+            last_connected: 1,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 1,
+            asn_group: Vec::new(),
+        };
+        let banned = LocalAddress {
+            address: AddrV2::Ipv4("14.146.182.45".parse().unwrap()),
+            last_connected: 2,
+            state: AddressState::Banned(u64::MAX),
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 2,
+            asn_group: Vec::new(),
+        };
+
+        address_man.addresses.insert(connected.id, connected);
+        address_man.addresses.insert(never_tried.id, never_tried);
+        address_man.addresses.insert(banned.id, banned);
+
+        address_man.prune_addresses();
+
+        // the banned peer is the worst tier, so it's the one evicted to get back under the
+        // cap of 2, even though it was heard from more recently than `never_tried`
+        assert_eq!(address_man.addresses.len(), 2);
+        assert!(!address_man.addresses.contains_key(&2));
+        assert!(address_man.addresses.contains_key(&0));
+        assert!(address_man.addresses.contains_key(&1));
+    }
+
+    /// A [`PeerStore`] that just records which ids were deleted, so a test can assert that an
+    /// in-memory eviction was actually mirrored into the configured store.
+    #[derive(Default)]
+    struct RecordingPeerStore {
+        deleted: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl PeerStore for RecordingPeerStore {
+        fn load_all(&self) -> Result<Vec<LocalAddress>, PeerStoreError> {
+            Ok(Vec::new())
+        }
+
+        fn upsert(&self, _address: &LocalAddress) -> Result<(), PeerStoreError> {
+            Ok(())
+        }
+
+        fn delete(&self, id: usize) -> Result<(), PeerStoreError> {
+            self.deleted.lock().unwrap().push(id);
+            Ok(())
+        }
+
+        fn mark_tried(&self, _id: usize, _when: u64) -> Result<(), PeerStoreError> {
+            Ok(())
+        }
+
+        fn mark_failed(
+            &self,
+            _id: usize,
+            _when: u64,
+            _attempts: u32,
+        ) -> Result<(), PeerStoreError> {
+            Ok(())
+        }
+
+        fn mark_banned(&self, _id: usize, _when: u64) -> Result<(), PeerStoreError> {
+            Ok(())
+        }
+
+        fn mark_state(
+            &self,
+            _id: usize,
+            _tag: u8,
+            _when: u64,
+            _attempts: u32,
+        ) -> Result<(), PeerStoreError> {
+            Ok(())
+        }
+
+        fn iter_by_service(
+            &self,
+            _service: ServiceFlags,
+        ) -> Result<Vec<LocalAddress>, PeerStoreError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_prune_addresses_deletes_evicted_peers_from_the_peer_store() {
+        let mut address_man = AddressMan::new(Some(2), &[]);
+        let store = Arc::new(RecordingPeerStore::default());
+        address_man.set_peer_store(store.clone());
+
+        let connected = LocalAddress {
+            address: AddrV2::Ipv4("12.146.182.45".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::Connected,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+        let never_tried = LocalAddress {
+            address: AddrV2::Ipv4("13.146.182.45".parse().unwrap()),
+            last_connected: 1,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 1,
+            asn_group: Vec::new(),
+        };
+        let banned = LocalAddress {
+            address: AddrV2::Ipv4("14.146.182.45".parse().unwrap()),
+            last_connected: 2,
+            state: AddressState::Banned(u64::MAX),
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 2,
+            asn_group: Vec::new(),
+        };
+
+        address_man.addresses.insert(connected.id, connected);
+        address_man.addresses.insert(never_tried.id, never_tried);
+        address_man.addresses.insert(banned.id, banned);
+
+        address_man.prune_addresses();
+
+        // the banned peer (id 2) is the one evicted from memory, so it's the one that must also
+        // be deleted from the store - otherwise a restart would reload it via `start_addr_man`
+        // and undo the eviction.
+        assert_eq!(store.deleted.lock().unwrap().as_slice(), &[2]);
+    }
+
     #[test]
     fn test_update_address_state() {
         let mut address_man = AddressMan::new(None, &[]);
@@ -1641,6 +3638,162 @@ mod test {
             .all(|addr| addr.services.has(service_flags::UTREEXO.into())));
     }
 
+    #[test]
+    fn test_network_group_bucketing() {
+        use super::network_group;
+
+        // Addresses in the same /16 share a network group.
+        let a = AddrV2::Ipv4("12.146.1.1".parse().unwrap());
+        let b = AddrV2::Ipv4("12.146.200.200".parse().unwrap());
+        let c = AddrV2::Ipv4("8.8.8.8".parse().unwrap());
+
+        assert_eq!(network_group(&a), network_group(&b));
+        assert_ne!(network_group(&a), network_group(&c));
+    }
+
+    #[test]
+    fn test_new_addresses_are_bucketed() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let signet_address =
+            load_addresses_from_json("./src/p2p_wire/seeds/signet_seeds.json").unwrap();
+
+        address_man.push_addresses(&signet_address);
+
+        let bucketed: usize = address_man
+            .new_buckets
+            .iter()
+            .flatten()
+            .filter(|slot| slot.is_some())
+            .count();
+        assert_eq!(bucketed, address_man.addresses.len());
+    }
+
+    #[test]
+    fn test_push_addresses_from_is_rate_limited() {
+        use super::MAX_ADDRESSES_PER_PUSH;
+
+        let mut address_man =
+            AddressMan::new(None, &[ReachableNetworks::IPv4, ReachableNetworks::IPv6]);
+
+        // Spread across many distinct /16 groups (rather than one), so bucket/slot collisions
+        // don't themselves become the bottleneck and mask whether the push-time cap is doing
+        // the limiting.
+        let flood: Vec<LocalAddress> = (0..MAX_ADDRESSES_PER_PUSH + 500)
+            .map(|i| LocalAddress {
+                address: AddrV2::Ipv4(Ipv4Addr::new(
+                    20 + (i / 256) as u8,
+                    (i % 256) as u8,
+                    1,
+                    1,
+                )),
+                last_connected: 0,
+                state: AddressState::NeverTried,
+                services: ServiceFlags::NETWORK
+                    | ServiceFlags::NETWORK_LIMITED
+                    | ServiceFlags::WITNESS,
+                port: 8333,
+                id: i,
+                asn_group: Vec::new(),
+            })
+            .collect();
+
+        address_man.push_addresses(&flood);
+
+        assert_eq!(address_man.addresses.len(), MAX_ADDRESSES_PER_PUSH);
+    }
+
+    #[test]
+    fn test_new_bucket_for_is_capped_by_source_group() {
+        // Mirrors Bitcoin Core's eclipse-resistance property: however many distinct peers relay
+        // us the *same* target address, the set of "new" buckets it can end up in is bounded to
+        // the 64 possible `src_group_bucket` outcomes (see `new_bucket_for`). Without this, a
+        // Sybil attacker controlling many source identities could steer a victim address into
+        // any of the `NEW_BUCKET_COUNT` buckets of their choosing.
+        let address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let target = AddrV2::Ipv4("12.146.1.1".parse().unwrap());
+
+        let buckets: std::collections::HashSet<usize> = (0u32..2000)
+            .map(|i| {
+                let src = AddrV2::Ipv4(Ipv4Addr::new(1, (i >> 8) as u8, (i & 0xff) as u8, 1));
+                address_man.new_bucket_for(&target, &src)
+            })
+            .collect();
+
+        assert!(buckets.len() <= 64);
+    }
+
+    #[test]
+    fn test_get_address_to_connect_caps_single_group_share() {
+        // An attacker flooding our address book with peers from a single /16 shouldn't be able
+        // to win more than `max_group_fraction` of our outbound slots, even though we have
+        // plenty of their addresses and only a few diverse ones.
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+
+        let flood: Vec<LocalAddress> = (0..40)
+            .map(|i| LocalAddress {
+                address: AddrV2::Ipv4(Ipv4Addr::new(60, 1, (i / 256) as u8, (i % 256) as u8)),
+                last_connected: 0,
+                state: AddressState::NeverTried,
+                services: ServiceFlags::NETWORK
+                    | ServiceFlags::NETWORK_LIMITED
+                    | ServiceFlags::WITNESS,
+                port: 8333,
+                id: i as usize,
+                asn_group: Vec::new(),
+            })
+            .collect();
+
+        let diverse: Vec<LocalAddress> = (0..4)
+            .map(|i| LocalAddress {
+                address: AddrV2::Ipv4(Ipv4Addr::new(70 + i as u8, 1, 1, 1)),
+                last_connected: 0,
+                state: AddressState::NeverTried,
+                services: ServiceFlags::NETWORK
+                    | ServiceFlags::NETWORK_LIMITED
+                    | ServiceFlags::WITNESS,
+                port: 8333,
+                id: 1000 + i as usize,
+                asn_group: Vec::new(),
+            })
+            .collect();
+
+        address_man.push_addresses(&flood);
+        address_man.push_addresses(&diverse);
+
+        const MAX_OUTGOING_PEERS: usize = 4;
+        let mut connected_groups: Vec<Vec<u8>> = Vec::new();
+
+        for _ in 0..MAX_OUTGOING_PEERS {
+            let (id, address) = address_man
+                .get_address_to_connect(
+                    ServiceFlags::NONE,
+                    false,
+                    &connected_groups,
+                    MAX_OUTGOING_PEERS,
+                )
+                .expect("plenty of known peers left");
+
+            address_man.update_set_state(id, AddressState::Connected);
+            connected_groups.push(address.asn_group);
+        }
+
+        let flood_group = address_man.group_for(&flood[0].address);
+        let flood_connections = connected_groups
+            .iter()
+            .filter(|group| **group == flood_group)
+            .count();
+
+        assert!(
+            flood_connections <= address_man.group_cap(MAX_OUTGOING_PEERS),
+            "flooded group took {flood_connections} of {MAX_OUTGOING_PEERS} outbound slots, \
+             above its cap"
+        );
+        assert!(
+            flood_connections < MAX_OUTGOING_PEERS,
+            "diverse peers were available but the flooded group still took every slot"
+        );
+    }
+
     #[test]
     fn test_add_fixed_addresses() {
         let mut address_man =
@@ -1648,4 +3801,396 @@ mod test {
         address_man.add_fixed_addresses(Network::Signet);
         assert!(!address_man.addresses.is_empty());
     }
+
+    #[test]
+    fn test_backoff_duration_doubles_and_caps() {
+        use super::backoff_duration;
+        use super::MAX_RETRY_TIME;
+        use super::RETRY_TIME;
+
+        assert_eq!(backoff_duration(0), RETRY_TIME);
+        assert_eq!(backoff_duration(1), RETRY_TIME * 2);
+        assert_eq!(backoff_duration(2), RETRY_TIME * 4);
+        assert_eq!(backoff_duration(100), MAX_RETRY_TIME);
+    }
+
+    #[test]
+    fn test_score_address_prefers_services_and_recency() {
+        use super::score_address;
+        use super::SELECTION_FLOOR;
+
+        let never_tried = LocalAddress {
+            address: AddrV2::Ipv4("12.146.182.45".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NONE,
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        let utreexo_peer = LocalAddress {
+            services: ServiceFlags::NETWORK | service_flags::UTREEXO.into(),
+            ..never_tried.clone()
+        };
+
+        // a never-tried peer still scores above zero, so it keeps a shot at being picked...
+        assert_eq!(score_address(&never_tried, 0), SELECTION_FLOOR);
+        // ...but a peer advertising a desired service we care about scores higher
+        assert!(score_address(&utreexo_peer, 0) > score_address(&never_tried, 0));
+
+        let freshly_connected = LocalAddress {
+            state: AddressState::Connected,
+            ..never_tried.clone()
+        };
+        assert!(score_address(&freshly_connected, 0) > score_address(&never_tried, 0));
+
+        let repeatedly_failed = LocalAddress {
+            state: AddressState::Failed(0, 5),
+            ..never_tried.clone()
+        };
+        assert!(score_address(&repeatedly_failed, 1_000) < score_address(&never_tried, 1_000));
+    }
+
+    #[test]
+    fn test_weighted_index_never_picks_zero_weight_candidate() {
+        use super::weighted_index;
+
+        for _ in 0..100 {
+            let idx = weighted_index(&[0.0, 1.0, 0.0]).unwrap();
+            assert_eq!(idx, 1);
+        }
+
+        assert_eq!(weighted_index(&[0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn test_canonicalize_ipv4_mapped_ipv6() {
+        use super::canonicalize;
+
+        let mapped = AddrV2::Ipv6("::ffff:12.146.1.1".parse().unwrap());
+        assert_eq!(
+            canonicalize(mapped),
+            AddrV2::Ipv4("12.146.1.1".parse().unwrap())
+        );
+
+        // a real IPv6 address should be left untouched
+        let real_v6 = AddrV2::Ipv6("2001:db8::1".parse().unwrap());
+        assert_eq!(canonicalize(real_v6.clone()), real_v6);
+    }
+
+    #[test]
+    fn test_push_addresses_dedupes_ipv4_mapped_ipv6() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let v4 = LocalAddress {
+            address: AddrV2::Ipv4("12.146.182.45".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED | ServiceFlags::WITNESS,
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        let same_host_mapped_ipv6 = LocalAddress {
+            address: AddrV2::Ipv6("::ffff:12.146.182.45".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED | ServiceFlags::WITNESS,
+            port: 8333,
+            id: 1,
+            asn_group: Vec::new(),
+        };
+
+        address_man.push_addresses(&[v4, same_host_mapped_ipv6]);
+
+        // the mapped IPv6 form is recognized as the same host, so only one entry is kept,
+        // stored in its canonical Ipv4 form
+        assert_eq!(address_man.addresses.len(), 1);
+        assert_eq!(
+            address_man.addresses.values().next().unwrap().address,
+            AddrV2::Ipv4("12.146.182.45".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_push_addresses_drops_unknown_network() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let unknown = LocalAddress {
+            address: AddrV2::Unknown(99, vec![1, 2, 3]),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED | ServiceFlags::WITNESS,
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        address_man.push_addresses(&[unknown]);
+        assert!(address_man.addresses.is_empty());
+    }
+
+    #[test]
+    fn test_disk_local_address_rejects_unknown_network() {
+        let unknown = LocalAddress {
+            address: AddrV2::Unknown(99, vec![1, 2, 3]),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NONE,
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        assert!(DiskLocalAddress::try_from(unknown).is_err());
+    }
+
+    #[test]
+    fn test_redacted_address_masks_ip_by_default() {
+        let address = LocalAddress {
+            address: AddrV2::Ipv4("12.146.1.1".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::NONE,
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        let redacted = address.loggable(false).to_string();
+        assert_eq!(redacted, "ipv4:xxx.xxx.xxx.xxx:8333");
+        assert!(!redacted.contains("12.146.1.1"));
+
+        let revealed = address.loggable(true).to_string();
+        assert!(revealed.contains("12.146.1.1"));
+    }
+
+    #[test]
+    fn test_ip_cidr_matches() {
+        let cidr: IpCidr = "12.146.0.0/16".parse().unwrap();
+        assert!(cidr.contains(&"12.146.1.1".parse().unwrap()));
+        assert!(!cidr.contains(&"12.147.1.1".parse().unwrap()));
+
+        let cidr_v6: IpCidr = "2001:db8::/32".parse().unwrap();
+        assert!(cidr_v6.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!cidr_v6.contains(&"2001:db9::1".parse().unwrap()));
+
+        assert!("12.146.0.0/33".parse::<IpCidr>().is_err());
+        assert!("not-a-cidr".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_ip_filter_classes() {
+        let public_ip = "12.146.1.1".parse().unwrap();
+        let private_ip = "192.168.1.1".parse().unwrap();
+
+        assert!(IpFilter::All.matches(&public_ip));
+        assert!(IpFilter::All.matches(&private_ip));
+        assert!(IpFilter::Public.matches(&public_ip));
+        assert!(!IpFilter::Public.matches(&private_ip));
+        assert!(!IpFilter::Private.matches(&public_ip));
+        assert!(IpFilter::Private.matches(&private_ip));
+
+        assert_eq!("public".parse::<IpFilter>().unwrap(), IpFilter::Public);
+        assert_eq!(
+            "12.146.0.0/16".parse::<IpFilter>().unwrap(),
+            IpFilter::Cidr("12.146.0.0/16".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_deny_ips_overrides_allow_ips() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        address_man.set_allow_ips(vec!["12.146.0.0/16".parse().unwrap()]);
+        address_man.set_deny_ips(vec!["12.146.1.0/24".parse().unwrap()]);
+
+        let allowed = LocalAddress {
+            address: AddrV2::Ipv4("12.146.2.1".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+        let denied = LocalAddress {
+            address: AddrV2::Ipv4("12.146.1.1".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 1,
+            asn_group: Vec::new(),
+        };
+        let not_allow_listed = LocalAddress {
+            address: AddrV2::Ipv4("8.8.8.8".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 2,
+            asn_group: Vec::new(),
+        };
+
+        assert!(address_man.is_net_reachable(&allowed));
+        assert!(!address_man.is_net_reachable(&denied));
+        assert!(!address_man.is_net_reachable(&not_allow_listed));
+    }
+
+    #[test]
+    fn test_allow_ips_permits_private_ranges() {
+        let private = LocalAddress {
+            address: AddrV2::Ipv4("10.0.0.5".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::NeverTried,
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 0,
+            asn_group: Vec::new(),
+        };
+
+        // by default, RFC1918 ranges are martian and unreachable...
+        let address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        assert!(!address_man.is_net_reachable(&private));
+
+        // ...but an operator running on a private/regtest network can explicitly allow them.
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        address_man.set_allow_ips(vec![IpFilter::Private]);
+        assert!(address_man.is_net_reachable(&private));
+
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        address_man.set_allow_ips(vec!["10.0.0.0/8".parse().unwrap()]);
+        assert!(address_man.is_net_reachable(&private));
+        address_man.push_addresses(&[private.clone()]);
+        assert_eq!(address_man.addresses.len(), 1);
+    }
+
+    #[test]
+    fn test_reserved_peer_always_eligible() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let reserved = LocalAddress {
+            address: AddrV2::Ipv4("12.146.1.1".parse().unwrap()),
+            last_connected: 0,
+            state: AddressState::Banned(u64::MAX),
+            services: ServiceFlags::default(),
+            port: 8333,
+            id: 42,
+            asn_group: Vec::new(),
+        };
+        address_man.add_reserved_peer(reserved);
+
+        let (id, _) = address_man
+            .get_address_to_connect(ServiceFlags::NONE, false, &[], 8)
+            .expect("reserved peer should always be eligible");
+        assert_eq!(id, 42);
+
+        // pruning never drops a reserved peer, no matter how low the size cap is
+        address_man.max_size = 0;
+        address_man.prune_addresses();
+        assert!(address_man.addresses.contains_key(&42));
+    }
+
+    #[test]
+    fn test_reserved_only_rejects_other_peers() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        address_man.set_reserved_only(true);
+        address_man.push_addresses(&get_addresses_and_random_times());
+
+        assert!(address_man
+            .get_address_to_connect(ServiceFlags::NONE, false, &[], 8)
+            .is_none());
+    }
+
+    #[test]
+    fn test_maybe_refresh_seeds_respects_interval() {
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let seeds = get_chain_dns_seeds(Network::Signet);
+
+        // with an empty address manager, refreshing is urgent: every seed is due immediately
+        let due = address_man.maybe_refresh_seeds(&seeds, 1_000);
+        assert_eq!(due.len(), seeds.len());
+
+        // right after being marked resolved, and still urgent (no addresses were added), seeds
+        // are due again regardless of the interval
+        let due = address_man.maybe_refresh_seeds(&seeds, 1_001);
+        assert_eq!(due.len(), seeds.len());
+    }
+
+    #[test]
+    fn test_maybe_refresh_seeds_waits_once_not_urgent() {
+        use super::MIN_ADDRESSES;
+        use super::MIN_ADDRESSES_CBF;
+        use super::MIN_ADDRESSES_UTREEXO;
+        use super::SEED_REFRESH_INTERVAL;
+
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let seeds = get_chain_dns_seeds(Network::Signet);
+
+        // fake a well-stocked address manager so refreshing is no longer urgent
+        for i in 0..MIN_ADDRESSES {
+            address_man.good_addresses.push(i);
+        }
+        address_man.good_peers_by_service.insert(
+            ServiceFlags::COMPACT_FILTERS,
+            (0..MIN_ADDRESSES_CBF).collect(),
+        );
+        address_man.good_peers_by_service.insert(
+            service_flags::UTREEXO.into(),
+            (0..MIN_ADDRESSES_UTREEXO).collect(),
+        );
+        assert!(address_man.enough_addresses());
+
+        let due = address_man.maybe_refresh_seeds(&seeds, 1_000);
+        assert_eq!(due.len(), seeds.len());
+
+        // not due again until SEED_REFRESH_INTERVAL has passed
+        let due = address_man.maybe_refresh_seeds(&seeds, 1_001);
+        assert!(due.is_empty());
+
+        let due = address_man.maybe_refresh_seeds(&seeds, 1_000 + SEED_REFRESH_INTERVAL);
+        assert_eq!(due.len(), seeds.len());
+    }
+
+    fn tempdir() -> String {
+        // create ./tmp-addrman if it doesn't exist
+        let tmp_dir = std::path::PathBuf::from("./tmp-addrman");
+        if !tmp_dir.exists() {
+            std::fs::create_dir(&tmp_dir).unwrap();
+        }
+        let test_name = rand::random::<u64>();
+        format!("./tmp-addrman/test-{test_name}")
+    }
+
+    #[test]
+    fn test_flush_and_load_from_disk_round_trips() {
+        let datadir = tempdir();
+        std::fs::create_dir_all(&datadir).unwrap();
+
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        address_man.push_addresses(&get_addresses_and_random_times());
+        let secret_key = address_man.secret_key;
+
+        address_man.flush_to_disk(&datadir).unwrap();
+
+        let mut loaded = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        let count = loaded.load_from_disk(&datadir).unwrap();
+
+        assert_eq!(count, address_man.addresses.len());
+        assert_eq!(loaded.secret_key, secret_key);
+        assert_eq!(loaded.addresses.len(), address_man.addresses.len());
+
+        std::fs::remove_dir_all(&datadir).ok();
+    }
+
+    #[test]
+    fn test_load_from_disk_rejects_wrong_magic() {
+        let datadir = tempdir();
+        std::fs::create_dir_all(&datadir).unwrap();
+        std::fs::write(format!("{datadir}/peers.bin"), b"not a snapshot").unwrap();
+
+        let mut address_man = AddressMan::new(None, &[ReachableNetworks::IPv4]);
+        assert!(address_man.load_from_disk(&datadir).is_err());
+
+        std::fs::remove_dir_all(&datadir).ok();
+    }
 }
@@ -0,0 +1,411 @@
+//! A background audit pass over the block range SwiftSync skipped full validation for.
+//!
+//! SwiftSync trusts the hints file to tell it which outputs stay unspent through the download
+//! window, and only sanity-checks the result at the end: the aggregator must net to zero and the
+//! supply must stay under the consensus cap (see `SwiftSync::handle_stop_height_reached`). A
+//! hints file that's wrong in a way that still balances out algebraically would pass both checks
+//! undetected. `HintsAudit` is the separate verification pass that doesn't trust the hints at
+//! all: borrowing the "enact, then verify separately" split, it re-downloads the same range in
+//! order and replays it against a UTXO set built from scratch, then compares what it finds
+//! unspent at each height against what the hints file claimed.
+//!
+//! In this tree `HintsAudit` is wired up as its own low-priority `NodeContext`, entered right
+//! after `SwiftSync` and before the node settles into `RunningNode`, rather than as a task spawned
+//! from inside `RunningNode` itself.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bitcoin::p2p::ServiceFlags;
+use bitcoin::Block;
+use bitcoin::BlockHash;
+use bitcoin::OutPoint;
+use bitcoin::TxOut;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use crate::node::periodic_job;
+use crate::node::swift_sync_ctx::parse_hints_file_with_retry;
+use crate::node::swift_sync_ctx::HintFileError;
+use crate::node::swift_sync_ctx::Hints;
+use crate::node::try_and_log;
+use crate::node::InflightRequests;
+use crate::node::NodeNotification;
+use crate::node::UtreexoNode;
+use crate::node_context::LoopControl;
+use crate::node_context::NodeContext;
+use crate::p2p_wire::error::WireError;
+use crate::p2p_wire::peer::PeerMessages;
+
+/// Maximum number of maintenance ticks [`HintsAudit::retry_pending_hints_check`] retries a
+/// single height's `NeedMoreData` before giving up and flagging a divergence. The tick interval
+/// itself (`HintsAudit::MAINTENANCE_TICK`) is the backoff, so this isn't a tight retry loop.
+const MAX_HINTS_RETRIES: u32 = 10;
+
+/// A block already replayed from scratch, waiting on a hints-file read that came back
+/// `NeedMoreData` before it can be compared against what the hints file claims.
+struct PendingHintsCheck {
+    /// The block's height.
+    height: u32,
+
+    /// The block's hash, for log messages.
+    hash: BlockHash,
+
+    /// This block's own outputs still unspent after the from-scratch replay.
+    unspent: HashSet<u64>,
+
+    /// How many maintenance ticks this check has already been retried on.
+    attempts: u32,
+}
+
+/// Re-validates the SwiftSync-trusted block range from scratch, without relying on the hints
+/// file, by replaying every block against a UTXO set built up in memory.
+///
+/// Implements:
+///     - `NodeContext`
+///     - `UtreexoNode<HintsAudit, Chain>`
+#[derive(Default)]
+pub struct HintsAudit {
+    /// UTXOs created by already-audited blocks that remain unspent, keyed by the outpoint that
+    /// created them.
+    utxos: HashMap<OutPoint, TxOut>,
+
+    /// Height of the next block to request and audit.
+    next_height: u32,
+
+    /// Height where our from-scratch replay disagreed with what the hints file implied, if any.
+    divergent_height: Option<u32>,
+
+    /// A replayed block whose hints-file comparison is still waiting on `NeedMoreData`, resolved
+    /// by [`HintsAudit::retry_pending_hints_check`] on a later maintenance tick instead of
+    /// blocking the event loop on a retry sleep.
+    pending_check: Option<PendingHintsCheck>,
+}
+
+impl NodeContext for HintsAudit {
+    fn get_required_services(&self) -> ServiceFlags {
+        ServiceFlags::WITNESS | ServiceFlags::NETWORK
+    }
+
+    // This is a best-effort background pass: it shouldn't compete with user-facing traffic, so
+    // we keep it slow and lightly connected.
+    const TRY_NEW_CONNECTION: u64 = 60;
+    const REQUEST_TIMEOUT: u64 = 2 * 60;
+    const MAX_INFLIGHT_REQUESTS: usize = 10;
+    const MAX_OUTGOING_PEERS: usize = 1;
+    const MAX_CONCURRENT_GETDATA: usize = 2;
+    const ASSUME_STALE: u64 = 10 * 60;
+    const MAINTENANCE_TICK: Duration = Duration::from_secs(30);
+}
+
+/// Node methods for a [`UtreexoNode`] where its Context is [`HintsAudit`].
+impl<Chain> UtreexoNode<Chain, HintsAudit>
+where
+    Chain: crate::ThreadSafeChain,
+    WireError: From<Chain::Error>,
+{
+    /// Starts the audit, replaying blocks up to the hints file's `stop_height` one at a time.
+    pub async fn run(mut self, done_cb: impl FnOnce(&Chain)) -> Self {
+        info!("Starting hints audit...");
+        self.context.next_height = 1;
+
+        // `SwiftSync` already reads through the same file up to its own `stop_height` before
+        // handing off here, so this shouldn't need to wait; `parse_hints_file_with_retry` backs
+        // off and retries regardless, rather than aborting on what looks like corruption.
+        let mut hints = parse_hints_file_with_retry(&self.datadir, self.network).await;
+        let stop_height = hints.stop_height();
+
+        let mut ticker = tokio::time::interval(HintsAudit::MAINTENANCE_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = ticker.tick() => match self.audit_maintenance_tick(stop_height, &mut hints).await {
+                    LoopControl::Continue => {},
+                    LoopControl::Break => break,
+                },
+
+                msg = self.node_rx.recv() => {
+                    let Some(msg) = msg else {
+                        break;
+                    };
+                    try_and_log!(self.handle_audit_message(msg, &mut hints, stop_height).await);
+
+                    while let Ok(msg) = self.node_rx.try_recv() {
+                        try_and_log!(self.handle_audit_message(msg, &mut hints, stop_height).await);
+                    }
+                    if *self.kill_signal.read().await {
+                        break;
+                    }
+                }
+            }
+        }
+
+        done_cb(&self.chain);
+        self
+    }
+
+    async fn audit_maintenance_tick(&mut self, stop_height: u32, hints: &mut Hints) -> LoopControl {
+        if *self.kill_signal.read().await {
+            return LoopControl::Break;
+        }
+
+        if self.context.divergent_height.is_some() {
+            return LoopControl::Break;
+        }
+
+        self.retry_pending_hints_check(hints);
+        if self.context.divergent_height.is_some() {
+            try_and_log!(self.invalidate_from_divergence());
+            return LoopControl::Break;
+        }
+
+        if self.context.next_height > stop_height && self.context.pending_check.is_none() {
+            self.finish_audit(stop_height);
+            return LoopControl::Break;
+        }
+
+        periodic_job!(
+            self.last_connection => self.maybe_open_connection(ServiceFlags::NETWORK),
+            HintsAudit::TRY_NEW_CONNECTION,
+        );
+
+        try_and_log!(self.check_for_timeout());
+        try_and_log!(self.request_next_audit_block());
+
+        LoopControl::Continue
+    }
+
+    async fn handle_audit_message(
+        &mut self,
+        msg: NodeNotification,
+        hints: &mut Hints,
+        stop_height: u32,
+    ) -> Result<(), WireError> {
+        match msg {
+            NodeNotification::FromUser(request, responder) => {
+                self.perform_user_request(request, responder).await;
+            }
+
+            NodeNotification::DnsSeedAddresses(addresses) => {
+                self.address_man.push_addresses(&addresses);
+            }
+
+            NodeNotification::FromPeer(peer, notification, time) => {
+                self.register_message_time(&notification, peer, time);
+
+                let Some(unhandled) = self.handle_peer_msg_common(notification, peer)? else {
+                    return Ok(());
+                };
+
+                match unhandled {
+                    PeerMessages::Block(block) => {
+                        let hash = block.block_hash();
+
+                        let Some(_) = self.inflight.remove(&InflightRequests::Blocks(hash)) else {
+                            warn!("Received block {hash} during audit, but we didn't ask for it");
+                            return Ok(());
+                        };
+
+                        self.audit_block(hash, &block, hints);
+                        try_and_log!(self.request_next_audit_block());
+
+                        if self.context.divergent_height.is_some() {
+                            self.invalidate_from_divergence()?;
+                        } else if self.context.next_height > stop_height
+                            && self.context.pending_check.is_none()
+                        {
+                            self.finish_audit(stop_height);
+                        }
+                    }
+
+                    PeerMessages::Ready(version) => {
+                        try_and_log!(self.handle_peer_ready(peer, &version));
+                    }
+
+                    PeerMessages::Disconnected(idx) => {
+                        try_and_log!(self.handle_disconnection(peer, idx));
+                    }
+
+                    _ => {}
+                }
+            }
+
+            NodeNotification::FromWorker(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Requests the next block in the audited range, if we aren't already waiting on one.
+    fn request_next_audit_block(&mut self) -> Result<(), WireError> {
+        if !self.can_request_more_blocks()
+            || self.context.divergent_height.is_some()
+            || self.context.pending_check.is_some()
+        {
+            return Ok(());
+        }
+
+        let Ok(hash) = self.chain.get_block_hash(self.context.next_height) else {
+            return Ok(());
+        };
+
+        self.request_blocks(vec![hash])
+    }
+
+    /// Replays a single block against the in-memory UTXO set and compares the result against what
+    /// the hints file claimed for this height, in two independent ways:
+    ///   - Every non-coinbase input must spend an output we actually saw created earlier in the
+    ///     replay. A missing one means the hints file let through a spend of something that was
+    ///     never really there.
+    ///   - The set of this block's own outputs still unspent at the end of the replay must match
+    ///     `hints.get_indexes(height)` exactly.
+    fn audit_block(&mut self, hash: BlockHash, block: &Block, hints: &mut Hints) {
+        let height = self.context.next_height;
+
+        for tx in &block.txdata {
+            if tx.is_coinbase() {
+                continue;
+            }
+
+            for input in &tx.input {
+                if self.context.utxos.remove(&input.previous_output).is_none() {
+                    error!(
+                        "Hints audit: block {hash} at height {height} spends {:?}, which isn't in \
+                         the UTXO set built from scratch; the hints file disagreed with reality \
+                         somewhere before this point",
+                        input.previous_output
+                    );
+                    self.context.divergent_height = Some(height);
+                    return;
+                }
+            }
+        }
+
+        let mut unspent = HashSet::new();
+        for tx in &block.txdata {
+            let txid = tx.compute_txid();
+
+            for (vout, out) in tx.output.iter().enumerate() {
+                if out.script_pubkey.is_op_return() {
+                    continue;
+                }
+
+                self.context
+                    .utxos
+                    .insert(OutPoint::new(txid, vout as u32), out.clone());
+                unspent.insert(vout as u64);
+            }
+        }
+
+        self.check_hints(height, hash, unspent, hints);
+    }
+
+    /// Compares `unspent` (this block's own outputs still unspent after replay) against what the
+    /// hints file claims for `height`. If the hints file doesn't cover this height yet
+    /// (`NeedMoreData`), stashes the comparison as `pending_check` instead of retrying in place,
+    /// so [`Self::retry_pending_hints_check`] can resolve it on a later maintenance tick without
+    /// blocking the event loop.
+    fn check_hints(&mut self, height: u32, hash: BlockHash, unspent: HashSet<u64>, hints: &mut Hints) {
+        match hints.get_indexes(height) {
+            Ok(indexes) => {
+                self.finish_hints_check(height, hash, &unspent, indexes.into_iter().collect())
+            }
+            Err(HintFileError::NeedMoreData) => {
+                debug!("Hints file doesn't cover height {height} yet, retrying later");
+                self.context.pending_check = Some(PendingHintsCheck {
+                    height,
+                    hash,
+                    unspent,
+                    attempts: 0,
+                });
+            }
+            Err(e) => panic!("hints file covers every height up to stop_height: {e}"),
+        }
+    }
+
+    /// Retries a [`PendingHintsCheck`] stashed by [`Self::check_hints`], once per maintenance
+    /// tick; the tick interval itself is the backoff, so this never blocks the event loop on a
+    /// sleep.
+    fn retry_pending_hints_check(&mut self, hints: &mut Hints) {
+        let Some(pending) = self.context.pending_check.take() else {
+            return;
+        };
+
+        match hints.get_indexes(pending.height) {
+            Ok(indexes) => self.finish_hints_check(
+                pending.height,
+                pending.hash,
+                &pending.unspent,
+                indexes.into_iter().collect(),
+            ),
+            Err(HintFileError::NeedMoreData) if pending.attempts + 1 < MAX_HINTS_RETRIES => {
+                debug!(
+                    "Hints file still doesn't cover height {} after {} attempts, retrying later",
+                    pending.height,
+                    pending.attempts + 1
+                );
+                self.context.pending_check = Some(PendingHintsCheck {
+                    attempts: pending.attempts + 1,
+                    ..pending
+                });
+            }
+            Err(HintFileError::NeedMoreData) => {
+                error!(
+                    "Hints audit: hints file still doesn't cover height {} after {} attempts, giving up",
+                    pending.height,
+                    pending.attempts + 1
+                );
+                self.context.divergent_height = Some(pending.height);
+            }
+            Err(e) => panic!("hints file covers every height up to stop_height: {e}"),
+        }
+    }
+
+    /// Finishes a hints-file comparison once `claimed` has actually been read, whether that
+    /// happened immediately in [`Self::check_hints`] or after retries in
+    /// [`Self::retry_pending_hints_check`].
+    fn finish_hints_check(
+        &mut self,
+        height: u32,
+        hash: BlockHash,
+        unspent: &HashSet<u64>,
+        claimed: HashSet<u64>,
+    ) {
+        if *unspent != claimed {
+            error!(
+                "Hints audit: block {hash} at height {height} has outputs {unspent:?} still \
+                 unspent, but the hints file claimed {claimed:?}"
+            );
+            self.context.divergent_height = Some(height);
+            return;
+        }
+
+        self.context.next_height += 1;
+    }
+
+    /// Invalidates the chain from the height where our from-scratch replay disagreed with what
+    /// the hints file implied, mirroring the height-150 rollback `SwiftSync` performs when it
+    /// catches an outright invalid block.
+    fn invalidate_from_divergence(&mut self) -> Result<(), WireError> {
+        let height = self
+            .context
+            .divergent_height
+            .expect("only called once divergent_height is set");
+
+        error!("Hints audit: invalidating the chain from height {height} onward");
+        let block_hash = self.chain.get_block_hash(height)?;
+        try_and_log!(self.chain.invalidate_block(block_hash));
+
+        Err(WireError::PeerMisbehaving)
+    }
+
+    fn finish_audit(&mut self, stop_height: u32) {
+        info!("Hints audit: finished re-validating up to height {stop_height}, no divergence found");
+    }
+}
@@ -0,0 +1,309 @@
+//! Produces the SwiftSync hints file `Hints::from_file` consumes, instead of requiring users to
+//! obtain `{network}.hints` out-of-band and trust it sight unseen.
+//!
+//! `HintsGen` replays an already-validated range of the chain one block at a time — the same
+//! "enact against an in-memory UTXO set, built from scratch" approach `HintsAudit` uses to verify
+//! a hints file — and, rather than comparing the result against an existing file, records it and
+//! serializes it in the exact binary layout `Hints::from_file` expects: the `UTXO` magic, version
+//! byte, stop height, a per-height index table of file offsets, and at those offsets each height's
+//! bit-packed unspent-output-index list. Users who already have a fully validated, proof-carrying
+//! chain (e.g. an archival node, or one that just finished a normal IBD) can run this to produce
+//! their own hints file instead of downloading one from a third party.
+//!
+//! In this tree `HintsGen` is wired up as its own `NodeContext`, the same way `HintsAudit` is.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::p2p::ServiceFlags;
+use bitcoin::Block;
+use bitcoin::OutPoint;
+use bitcoin::TxOut;
+use tracing::info;
+use tracing::warn;
+
+use crate::node::periodic_job;
+use crate::node::try_and_log;
+use crate::node::InflightRequests;
+use crate::node::NodeNotification;
+use crate::node::UtreexoNode;
+use crate::node_context::LoopControl;
+use crate::node_context::NodeContext;
+use crate::p2p_wire::error::WireError;
+use crate::p2p_wire::peer::PeerMessages;
+
+/// Replays an already-validated block range from scratch, against a UTXO set built up in memory,
+/// and records which of each height's own outputs remain unspent — the same per-height fact
+/// `HintsAudit` checks an existing hints file against — so that it can be written out as a new
+/// hints file.
+///
+/// Implements:
+///     - `NodeContext`
+///     - `UtreexoNode<HintsGen, Chain>`
+#[derive(Default)]
+pub struct HintsGen {
+    /// UTXOs created by already-recorded blocks that remain unspent, keyed by the outpoint that
+    /// created them.
+    utxos: HashMap<OutPoint, TxOut>,
+
+    /// Height of the next block to request and record.
+    next_height: u32,
+
+    /// Each recorded height's unspent output indexes, in the order `Hints::from_file` expects.
+    records: BTreeMap<u32, Vec<u64>>,
+}
+
+impl NodeContext for HintsGen {
+    fn get_required_services(&self) -> ServiceFlags {
+        ServiceFlags::WITNESS | ServiceFlags::NETWORK
+    }
+
+    // Same rationale as `HintsAudit`: this is a best-effort background pass over blocks we (or a
+    // peer) already consider valid, not something that should compete with user-facing traffic.
+    const TRY_NEW_CONNECTION: u64 = 60;
+    const REQUEST_TIMEOUT: u64 = 2 * 60;
+    const MAX_INFLIGHT_REQUESTS: usize = 10;
+    const MAX_OUTGOING_PEERS: usize = 1;
+    const MAX_CONCURRENT_GETDATA: usize = 2;
+    const ASSUME_STALE: u64 = 10 * 60;
+    const MAINTENANCE_TICK: Duration = Duration::from_secs(30);
+}
+
+/// Node methods for a [`UtreexoNode`] where its Context is [`HintsGen`].
+impl<Chain> UtreexoNode<Chain, HintsGen>
+where
+    Chain: crate::ThreadSafeChain,
+    WireError: From<Chain::Error>,
+{
+    /// Starts generation, replaying blocks `1..=stop_height` one at a time and writing
+    /// `{datadir}/{network}.hints` once the whole range has been recorded.
+    pub async fn run(mut self, stop_height: u32, done_cb: impl FnOnce(&Chain)) -> Self {
+        info!("Starting hints generation up to height {stop_height}...");
+        self.context.next_height = 1;
+
+        let mut ticker = tokio::time::interval(HintsGen::MAINTENANCE_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = ticker.tick() => match self.gen_maintenance_tick(stop_height).await {
+                    LoopControl::Continue => {},
+                    LoopControl::Break => break,
+                },
+
+                msg = self.node_rx.recv() => {
+                    let Some(msg) = msg else {
+                        break;
+                    };
+                    try_and_log!(self.handle_gen_message(msg, stop_height).await);
+
+                    while let Ok(msg) = self.node_rx.try_recv() {
+                        try_and_log!(self.handle_gen_message(msg, stop_height).await);
+                    }
+                    if *self.kill_signal.read().await {
+                        break;
+                    }
+                }
+            }
+        }
+
+        done_cb(&self.chain);
+        self
+    }
+
+    async fn gen_maintenance_tick(&mut self, stop_height: u32) -> LoopControl {
+        if *self.kill_signal.read().await {
+            return LoopControl::Break;
+        }
+
+        if self.context.next_height > stop_height {
+            self.finish_generation(stop_height);
+            return LoopControl::Break;
+        }
+
+        periodic_job!(
+            self.last_connection => self.maybe_open_connection(ServiceFlags::NETWORK),
+            HintsGen::TRY_NEW_CONNECTION,
+        );
+
+        try_and_log!(self.check_for_timeout());
+        try_and_log!(self.request_next_gen_block());
+
+        LoopControl::Continue
+    }
+
+    async fn handle_gen_message(
+        &mut self,
+        msg: NodeNotification,
+        stop_height: u32,
+    ) -> Result<(), WireError> {
+        match msg {
+            NodeNotification::FromUser(request, responder) => {
+                self.perform_user_request(request, responder).await;
+            }
+
+            NodeNotification::DnsSeedAddresses(addresses) => {
+                self.address_man.push_addresses(&addresses);
+            }
+
+            NodeNotification::FromPeer(peer, notification, time) => {
+                self.register_message_time(&notification, peer, time);
+
+                let Some(unhandled) = self.handle_peer_msg_common(notification, peer)? else {
+                    return Ok(());
+                };
+
+                match unhandled {
+                    PeerMessages::Block(block) => {
+                        let hash = block.block_hash();
+
+                        let Some(_) = self.inflight.remove(&InflightRequests::Blocks(hash)) else {
+                            warn!("Received block {hash} during hints generation, but we didn't ask for it");
+                            return Ok(());
+                        };
+
+                        self.record_block(&block);
+                        try_and_log!(self.request_next_gen_block());
+
+                        if self.context.next_height > stop_height {
+                            self.finish_generation(stop_height);
+                        }
+                    }
+
+                    PeerMessages::Ready(version) => {
+                        try_and_log!(self.handle_peer_ready(peer, &version));
+                    }
+
+                    PeerMessages::Disconnected(idx) => {
+                        try_and_log!(self.handle_disconnection(peer, idx));
+                    }
+
+                    _ => {}
+                }
+            }
+
+            NodeNotification::FromWorker(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Requests the next block in the range being recorded, if we aren't already waiting on one.
+    fn request_next_gen_block(&mut self) -> Result<(), WireError> {
+        if !self.can_request_more_blocks() {
+            return Ok(());
+        }
+
+        let Ok(hash) = self.chain.get_block_hash(self.context.next_height) else {
+            return Ok(());
+        };
+
+        self.request_blocks(vec![hash])
+    }
+
+    /// Folds one more block into the in-memory UTXO set and records which of its own non-`OP_RETURN`
+    /// outputs remain unspent at this point in the replay — exactly the fact
+    /// `HintsAudit::audit_block` checks an existing hints file's claim against for the same height,
+    /// so a hints file built from this matches what `HintsAudit` (and SwiftSync itself) expect.
+    fn record_block(&mut self, block: &Block) {
+        let height = self.context.next_height;
+
+        for tx in &block.txdata {
+            if tx.is_coinbase() {
+                continue;
+            }
+
+            for input in &tx.input {
+                self.context.utxos.remove(&input.previous_output);
+            }
+        }
+
+        let mut unspent = Vec::new();
+        for tx in &block.txdata {
+            let txid = tx.compute_txid();
+
+            for (vout, out) in tx.output.iter().enumerate() {
+                if out.script_pubkey.is_op_return() {
+                    continue;
+                }
+
+                self.context
+                    .utxos
+                    .insert(OutPoint::new(txid, vout as u32), out.clone());
+                unspent.push(vout as u64);
+            }
+        }
+
+        self.context.records.insert(height, unspent);
+        self.context.next_height += 1;
+    }
+
+    fn finish_generation(&mut self, stop_height: u32) {
+        let path = format!("{}/{}.hints", self.datadir, self.network);
+
+        match write_hints_file(&path, stop_height, &self.context.records) {
+            Ok(checksum) => info!(
+                "Hints generation finished: wrote {path} up to height {stop_height}, sha256 {checksum:x}",
+            ),
+            Err(e) => warn!("Failed to write generated hints file to {path}: {e:?}"),
+        }
+    }
+}
+
+/// Serializes `records` (one entry per height in `1..=stop_height`) into the binary layout
+/// `Hints::from_file` expects and writes it to `path`, returning the sha256 digest of the file's
+/// contents so the caller can publish a checksum alongside it.
+///
+/// Layout, matching `Hints::from_file`/`Hints::get_indexes` exactly:
+///   - `UTXO` magic (4 bytes), version byte (`0x00`), stop height (u32 LE)
+///   - one `(height: u32 LE, file_pos: u64 LE)` pair per height in `1..=stop_height`, in order
+///   - at each height's `file_pos`: a `u32` LE bit count, then that many bits packed LSB-first,
+///     one bit per output index in `0..num_bits`, set for every index in that height's record
+fn write_hints_file(
+    path: &str,
+    stop_height: u32,
+    records: &BTreeMap<u32, Vec<u64>>,
+) -> io::Result<sha256::Hash> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x55, 0x54, 0x58, 0x4f]); // "UTXO"
+    header.push(0x00);
+    header.extend_from_slice(&stop_height.to_le_bytes());
+
+    let index_table_len = stop_height as usize * 12; // (height: u32, file_pos: u64) per height
+    let data_start = header.len() + index_table_len;
+
+    let mut index_table = Vec::with_capacity(index_table_len);
+    let mut data = Vec::new();
+
+    for height in 1..=stop_height {
+        let indexes = records.get(&height).cloned().unwrap_or_default();
+
+        index_table.extend_from_slice(&height.to_le_bytes());
+        let file_pos = (data_start + data.len()) as u64;
+        index_table.extend_from_slice(&file_pos.to_le_bytes());
+
+        let num_bits = indexes.iter().copied().max().map_or(0, |max| max + 1);
+        data.extend_from_slice(&(num_bits as u32).to_le_bytes());
+
+        let mut packed = vec![0u8; num_bits.div_ceil(8) as usize];
+        for index in indexes {
+            let idx = index as usize;
+            packed[idx / 8] |= 1 << (idx % 8);
+        }
+        data.extend_from_slice(&packed);
+    }
+
+    let mut contents = header;
+    contents.extend_from_slice(&index_table);
+    contents.extend_from_slice(&data);
+
+    fs::write(path, &contents)?;
+    Ok(sha256::Hash::hash(&contents))
+}
@@ -2,8 +2,12 @@
 //! needed to validate the UTXO set with the SwiftSync method.
 
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
+use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
@@ -63,15 +67,62 @@ pub struct SwiftSync {
     /// The secret salt used to compute the aggregator element hashes.
     salt: Arc<SipHashKeys>,
 
+    /// The raw entropy `salt` was derived from. Kept around (instead of just the derived
+    /// `SipHashKeys`) so a checkpoint can persist it and a resumed session can reconstruct the
+    /// exact same salt; the aggregator's element hashes are salt-dependent, so resuming with a
+    /// different salt than the one blocks so far were folded in with would make the final
+    /// `is_zero()` check meaningless.
+    salt_seed: (u64, u64, u64, u64),
+
     /// The total unspent amount. Once we reach the SwiftSync stop height, this must be less or
     /// equal than the theoretical supply limit at that height.
     supply: Amount,
 
+    /// One bit per height in `[0, stop_height]`, set once that height's block has been folded
+    /// into `agg` and `supply`. Persisted in the checkpoint and consulted on resume to avoid
+    /// double-counting a height that was already processed before a restart.
+    processed: Vec<u8>,
+
+    /// Cumulative requested-heights count (summed across [`subchains`](Self::subchains)) at which
+    /// the checkpoint file was last flushed, used to bound how far a crash can set us back
+    /// without writing a checkpoint on every single block.
+    last_checkpoint_height: u32,
+
     /// Height at which SwiftSync was aborted, if any.
     ///
     /// We abort when either the hints are found to be invalid or the current chain is invalid (we
     /// may find an invalid block or, at the end, a violation of the maximum supply limit).
     abort_height: Option<u32>,
+
+    /// Per-subchain download cursors, keyed by the subchain's start height.
+    ///
+    /// The SwiftSync aggregator fold is additive and order-independent (each worker's `agg_re`
+    /// and unspent amount are just summed into `agg`/`supply`), so nothing below the stop height
+    /// needs to be downloaded or processed in a particular order. Rather than advancing a single
+    /// cursor from the validation index up to the stop height — which serializes progress behind
+    /// whichever peer is slowest to answer next — the range is split into fixed-size subchains
+    /// (see [`SUBCHAIN_SIZE`]) that are requested independently, so many peers can each be
+    /// downloading a different slice of the range at once.
+    subchains: BTreeMap<u32, SubchainState>,
+
+    /// Rolling download-performance stats per peer that has answered at least one block request,
+    /// folded in as blocks arrive. Used by [`UtreexoNode::maybe_retry_stalled_blocks`] to judge
+    /// how long a block should plausibly take before it's considered stalled.
+    peer_stats: HashMap<PeerId, PeerStats>,
+
+    /// When each currently-outstanding block was last (re-)requested and how many times we've
+    /// speculatively retried it. Entries are created when a block is first requested and removed
+    /// as soon as it arrives, regardless of which peer it came from.
+    dispatches: HashMap<BlockHash, BlockDispatch>,
+
+    /// When [`UtreexoNode::sample_for_stall`] last sampled aggregate download progress. `None`
+    /// until the first sample, which is taken immediately rather than delayed a full
+    /// `STALL_SAMPLE_INTERVAL`.
+    last_stall_sample: Option<Instant>,
+
+    /// Count of processed heights as of the last stall sample, so each sample only needs to
+    /// compare against this instead of rescanning [`processed`](Self::processed) from scratch.
+    processed_at_last_sample: u32,
 }
 
 impl NodeContext for SwiftSync {
@@ -93,6 +144,302 @@ impl NodeContext for SwiftSync {
 // This is more than enough to avoid CPU from ever becoming a bottleneck
 const MAX_PARALLEL_WORKERS: usize = 6;
 
+/// How many heights to advance between checkpoint flushes. A few thousand blocks bounds how much
+/// re-download and re-processing a crash can cost us, without writing to disk on every block.
+const CHECKPOINT_INTERVAL: u32 = 2_000;
+
+/// Size, in heights, of one subchain (see [`SwiftSync::subchains`]). Mirrors OpenEthereum's
+/// range/subchain sync strategy: a chunk large enough to keep a peer busy for a while, but small
+/// enough that many can be in flight to different peers at once.
+const SUBCHAIN_SIZE: u32 = 2_000;
+
+/// How many subchains can be requested concurrently. Bounds how thin requests get spread across
+/// the range; `get_blocks_to_download` tops each of these up to `BLOCKS_PER_GETDATA` per call, so
+/// this is effectively how many independent download streams are kept busy at once.
+const MAX_ACTIVE_SUBCHAINS: usize = 8;
+
+/// Download cursor for one [`SUBCHAIN_SIZE`]-sized slice of the SwiftSync height range, keyed by
+/// its start height in [`SwiftSync::subchains`].
+///
+/// Whether a given height within the subchain has actually been *processed* (folded into the
+/// aggregator) is tracked separately, in [`SwiftSync::processed`] — this only tracks how far the
+/// subchain's own GETDATA requests have advanced.
+#[derive(Clone, Copy)]
+struct SubchainState {
+    /// Last height in this subchain (inclusive): `min(start + SUBCHAIN_SIZE - 1, stop_height)`.
+    end: u32,
+
+    /// Next height in this subchain we haven't yet sent a GETDATA for.
+    next_request: u32,
+}
+
+impl SubchainState {
+    /// Whether every height in this subchain has had a GETDATA sent for it.
+    fn is_requested(&self) -> bool {
+        self.next_request > self.end
+    }
+}
+
+/// Smoothing factor for the per-peer throughput/latency EWMAs in [`PeerStats`]. Closer to `1.0`
+/// weighs recent samples more heavily, so the scheduler adapts quickly to a peer slowing down or
+/// speeding up instead of averaging that out over a long window.
+const PEER_STATS_ALPHA: f64 = 0.3;
+
+/// Once a block has been outstanding for longer than the best peer's mean latency times this
+/// factor, it's considered stalled and speculatively re-requested rather than waiting out the
+/// flat `REQUEST_TIMEOUT` the shared `check_for_timeout` enforces.
+const STALL_LATENCY_MULTIPLIER: f64 = 4.0;
+
+/// After this many speculative retries, a still-outstanding block is logged as a hard warning:
+/// something beyond ordinary peer flakiness is likely going on.
+const MAX_BLOCK_RETRIES: u32 = 5;
+
+/// How often [`UtreexoNode::sample_for_stall`] checks aggregate download progress. Kept
+/// independent of and much coarser than `MAINTENANCE_TICK`, since judging "are we stalled"
+/// needs a window, not a per-tick snapshot. Mirrors btcd's `netsync` stall-detection interval.
+const STALL_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// If fewer than this many new heights were processed over a `STALL_SAMPLE_INTERVAL` window,
+/// overall progress is considered stalled and we look for a specific peer to blame.
+const MIN_PROGRESS_PER_WINDOW: u32 = 1;
+
+/// A peer whose most recent delivery is older than this (a peer that has never delivered
+/// anything always counts as older) is evicted outright once progress has stalled, rather than
+/// waiting on `check_for_timeout`/`maybe_retry_stalled_blocks` to eventually give up on it.
+const MAX_STALL_DURATION: Duration = Duration::from_secs(45);
+
+/// How long `run` waits before retrying a hints file that [`HintFileError::NeedMoreData`] came
+/// back for, e.g. one a concurrent `hints_gen` pass is still writing.
+const HINTS_FILE_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum number of already-queued peer messages `run`'s main loop will drain in one wakeup
+/// before yielding back to `select!`. With `MAX_INFLIGHT_REQUESTS` at 100 and many peers
+/// connected at once, an unbounded `while let Ok(msg) = try_recv()` drain can keep the loop busy
+/// long enough to starve the `biased` maintenance ticker — the same freeze Substrate's network
+/// worker fixed by capping messages processed per poll. Any message left over once the budget is
+/// spent simply stays queued in the channel for the next wakeup.
+const MAX_DRAIN_PER_WAKEUP: usize = 64;
+
+/// Rolling download-performance stats for one peer, following iroh's downloader design: bytes per
+/// second and mean request-to-arrival latency, each an exponential moving average so recent
+/// samples dominate.
+#[derive(Clone, Copy, Debug, Default)]
+struct PeerStats {
+    ewma_throughput_bps: f64,
+    ewma_latency_secs: f64,
+
+    /// When this peer last delivered a block, if ever. `None` for a peer that's been connected
+    /// and assigned work but hasn't answered yet — the stall-sampling pass in
+    /// `UtreexoNode::sample_for_stall` treats that the same as a very old delivery.
+    last_delivery: Option<Instant>,
+}
+
+impl PeerStats {
+    /// Folds in one more `(bytes received, request-to-arrival latency)` sample.
+    fn update(&mut self, bytes: f64, elapsed_secs: f64) {
+        let throughput = if elapsed_secs > 0.0 {
+            bytes / elapsed_secs
+        } else {
+            bytes
+        };
+
+        if self.ewma_latency_secs == 0.0 {
+            // First sample for this peer: take it as-is instead of blending with the zero default.
+            self.ewma_throughput_bps = throughput;
+            self.ewma_latency_secs = elapsed_secs;
+        } else {
+            self.ewma_throughput_bps += PEER_STATS_ALPHA * (throughput - self.ewma_throughput_bps);
+            self.ewma_latency_secs += PEER_STATS_ALPHA * (elapsed_secs - self.ewma_latency_secs);
+        }
+
+        self.last_delivery = Some(Instant::now());
+    }
+
+    /// How long it's been since this peer last delivered a block, treating "never" as an
+    /// unbounded stall.
+    fn stalled_for(&self) -> Duration {
+        self.last_delivery
+            .map_or(Duration::MAX, |t| t.elapsed())
+    }
+}
+
+/// One outstanding GETDATA for a single block: when it was last (re-)requested, and how many
+/// times we've speculatively retried it since the first request.
+#[derive(Clone, Copy)]
+struct BlockDispatch {
+    requested_at: Instant,
+    retries: u32,
+}
+
+/// Sets the bit for `height` in a processed-heights bitmap.
+fn mark_processed(processed: &mut [u8], height: u32) {
+    let idx = height as usize;
+    processed[idx / 8] |= 1 << (idx % 8);
+}
+
+/// Checks whether `height`'s bit is set in a processed-heights bitmap.
+fn is_processed(processed: &[u8], height: u32) -> bool {
+    let idx = height as usize;
+    processed
+        .get(idx / 8)
+        .is_some_and(|byte| (byte >> (idx % 8)) & 1 == 1)
+}
+
+/// Bytes identifying which hints file a checkpoint was produced against: the hints file's own
+/// magic and version bytes (see [`Hints::from_file`]), plus its `stop_height`. A checkpoint whose
+/// identity doesn't match the hints file we're about to resume with is for some other run (a
+/// different hints file, or the same file regenerated with a different range) and must be
+/// discarded rather than reloaded.
+fn hints_identity(stop_height: u32) -> Vec<u8> {
+    let mut identity = Vec::with_capacity(9);
+    identity.extend_from_slice(&[0x55, 0x54, 0x58, 0x4f]); // Hints::from_file's magic
+    identity.push(0x00); // Hints::from_file's version
+    identity.extend_from_slice(&stop_height.to_le_bytes());
+    identity
+}
+
+/// On-disk SwiftSync progress checkpoint, periodically flushed to
+/// `{datadir}/{network}.swiftsync.state` so a restart can resume a run instead of redownloading
+/// and reprocessing the whole SwiftSync window from genesis.
+struct SwiftSyncCheckpoint {
+    hints_identity: Vec<u8>,
+    salt_seed: (u64, u64, u64, u64),
+    agg_bytes: Vec<u8>,
+    supply_sat: u64,
+    subchains: BTreeMap<u32, SubchainState>,
+    processed: Vec<u8>,
+}
+
+impl SwiftSyncCheckpoint {
+    fn write(&self, path: &str) -> io::Result<()> {
+        let mut buf = Vec::new();
+        Self::write_len_prefixed(&mut buf, &self.hints_identity);
+        buf.extend_from_slice(&self.salt_seed.0.to_le_bytes());
+        buf.extend_from_slice(&self.salt_seed.1.to_le_bytes());
+        buf.extend_from_slice(&self.salt_seed.2.to_le_bytes());
+        buf.extend_from_slice(&self.salt_seed.3.to_le_bytes());
+        Self::write_len_prefixed(&mut buf, &self.agg_bytes);
+        buf.extend_from_slice(&self.supply_sat.to_le_bytes());
+        Self::write_subchains(&mut buf, &self.subchains);
+        Self::write_len_prefixed(&mut buf, &self.processed);
+
+        fs::write(path, buf)
+    }
+
+    fn write_len_prefixed(buf: &mut Vec<u8>, bytes: &[u8]) {
+        buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(bytes);
+    }
+
+    fn write_subchains(buf: &mut Vec<u8>, subchains: &BTreeMap<u32, SubchainState>) {
+        buf.extend_from_slice(&(subchains.len() as u32).to_le_bytes());
+        for (start, state) in subchains {
+            buf.extend_from_slice(&start.to_le_bytes());
+            buf.extend_from_slice(&state.end.to_le_bytes());
+            buf.extend_from_slice(&state.next_request.to_le_bytes());
+        }
+    }
+
+    fn read_len_prefixed(file: &mut File) -> io::Result<Vec<u8>> {
+        let mut len = [0; 4];
+        file.read_exact(&mut len)?;
+
+        let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_subchains(file: &mut File) -> io::Result<BTreeMap<u32, SubchainState>> {
+        let mut count = [0; 4];
+        file.read_exact(&mut count)?;
+        let count = u32::from_le_bytes(count);
+
+        let mut subchains = BTreeMap::new();
+        for _ in 0..count {
+            let mut start = [0; 4];
+            file.read_exact(&mut start)?;
+            let start = u32::from_le_bytes(start);
+
+            let mut end = [0; 4];
+            file.read_exact(&mut end)?;
+            let end = u32::from_le_bytes(end);
+
+            let mut next_request = [0; 4];
+            file.read_exact(&mut next_request)?;
+            let next_request = u32::from_le_bytes(next_request);
+
+            subchains.insert(start, SubchainState { end, next_request });
+        }
+
+        Ok(subchains)
+    }
+
+    fn read(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+
+        let hints_identity = Self::read_len_prefixed(&mut file)?;
+
+        let mut seed = [0; 32];
+        file.read_exact(&mut seed)?;
+        let salt_seed = (
+            u64::from_le_bytes(seed[0..8].try_into().unwrap()),
+            u64::from_le_bytes(seed[8..16].try_into().unwrap()),
+            u64::from_le_bytes(seed[16..24].try_into().unwrap()),
+            u64::from_le_bytes(seed[24..32].try_into().unwrap()),
+        );
+
+        let agg_bytes = Self::read_len_prefixed(&mut file)?;
+
+        let mut supply_sat = [0; 8];
+        file.read_exact(&mut supply_sat)?;
+        let supply_sat = u64::from_le_bytes(supply_sat);
+
+        let subchains = Self::read_subchains(&mut file)?;
+        let processed = Self::read_len_prefixed(&mut file)?;
+
+        Ok(Self {
+            hints_identity,
+            salt_seed,
+            agg_bytes,
+            supply_sat,
+            subchains,
+            processed,
+        })
+    }
+}
+
+/// Parses the SwiftSync hints file and returns the [`Hints`] struct.
+///
+/// Returns [`HintFileError::NeedMoreData`] rather than panicking if the file exists but isn't
+/// fully written yet (e.g. a concurrent `hints_gen` pass is still producing it); callers that can
+/// retry should do so instead of treating this the same as a corrupt file.
+fn parse_hints_file(datadir: &str, network: Network) -> Result<Hints, HintFileError> {
+    let path = format!("{datadir}/{network}.hints");
+
+    let hints_file = File::open(path).expect("invalid hints file path");
+    Hints::from_file(hints_file)
+}
+
+/// Parses the hints file like [`parse_hints_file`], but retries every
+/// [`HINTS_FILE_RETRY_INTERVAL`] on [`HintFileError::NeedMoreData`] instead of giving up,
+/// since that just means a concurrent `hints_gen` pass hasn't finished writing it yet. Shared by
+/// [`SwiftSync`] and `HintsAudit`, which both start from the same hints file.
+pub(crate) async fn parse_hints_file_with_retry(datadir: &str, network: Network) -> Hints {
+    loop {
+        match parse_hints_file(datadir, network) {
+            Ok(hints) => break hints,
+            Err(HintFileError::NeedMoreData) => {
+                debug!(
+                    "Hints file isn't fully written yet, retrying in \
+                     {HINTS_FILE_RETRY_INTERVAL:?}"
+                );
+                time::sleep(HINTS_FILE_RETRY_INTERVAL).await;
+            }
+            Err(e) => panic!("invalid or truncated hints file: {e}"),
+        }
+    }
+}
+
 /// Node methods for a [`UtreexoNode`] where its Context is [`SwiftSync`].
 /// See [node](crates/floresta-wire/src/p2p_wire/node.rs) for more information.
 impl<Chain> UtreexoNode<Chain, SwiftSync>
@@ -100,23 +447,59 @@ where
     Chain: ThreadSafeChain,
     WireError: From<Chain::Error>,
 {
-    /// Parses the SwiftSync hints file and returns the [`Hints`] struct.
-    fn parse_hints_file(datadir: &str, network: Network) -> Hints {
-        let path = format!("{datadir}/{network}.hints");
-
-        let hints_file = File::open(path).expect("invalid hints file path");
-        Hints::from_file(hints_file)
-    }
-
-    /// Generates a random salt for this SwiftSync session.
-    fn generate_salt() -> Arc<SipHashKeys> {
+    /// Generates a random salt for this SwiftSync session, returning both the derived keys and
+    /// the raw seed they were built from, since the seed is what gets persisted for a resume.
+    fn generate_salt() -> (Arc<SipHashKeys>, (u64, u64, u64, u64)) {
         let mut rng = OsRng;
-        Arc::new(SipHashKeys::new(
+        let seed = (
             rng.next_u64(),
             rng.next_u64(),
             rng.next_u64(),
             rng.next_u64(),
-        ))
+        );
+
+        let keys = Arc::new(SipHashKeys::new(seed.0, seed.1, seed.2, seed.3));
+        (keys, seed)
+    }
+
+    /// Path of this session's SwiftSync checkpoint file.
+    fn checkpoint_path(&self) -> String {
+        format!("{}/{}.swiftsync.state", self.datadir, self.network)
+    }
+
+    /// Loads the checkpoint at [`UtreexoNode::checkpoint_path`], if one exists and matches
+    /// `identity` (see [`hints_identity`]).
+    fn load_checkpoint(&self, identity: &[u8]) -> Option<SwiftSyncCheckpoint> {
+        let checkpoint = SwiftSyncCheckpoint::read(&self.checkpoint_path()).ok()?;
+        (checkpoint.hints_identity == identity).then_some(checkpoint)
+    }
+
+    /// Persists the in-progress SwiftSync state, so a crash or restart can resume from here
+    /// instead of redownloading and reprocessing the whole range from genesis.
+    fn flush_checkpoint(&mut self, hints: &Hints) -> io::Result<()> {
+        let checkpoint = SwiftSyncCheckpoint {
+            hints_identity: hints_identity(hints.stop_height),
+            salt_seed: self.context.salt_seed,
+            agg_bytes: self.context.agg.to_bytes(),
+            supply_sat: self.context.supply.to_sat(),
+            subchains: self.context.subchains.clone(),
+            processed: self.context.processed.clone(),
+        };
+
+        checkpoint.write(&self.checkpoint_path())?;
+        self.context.last_checkpoint_height = self.requested_progress();
+        Ok(())
+    }
+
+    /// Deletes the checkpoint, if any. Called once SwiftSync stops, successfully or not, since a
+    /// checkpoint is only ever meaningful for resuming the run that produced it.
+    fn delete_checkpoint(&self) {
+        let path = self.checkpoint_path();
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to remove SwiftSync checkpoint at {path}: {e:?}");
+            }
+        }
     }
 
     /// Returns `true` if SwiftSync failed, due to the hints being invalid or the current chain
@@ -125,40 +508,247 @@ where
         self.context.abort_height.is_some()
     }
 
-    /// Computes the next blocks to request, and sends a GETDATA request, advancing
-    /// `last_block_request` up to the SwiftSync hints `stop_height`.
-    fn get_blocks_to_download(&mut self, stop_height: u32) {
-        // If this request would make our inflight queue too long, postpone it
-        if !self.can_request_more_blocks() || self.was_aborted() {
+    /// Splits `(start, stop_height]` into fixed-size [`SUBCHAIN_SIZE`] chunks and seeds
+    /// [`SwiftSync::subchains`] with one entry per chunk, each ready to be requested starting from
+    /// its first height.
+    ///
+    /// Only called on a fresh (non-resumed) run; a resumed run restores `subchains` straight from
+    /// the checkpoint instead, since they may already be partially requested.
+    fn init_subchains(&mut self, start: u32, stop_height: u32) {
+        let mut chunk_start = start + 1;
+        while chunk_start <= stop_height {
+            let end = (chunk_start + SUBCHAIN_SIZE - 1).min(stop_height);
+            self.context.subchains.insert(
+                chunk_start,
+                SubchainState {
+                    end,
+                    next_request: chunk_start,
+                },
+            );
+            chunk_start = end + 1;
+        }
+    }
+
+    /// Sum, across all subchains, of how many heights have had a GETDATA sent for them. Used only
+    /// to pace checkpoint flushes; monotonically non-decreasing as subchains are requested.
+    fn requested_progress(&self) -> u32 {
+        self.context
+            .subchains
+            .iter()
+            .map(|(start, s)| s.next_request - start)
+            .sum()
+    }
+
+    /// Tops up every subchain that still has unrequested heights, sending a GETDATA request for
+    /// each, up to [`MAX_ACTIVE_SUBCHAINS`] subchains per call. Since the SwiftSync aggregator
+    /// fold is additive and order-independent, subchains are requested and processed fully
+    /// independently of one another, so many of them can be in flight to different peers at once
+    /// instead of a single contiguous stream serializing progress behind whichever peer is
+    /// slowest.
+    fn get_blocks_to_download(&mut self) {
+        if self.was_aborted() {
             return;
         }
 
-        let prev_last_request = self.last_block_request;
-        let mut blocks = Vec::with_capacity(SwiftSync::BLOCKS_PER_GETDATA);
+        let active: Vec<u32> = self
+            .context
+            .subchains
+            .iter()
+            .filter(|(_, s)| !s.is_requested())
+            .take(MAX_ACTIVE_SUBCHAINS)
+            .map(|(start, _)| *start)
+            .collect();
 
-        for _ in 0..SwiftSync::BLOCKS_PER_GETDATA {
-            let next_height = self.last_block_request + 1;
-            if next_height > stop_height {
-                // We need to reach it but not exceed it
+        for start in active {
+            // If this request would make our inflight queue too long, postpone it
+            if !self.can_request_more_blocks() {
                 break;
             }
 
-            let Ok(next_block) = self.chain.get_block_hash(next_height) else {
+            self.request_subchain_batch(start);
+        }
+    }
+
+    /// Requests up to `BLOCKS_PER_GETDATA` not-yet-requested heights from the subchain starting
+    /// at `start`, advancing its cursor on success.
+    fn request_subchain_batch(&mut self, start: u32) {
+        let Some(subchain) = self.context.subchains.get(&start).copied() else {
+            return;
+        };
+
+        let mut next_request = subchain.next_request;
+        let mut blocks = Vec::with_capacity(SwiftSync::BLOCKS_PER_GETDATA);
+
+        while next_request <= subchain.end && blocks.len() < SwiftSync::BLOCKS_PER_GETDATA {
+            let Ok(hash) = self.chain.get_block_hash(next_request) else {
                 // Likely end of chain (e.g., `BlockNotPresent`)
                 break;
             };
 
-            blocks.push(next_block);
-            self.last_block_request += 1;
+            blocks.push(hash);
+            next_request += 1;
         }
 
-        if let Err(e) = self.request_blocks(blocks) {
-            // Rollback so we can retry the same heights next time.
-            error!("Failed to request blocks: {e:?}");
-            self.last_block_request = prev_last_request;
+        if blocks.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.request_blocks(blocks.clone()) {
+            // Leave the cursor untouched, so we retry the same heights next time.
+            error!("Failed to request blocks for subchain starting at {start}: {e:?}");
+            return;
         }
         // If `request_blocks` succeeds, we will keep track of the requests in `self.inflight`,
         // so even if the remote peer disconnects, we can still re-request them.
+
+        let now = Instant::now();
+        for hash in blocks {
+            self.context.dispatches.insert(
+                hash,
+                BlockDispatch {
+                    requested_at: now,
+                    retries: 0,
+                },
+            );
+        }
+
+        if let Some(subchain) = self.context.subchains.get_mut(&start) {
+            subchain.next_request = next_request;
+        }
+    }
+
+    /// Speculatively re-requests blocks that have been outstanding much longer than peers have
+    /// recently taken to answer, instead of waiting out the flat `REQUEST_TIMEOUT` that the
+    /// shared `check_for_timeout` enforces. One slow or stuck peer shouldn't get to stall the
+    /// same heights for the full timeout when other peers are already answering quickly.
+    ///
+    /// NOTE: blocks are re-enqueued through `request_blocks`, which owns peer selection for a
+    /// GETDATA; we can't pin a retry to a specific peer here, only nudge it to happen again sooner
+    /// than the default timeout would. `peer_stats` is still what answers "how long is too long".
+    fn maybe_retry_stalled_blocks(&mut self) -> Result<(), WireError> {
+        let best_latency = self
+            .context
+            .peer_stats
+            .values()
+            .map(|s| s.ewma_latency_secs)
+            .filter(|l| *l > 0.0)
+            .fold(None, |acc: Option<f64>, l| Some(acc.map_or(l, |a| a.min(l))));
+
+        // No stats yet (e.g. right after a fresh start): nothing to judge "stalled" against, so
+        // defer entirely to `check_for_timeout`.
+        let Some(best_latency) = best_latency else {
+            return Ok(());
+        };
+
+        let deadline = Duration::from_secs_f64((best_latency * STALL_LATENCY_MULTIPLIER).max(1.0));
+
+        let stalled: Vec<BlockHash> = self
+            .context
+            .dispatches
+            .iter()
+            .filter(|(_, d)| d.requested_at.elapsed() > deadline)
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        for hash in stalled {
+            let retries = self.context.dispatches[&hash].retries;
+
+            if retries >= MAX_BLOCK_RETRIES {
+                warn!(
+                    "SwiftSync: block {hash} has been retried {retries} times and is still \
+                     outstanding; a peer may be stuck or misbehaving"
+                );
+                // Stop chasing it every tick; `check_for_timeout` still owns the block until it
+                // eventually arrives (or its peer is disconnected).
+                self.context.dispatches.remove(&hash);
+                continue;
+            }
+
+            debug!("SwiftSync: block {hash} looks stalled after {deadline:?}, retrying");
+            if let Err(e) = self.request_blocks(vec![hash]) {
+                error!("Failed to retry stalled block {hash}: {e:?}");
+                continue;
+            }
+
+            if let Some(dispatch) = self.context.dispatches.get_mut(&hash) {
+                dispatch.requested_at = Instant::now();
+                dispatch.retries += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count of heights folded into the aggregator so far.
+    fn processed_count(&self) -> u32 {
+        self.context
+            .processed
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum()
+    }
+
+    /// On a fixed `STALL_SAMPLE_INTERVAL`, checks whether aggregate download progress has
+    /// stalled and, if so, disconnects and bans whichever connected peer looks responsible —
+    /// one that's never delivered a block, or whose most recent delivery is older than
+    /// `MAX_STALL_DURATION` — then opens a replacement connection immediately. Ports btcd's
+    /// `netsync` stall-detection approach: unlike `ASSUME_STALE`, which only widens the
+    /// connection pool once the whole node looks stuck, this targets the specific peer that's
+    /// likely holding everyone back.
+    fn sample_for_stall(&mut self) -> Result<(), WireError> {
+        let now = Instant::now();
+
+        let Some(last_sample) = self.context.last_stall_sample else {
+            self.context.last_stall_sample = Some(now);
+            self.context.processed_at_last_sample = self.processed_count();
+            return Ok(());
+        };
+
+        if now.duration_since(last_sample) < STALL_SAMPLE_INTERVAL {
+            return Ok(());
+        }
+
+        let processed = self.processed_count();
+        let progress = processed.saturating_sub(self.context.processed_at_last_sample);
+        self.context.last_stall_sample = Some(now);
+        self.context.processed_at_last_sample = processed;
+
+        if progress >= MIN_PROGRESS_PER_WINDOW {
+            return Ok(());
+        }
+
+        let stalled_peer = self
+            .context
+            .peer_stats
+            .iter()
+            .filter(|(_, stats)| stats.stalled_for() > MAX_STALL_DURATION)
+            .max_by_key(|(_, stats)| stats.stalled_for())
+            .map(|(peer, _)| *peer);
+
+        let Some(peer_id) = stalled_peer else {
+            // Progress stalled, but nobody's individually over the line yet (e.g. we're between
+            // peers entirely); `assume_stale`'s extra connection remains the fallback here.
+            return Ok(());
+        };
+
+        warn!(
+            "SwiftSync: progress stalled (<{MIN_PROGRESS_PER_WINDOW} heights processed in \
+             {STALL_SAMPLE_INTERVAL:?}) and peer {peer_id} hasn't delivered a block in over \
+             {MAX_STALL_DURATION:?}; disconnecting it"
+        );
+
+        if let Some(peer) = self.peers.get(&peer_id).cloned() {
+            self.address_man.update_set_state(
+                peer.address_id as usize,
+                AddressState::Banned(SwiftSync::BAN_TIME),
+            );
+        }
+
+        self.context.peer_stats.remove(&peer_id);
+        self.send_to_peer(peer_id, NodeRequest::Shutdown)?;
+        try_and_log!(self.create_connection(ConnectionKind::Extra));
+
+        Ok(())
     }
 
     /// Starts SwiftSync processing for up to `MAX_PARALLEL_WORKERS` pending blocks.
@@ -191,6 +781,16 @@ where
                 // NOTE: if a previous block was invalid, we will get this error
                 .ok_or(BlockchainError::OrphanOrInvalidBlock)?;
 
+            if is_processed(&self.context.processed, height) {
+                // Already folded into the aggregator before a prior restart: this copy was
+                // redownloaded because a subchain's request cursor had advanced past this height
+                // before the checkpoint that resumed it was flushed. Discard it instead of
+                // reprocessing, which would double-count it.
+                debug!("Skipping already-processed SwiftSync block at height {height}");
+                self.blocks.remove(&hash);
+                continue;
+            }
+
             self.start_processing_swiftsync(hash, height, hints)?;
         }
 
@@ -213,7 +813,17 @@ where
         if entry.processing_since.is_some() {
             return Ok(()); // already being processed
         }
-        let unspent_indexes: HashSet<u64> = hints.get_indexes(block_height).into_iter().collect();
+        let unspent_indexes: HashSet<u64> = match hints.get_indexes(block_height) {
+            Ok(indexes) => indexes.into_iter().collect(),
+            Err(HintFileError::NeedMoreData) => {
+                // The hints file doesn't cover this height yet (e.g. a concurrent `hints_gen`
+                // pass is still writing it). Leave the block queued and retry it the next time
+                // `pump_swiftsync` runs instead of treating this like a corrupt file.
+                debug!("Hints file doesn't cover height {block_height} yet, retrying later");
+                return Ok(());
+            }
+            Err(e) => panic!("hints file covers every height up to stop_height: {e}"),
+        };
 
         // Start the processing timer
         entry.processing_since = Some(Instant::now());
@@ -255,13 +865,41 @@ where
     pub async fn run(mut self, done_cb: impl FnOnce(&Chain)) -> Self {
         info!("Starting SwiftSync node...");
         self.last_block_request = self.chain.get_validation_index().unwrap();
-        assert_eq!(self.last_block_request, 0);
 
-        // Parse the hints file and randomly fill the SwiftSync salt for this session
-        let mut hints = Self::parse_hints_file(&self.datadir, self.network);
+        // Parse the hints file and either resume from a matching checkpoint or start fresh with
+        // a randomly generated salt for this session.
+        let mut hints = parse_hints_file_with_retry(&self.datadir, self.network).await;
+        let identity = hints_identity(hints.stop_height);
+
+        if let Some(checkpoint) = self.load_checkpoint(&identity) {
+            info!(
+                "Resuming SwiftSync from a checkpoint with {} of {} subchains fully requested",
+                checkpoint
+                    .subchains
+                    .values()
+                    .filter(|s| s.is_requested())
+                    .count(),
+                checkpoint.subchains.len(),
+            );
 
-        // Generate the random salt
-        self.context.salt = Self::generate_salt();
+            let (k0, k1, k2, k3) = checkpoint.salt_seed;
+            self.context.salt = Arc::new(SipHashKeys::new(k0, k1, k2, k3));
+            self.context.salt_seed = checkpoint.salt_seed;
+            self.context.agg = SwiftSyncAgg::from_bytes(&checkpoint.agg_bytes);
+            self.context.supply = Amount::from_sat(checkpoint.supply_sat);
+            self.context.processed = checkpoint.processed;
+            self.context.subchains = checkpoint.subchains;
+        } else {
+            assert_eq!(self.last_block_request, 0);
+
+            let (salt, salt_seed) = Self::generate_salt();
+            self.context.salt = salt;
+            self.context.salt_seed = salt_seed;
+            self.context.processed = vec![0; (hints.stop_height as usize / 8) + 1];
+            self.init_subchains(self.last_block_request, hints.stop_height);
+        }
+
+        self.context.last_checkpoint_height = self.requested_progress();
 
         info!("Performing SwiftSync up to height {}", hints.stop_height);
 
@@ -287,8 +925,14 @@ where
                     // We only update the aggregator when reading responses from the workers
                     try_and_log!(self.handle_message(msg, &mut hints).await);
 
-                    // Drain all queued messages
-                    while let Ok(msg) = self.node_rx.try_recv() {
+                    // Drain up to MAX_DRAIN_PER_WAKEUP-1 more already-queued messages, then yield
+                    // back to `select!` regardless of whether the channel is empty yet, so a
+                    // message flood can't starve the maintenance ticker. Anything left queued is
+                    // simply handled on the next wakeup.
+                    for _ in 1..MAX_DRAIN_PER_WAKEUP {
+                        let Ok(msg) = self.node_rx.try_recv() else {
+                            break;
+                        };
                         try_and_log!(self.handle_message(msg, &mut hints).await);
                     }
                     if *self.kill_signal.read().await {
@@ -320,9 +964,14 @@ where
             return LoopControl::Break;
         }
 
-        // If we have reached the SwiftSync stop height, we aren't waiting for inflight requested
-        // blocks, and there's no in-memory block being processed, we have finished.
-        if self.last_block_request == hints.stop_height && self.unprocessed_blocks() == 0 {
+        // If every subchain has had all its heights requested, we aren't waiting for inflight
+        // requested blocks, and there's no in-memory block being processed, we have finished.
+        let fully_requested = self
+            .context
+            .subchains
+            .values()
+            .all(SubchainState::is_requested);
+        if fully_requested && self.unprocessed_blocks() == 0 {
             self.handle_stop_height_reached(hints.stop_height);
             return LoopControl::Break;
         }
@@ -342,6 +991,13 @@ where
         // Re-request blocks that haven't arrived in `SwiftSync::REQUEST_TIMEOUT` seconds
         try_and_log!(self.check_for_timeout());
 
+        // Re-request blocks that look stalled well before `REQUEST_TIMEOUT` would fire, judged
+        // against how fast peers have actually been answering recently.
+        try_and_log!(self.maybe_retry_stalled_blocks());
+
+        // Identify and evict a specific peer if it looks responsible for stalled progress.
+        try_and_log!(self.sample_for_stall());
+
         let assume_stale = Instant::now()
             .duration_since(self.common.last_tip_update)
             .as_secs()
@@ -353,9 +1009,13 @@ where
             return LoopControl::Continue;
         }
 
+        if self.requested_progress() - self.context.last_checkpoint_height >= CHECKPOINT_INTERVAL {
+            try_and_log!(self.flush_checkpoint(hints));
+        }
+
         try_and_log!(self.pump_swiftsync(hints));
 
-        self.get_blocks_to_download(hints.stop_height);
+        self.get_blocks_to_download();
         LoopControl::Continue
     }
 
@@ -371,6 +1031,7 @@ where
             error!("SwiftSync failed with the provided hints file; end aggregator is not zero");
 
             self.context.abort_height = Some(stop_height);
+            self.delete_checkpoint();
             return;
         }
 
@@ -379,10 +1040,12 @@ where
             error!("Aborting SwiftSync: most PoW chain has excess supply ({final_supply})");
 
             self.context.abort_height = Some(stop_height);
+            self.delete_checkpoint();
             return;
         }
 
         info!("SwiftSync is finished, switching to normal operation mode");
+        self.delete_checkpoint();
         let tip_hash = self.chain.get_block_hash(stop_height).unwrap();
 
         self.chain
@@ -430,6 +1093,15 @@ where
                             return Ok(());
                         };
 
+                        if let Some(dispatch) = self.context.dispatches.remove(&hash) {
+                            let elapsed = dispatch.requested_at.elapsed().as_secs_f64();
+                            self.context
+                                .peer_stats
+                                .entry(peer)
+                                .or_default()
+                                .update(block.total_size() as f64, elapsed);
+                        }
+
                         // Reply and return early if it's a user-requested block. Else continue handling it.
                         let Some(block) = self.check_is_user_block_and_reply(block)? else {
                             return Ok(());
@@ -445,14 +1117,18 @@ where
                         self.blocks.insert(hash, inflight_block);
 
                         self.pump_swiftsync(hints)?;
-                        self.get_blocks_to_download(hints.stop_height);
+                        self.get_blocks_to_download();
                     }
 
                     PeerMessages::Ready(version) => {
+                        // Registered eagerly (rather than lazily on first delivery) so a peer
+                        // that never answers anything is still a candidate for `sample_for_stall`.
+                        self.context.peer_stats.entry(peer).or_default();
                         try_and_log!(self.handle_peer_ready(peer, &version));
                     }
 
                     PeerMessages::Disconnected(idx) => {
+                        self.context.peer_stats.remove(&peer);
                         try_and_log!(self.handle_disconnection(peer, idx));
                     }
 
@@ -492,11 +1168,13 @@ where
         match result {
             Err(e) => {
                 self.context.abort_height = Some(height);
+                self.delete_checkpoint();
                 self.handle_invalid_block(e, block.block.header, block.peer)?
             }
             Ok((agg_re, unspent_amount)) => {
                 self.context.agg += agg_re;
                 self.context.supply += unspent_amount;
+                mark_processed(&mut self.context.processed, height);
                 self.handle_valid_worker_block(block_hash, height, block);
             }
         };
@@ -563,8 +1241,9 @@ where
         Err(WireError::PeerMisbehaving)
     }
 
-    /// This method is currently just about updating metrics, but may be changed to persist the
-    /// SwiftSync progress.
+    /// Updates metrics for a successfully processed block. The caller is responsible for folding
+    /// its result into `self.context.agg`/`supply` and marking it processed before calling this;
+    /// the checkpoint itself is flushed separately, on [`CHECKPOINT_INTERVAL`], not per block.
     fn handle_valid_worker_block(
         &mut self,
         block_hash: BlockHash,
@@ -577,7 +1256,6 @@ where
             block.block.txdata.len(),
         );
 
-        // TODO should we flush on SwiftSync?
         // TODO notify the block
         self.last_tip_update = Instant::now();
 
@@ -602,6 +1280,53 @@ where
     }
 }
 
+/// An error reading a SwiftSync hints file (see [`Hints`]).
+#[derive(Debug)]
+pub enum HintFileError {
+    /// The version byte isn't one this build knows how to read.
+    UnsupportedVersion(u8),
+
+    /// `get_indexes` was asked for a height the index table doesn't cover.
+    HeightNotFound(u32),
+
+    /// The magic bytes didn't match, or some other I/O failure made the file unreadable.
+    Corrupt,
+
+    /// A read hit EOF partway through a record. The file may simply still be downloading; the
+    /// cursor is left where the caller can retry the same read once more bytes have landed
+    /// (see [`Hints::get_indexes`]).
+    NeedMoreData,
+}
+
+impl fmt::Display for HintFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HintFileError::UnsupportedVersion(v) => {
+                write!(f, "unsupported hints file version: {v}")
+            }
+            HintFileError::HeightNotFound(h) => {
+                write!(f, "hints file has no entry for height {h}")
+            }
+            HintFileError::Corrupt => write!(f, "hints file is corrupt or unreadable"),
+            HintFileError::NeedMoreData => {
+                write!(f, "hints file is truncated; it may still be downloading")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HintFileError {}
+
+/// Reads exactly `buf.len()` bytes, translating an EOF mid-read into
+/// [`HintFileError::NeedMoreData`] instead of treating it like any other I/O failure.
+fn read_exact_or_need_more(file: &mut File, buf: &mut [u8]) -> Result<(), HintFileError> {
+    match file.read_exact(buf) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(HintFileError::NeedMoreData),
+        Err(_) => Err(HintFileError::Corrupt),
+    }
+}
+
 #[derive(Debug)]
 pub struct Hints {
     pub(crate) map: BTreeMap<u32, u64>,
@@ -610,38 +1335,47 @@ pub struct Hints {
 }
 
 impl Hints {
-    // # Panics
-    //
-    // Panics when expected data is not present, or the hintfile overflows the maximum blockheight
-    pub fn from_file(mut file: File) -> Self {
+    /// Parses a hints file's header and index table.
+    ///
+    /// Unlike [`Hints::get_indexes`], a truncated read here has no previously-established cursor
+    /// to restore: the caller is expected to retry by reopening (or re-seeking to the start of)
+    /// the file once it believes more data has landed, rather than resuming this call in place.
+    pub fn from_file(mut file: File) -> Result<Self, HintFileError> {
         let mut map = BTreeMap::new();
+
         let mut magic = [0; 4];
-        file.read_exact(&mut magic).unwrap();
-        assert_eq!(magic, [0x55, 0x54, 0x58, 0x4f]);
+        read_exact_or_need_more(&mut file, &mut magic)?;
+        if magic != [0x55, 0x54, 0x58, 0x4f] {
+            return Err(HintFileError::Corrupt);
+        }
+
         let mut ver = [0; 1];
-        file.read_exact(&mut ver).unwrap();
-        if u8::from_le_bytes(ver) != 0x00 {
-            core::panic!("Unsupported file version.");
+        read_exact_or_need_more(&mut file, &mut ver)?;
+        if ver[0] != 0x00 {
+            return Err(HintFileError::UnsupportedVersion(ver[0]));
         }
+
         let mut stop_height = [0; 4];
-        file.read_exact(&mut stop_height).expect("empty file");
+        read_exact_or_need_more(&mut file, &mut stop_height)?;
         let stop_height = u32::from_le_bytes(stop_height);
+
         for _ in 1..=stop_height {
             let mut height = [0; 4];
-            file.read_exact(&mut height)
-                .expect("expected kv pair does not exist.");
+            read_exact_or_need_more(&mut file, &mut height)?;
             let height = u32::from_le_bytes(height);
+
             let mut file_pos = [0; 8];
-            file.read_exact(&mut file_pos)
-                .expect("expected kv pair does not exist.");
+            read_exact_or_need_more(&mut file, &mut file_pos)?;
             let file_pos = u64::from_le_bytes(file_pos);
+
             map.insert(height, file_pos);
         }
-        Self {
+
+        Ok(Self {
             map,
             file,
             stop_height,
-        }
+        })
     }
 
     /// Get the stop height of the hint file.
@@ -649,25 +1383,37 @@ impl Hints {
         self.stop_height
     }
 
-    /// # Panics
+    /// Returns the unspent output indexes recorded for `height`.
     ///
-    /// If there are no offset present at that height, aka an overflow, or the entry has already
-    /// been fetched.
-    pub fn get_indexes(&mut self, height: u32) -> Vec<u64> {
+    /// On [`HintFileError::NeedMoreData`] (the record's bytes aren't all on disk yet), the file
+    /// cursor is seeked back to the entry's own `file_pos`, so the caller can simply call this
+    /// again later instead of resuming a half-read record.
+    pub fn get_indexes(&mut self, height: u32) -> Result<Vec<u64>, HintFileError> {
         let file_pos = self
             .map
             .get(&height)
-            .cloned()
-            .expect("block height overflow");
+            .copied()
+            .ok_or(HintFileError::HeightNotFound(height))?;
 
-        // Move the file cursor to the correct byte offset
         self.file
             .seek(SeekFrom::Start(file_pos))
-            .expect("missing file position.");
+            .map_err(|_| HintFileError::Corrupt)?;
+
+        let result = self.read_indexes_at_cursor();
+        if result.is_err() {
+            // Never leave a caller holding a half-read record: rewind to the entry's start so a
+            // retry re-reads the whole thing rather than resuming mid-bitmap.
+            let _ = self.file.seek(SeekFrom::Start(file_pos));
+        }
+        result
+    }
 
+    /// Reads one height's `(bit count, packed bits)` record starting at the file's current
+    /// cursor position. Assumes the caller has already seeked to the record's start.
+    fn read_indexes_at_cursor(&mut self) -> Result<Vec<u64>, HintFileError> {
         // Read the next 4 bytes (little-endian) which store how many bits follow
         let mut bits_arr = [0; 4];
-        self.file.read_exact(&mut bits_arr).unwrap();
+        read_exact_or_need_more(&mut self.file, &mut bits_arr)?;
         let num_bits = u32::from_le_bytes(bits_arr);
 
         let mut unspents = Vec::new();
@@ -677,8 +1423,8 @@ impl Hints {
             let leftovers = bit_pos % 8;
             if leftovers == 0 {
                 let mut single_byte_arr = [0; 1];
-                self.file.read_exact(&mut single_byte_arr).unwrap();
-                curr_byte = u8::from_le_bytes(single_byte_arr);
+                read_exact_or_need_more(&mut self.file, &mut single_byte_arr)?;
+                curr_byte = single_byte_arr[0];
             }
 
             // Check current bit in curr_byte; if it's 1, push this txout index
@@ -686,6 +1432,6 @@ impl Hints {
                 unspents.push(bit_pos as u64);
             }
         }
-        unspents
+        Ok(unspents)
     }
 }
@@ -0,0 +1,446 @@
+//! Pluggable persistence for the peers known to [`AddressMan`](super::address_man::AddressMan).
+//!
+//! By default, peers are persisted only through `AddressMan::dump_peers`/`start_addr_man`,
+//! which (de)serialize the *entire* known-peers table as a single `peers.json` file. That's
+//! fine at small scale, but becomes an expensive, all-or-nothing rewrite as the table grows
+//! towards `MAX_ADDRESSES`, and it throws away the fact that only one peer's state actually
+//! changed on a connect/fail/ban.
+//!
+//! [`PeerStore`] decouples *how* peers are stored from *how* `AddressMan` selects between them
+//! in memory: [`JsonFilePeerStore`] keeps today's whole-file-at-a-time behavior as the default,
+//! while `SqlitePeerStore`, behind the `sqlite-peer-store` feature, persists a single state
+//! transition at a time and survives restarts without forcing `AddressMan` to rebuild its
+//! new/tried buckets from a full rewrite.
+
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::path::PathBuf;
+
+use bitcoin::p2p::ServiceFlags;
+
+use super::address_man::AddressState;
+use super::address_man::DiskLocalAddress;
+use super::address_man::LocalAddress;
+
+/// An error returned by a [`PeerStore`] implementation.
+#[derive(Debug)]
+pub enum PeerStoreError {
+    /// Reading or writing the backing file/database failed
+    Io(std::io::Error),
+
+    /// The persisted data couldn't be (de)serialized
+    Serialization(serde_json::Error),
+
+    /// The SQLite backend hit an error
+    #[cfg(feature = "sqlite-peer-store")]
+    Sqlite(rusqlite::Error),
+}
+
+impl std::fmt::Display for PeerStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeerStoreError::Io(e) => write!(f, "peer store I/O error: {e}"),
+            PeerStoreError::Serialization(e) => write!(f, "peer store serialization error: {e}"),
+            #[cfg(feature = "sqlite-peer-store")]
+            PeerStoreError::Sqlite(e) => write!(f, "peer store sqlite error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PeerStoreError {}
+
+impl From<std::io::Error> for PeerStoreError {
+    fn from(value: std::io::Error) -> Self {
+        PeerStoreError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for PeerStoreError {
+    fn from(value: serde_json::Error) -> Self {
+        PeerStoreError::Serialization(value)
+    }
+}
+
+#[cfg(feature = "sqlite-peer-store")]
+impl From<rusqlite::Error> for PeerStoreError {
+    fn from(value: rusqlite::Error) -> Self {
+        PeerStoreError::Sqlite(value)
+    }
+}
+
+/// Persists the peers known to `AddressMan`, independently of how they're selected in memory.
+///
+/// Implementations are responsible for keeping enough information about each peer (address,
+/// services, port, state, last-connected time and consecutive-failure count) that `AddressMan`
+/// can resume from them without rebuilding its new/tried buckets from scratch. See
+/// `AddressMan::set_peer_store`.
+pub trait PeerStore {
+    /// Loads every known peer.
+    fn load_all(&self) -> Result<Vec<LocalAddress>, PeerStoreError>;
+
+    /// Inserts a new peer, or replaces the existing one with the same id.
+    fn upsert(&self, address: &LocalAddress) -> Result<(), PeerStoreError>;
+
+    /// Removes a peer by id, if present.
+    fn delete(&self, id: usize) -> Result<(), PeerStoreError>;
+
+    /// Marks a peer as successfully connected to at `when` (a UNIX timestamp).
+    fn mark_tried(&self, id: usize, when: u64) -> Result<(), PeerStoreError>;
+
+    /// Marks a peer as having failed to connect at `when`, recording the updated number of
+    /// consecutive failures (used to compute backoff, see `backoff_duration`).
+    fn mark_failed(&self, id: usize, when: u64, attempts: u32) -> Result<(), PeerStoreError>;
+
+    /// Marks a peer as banned until `when` (a UNIX timestamp).
+    fn mark_banned(&self, id: usize, when: u64) -> Result<(), PeerStoreError>;
+
+    /// Marks a peer with an arbitrary [`AddressState`] identified by its stable
+    /// [`AddressState::to_num`] tag, for states that don't have a dedicated `mark_*` method
+    /// (the richer failure-reason variants: timeouts, protocol violations, and the like). Pair
+    /// with [`AddressState::from_num`] to reconstruct the state on load.
+    fn mark_state(
+        &self,
+        id: usize,
+        tag: u8,
+        when: u64,
+        attempts: u32,
+    ) -> Result<(), PeerStoreError>;
+
+    /// Returns every known peer that advertises `service`.
+    fn iter_by_service(&self, service: ServiceFlags) -> Result<Vec<LocalAddress>, PeerStoreError>;
+}
+
+/// The default [`PeerStore`]: keeps the whole peer table as a single `peers.json` file,
+/// rewriting it in full on every write.
+///
+/// This mirrors `AddressMan`'s historical behavior, simple and dependency-free, at the cost of
+/// an all-or-nothing rewrite as the table grows towards `MAX_ADDRESSES`.
+pub struct JsonFilePeerStore {
+    path: PathBuf,
+}
+
+impl JsonFilePeerStore {
+    /// Creates a store backed by `<datadir>/peers.json`.
+    pub fn new(datadir: impl AsRef<Path>) -> Self {
+        JsonFilePeerStore {
+            path: datadir.as_ref().join("peers.json"),
+        }
+    }
+
+    fn read_all(&self) -> Result<HashMap<usize, LocalAddress>, PeerStoreError> {
+        let Ok(contents) = read_to_string(&self.path) else {
+            // No file yet is not an error: we just don't have any persisted peers.
+            return Ok(HashMap::new());
+        };
+
+        let peers: Vec<DiskLocalAddress> = serde_json::from_str(&contents)?;
+        Ok(peers
+            .into_iter()
+            .map(Into::<LocalAddress>::into)
+            .map(|address| (address.id, address))
+            .collect())
+    }
+
+    fn write_all(&self, peers: &HashMap<usize, LocalAddress>) -> Result<(), PeerStoreError> {
+        let peers: Vec<DiskLocalAddress> = peers
+            .values()
+            .cloned()
+            .filter_map(|address| DiskLocalAddress::try_from(address).ok())
+            .collect();
+        let json = serde_json::to_string(&peers)?;
+        std::fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    fn transition(&self, id: usize, state: AddressState) -> Result<(), PeerStoreError> {
+        let mut peers = self.read_all()?;
+        if let Some(address) = peers.get_mut(&id) {
+            address.set_state(state);
+        }
+
+        self.write_all(&peers)
+    }
+}
+
+impl PeerStore for JsonFilePeerStore {
+    fn load_all(&self) -> Result<Vec<LocalAddress>, PeerStoreError> {
+        Ok(self.read_all()?.into_values().collect())
+    }
+
+    fn upsert(&self, address: &LocalAddress) -> Result<(), PeerStoreError> {
+        let mut peers = self.read_all()?;
+        peers.insert(address.id, address.clone());
+        self.write_all(&peers)
+    }
+
+    fn delete(&self, id: usize) -> Result<(), PeerStoreError> {
+        let mut peers = self.read_all()?;
+        peers.remove(&id);
+        self.write_all(&peers)
+    }
+
+    fn mark_tried(&self, id: usize, when: u64) -> Result<(), PeerStoreError> {
+        self.transition(id, AddressState::Tried(when))
+    }
+
+    fn mark_failed(&self, id: usize, when: u64, attempts: u32) -> Result<(), PeerStoreError> {
+        self.transition(id, AddressState::Failed(when, attempts))
+    }
+
+    fn mark_banned(&self, id: usize, when: u64) -> Result<(), PeerStoreError> {
+        self.transition(id, AddressState::Banned(when))
+    }
+
+    fn mark_state(
+        &self,
+        id: usize,
+        tag: u8,
+        when: u64,
+        attempts: u32,
+    ) -> Result<(), PeerStoreError> {
+        self.transition(id, AddressState::from_num(tag, when, attempts))
+    }
+
+    fn iter_by_service(&self, service: ServiceFlags) -> Result<Vec<LocalAddress>, PeerStoreError> {
+        Ok(self
+            .read_all()?
+            .into_values()
+            .filter(|address| address.get_services().has(service))
+            .collect())
+    }
+}
+
+/// A [`PeerStore`] backed by a SQLite database, enabled with the `sqlite-peer-store` feature.
+///
+/// Unlike [`JsonFilePeerStore`], every write only touches the affected row: connecting, failing
+/// or banning a single peer is a single `UPDATE`, not a rewrite of every other known peer. The
+/// `peers` table keeps address, services, port, state, last_connected, attempt-count and
+/// network-group columns, with indices on `state` and `services` so peer selection queries
+/// (e.g. "give me a peer advertising Utreexo") don't require a full scan.
+#[cfg(feature = "sqlite-peer-store")]
+pub struct SqlitePeerStore {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite-peer-store")]
+impl SqlitePeerStore {
+    /// Opens (or creates) the peer database at `<datadir>/peers.sqlite3`.
+    pub fn new(datadir: impl AsRef<Path>) -> Result<Self, PeerStoreError> {
+        let conn = rusqlite::Connection::open(datadir.as_ref().join("peers.sqlite3"))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS peers (
+                id              INTEGER PRIMARY KEY,
+                address         TEXT NOT NULL,
+                network_group   TEXT NOT NULL,
+                services        INTEGER NOT NULL,
+                port            INTEGER NOT NULL,
+                state           INTEGER NOT NULL,
+                state_time      INTEGER NOT NULL,
+                attempts        INTEGER NOT NULL DEFAULT 0,
+                last_connected   INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS peers_services_idx ON peers (services);
+            CREATE INDEX IF NOT EXISTS peers_state_idx ON peers (state);",
+        )?;
+
+        Ok(SqlitePeerStore {
+            conn: std::sync::Mutex::new(conn),
+        })
+    }
+
+    /// A coarse network-group tag for `address`, used only for the indexed `network_group`
+    /// column (not to be confused with `AddressMan`'s internal bucketing, which groups more
+    /// finely to resist eclipse attacks).
+    fn network_group_tag(address: &LocalAddress) -> &'static str {
+        match address.get_address() {
+            bitcoin::p2p::address::AddrV2::Ipv4(_) => "ipv4",
+            bitcoin::p2p::address::AddrV2::Ipv6(_) => "ipv6",
+            bitcoin::p2p::address::AddrV2::TorV2(_) => "torv2",
+            bitcoin::p2p::address::AddrV2::TorV3(_) => "torv3",
+            bitcoin::p2p::address::AddrV2::I2p(_) => "i2p",
+            bitcoin::p2p::address::AddrV2::Cjdns(_) => "cjdns",
+            bitcoin::p2p::address::AddrV2::Unknown(_, _) => "unknown",
+        }
+    }
+
+    /// Breaks a state down into the `(tag, state_time, attempts)` triple stored in the `peers`
+    /// table, using [`AddressState::to_num`] for `tag` instead of a hand-picked string so the
+    /// mapping stays stable as new variants are added. Pair with [`AddressState::from_num`] to
+    /// reconstruct the state on load (see [`Self::load_one`]).
+    fn state_parts(state: &AddressState) -> (u8, u64, u32) {
+        state.parts()
+    }
+
+    fn upsert_row(
+        conn: &rusqlite::Connection,
+        address: &LocalAddress,
+    ) -> Result<(), PeerStoreError> {
+        // An address of an unrecognized network has no faithful row to write; silently skip it
+        // rather than persisting a fabricated one (see `DiskLocalAddress`'s `TryFrom`).
+        let Ok(disk) = DiskLocalAddress::try_from(address.clone()) else {
+            return Ok(());
+        };
+        let (state, state_time, attempts) = Self::state_parts(address.get_state());
+
+        conn.execute(
+            "INSERT INTO peers
+                (id, address, network_group, services, port, state, state_time, attempts, last_connected)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(id) DO UPDATE SET
+                address = excluded.address,
+                network_group = excluded.network_group,
+                services = excluded.services,
+                port = excluded.port,
+                state = excluded.state,
+                state_time = excluded.state_time,
+                attempts = excluded.attempts,
+                last_connected = excluded.last_connected",
+            rusqlite::params![
+                address.id as i64,
+                serde_json::to_string(&disk)?,
+                Self::network_group_tag(address),
+                address.get_services().to_u64() as i64,
+                address.get_port() as i64,
+                state,
+                state_time as i64,
+                attempts,
+                address.get_last_connected() as i64,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn update_state(
+        &self,
+        id: usize,
+        tag: u8,
+        state_time: u64,
+        attempts: u32,
+    ) -> Result<(), PeerStoreError> {
+        let conn = self.conn.lock().expect("peer store mutex poisoned");
+        conn.execute(
+            "UPDATE peers SET state = ?1, state_time = ?2, attempts = ?3 WHERE id = ?4",
+            rusqlite::params![tag, state_time as i64, attempts, id as i64],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite-peer-store")]
+impl PeerStore for SqlitePeerStore {
+    fn load_all(&self) -> Result<Vec<LocalAddress>, PeerStoreError> {
+        let conn = self.conn.lock().expect("peer store mutex poisoned");
+        let mut stmt = conn.prepare("SELECT id FROM peers")?;
+        let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+
+        let mut peers = Vec::new();
+        for id in ids {
+            peers.push(id?);
+        }
+
+        // Re-querying row-by-row keeps the mapping from a `peers` row back to `LocalAddress` in
+        // one place (`load_one`), rather than duplicating it for bulk loads.
+        drop(stmt);
+        peers
+            .into_iter()
+            .map(|id| Self::load_one(&conn, id))
+            .collect()
+    }
+
+    fn upsert(&self, address: &LocalAddress) -> Result<(), PeerStoreError> {
+        let conn = self.conn.lock().expect("peer store mutex poisoned");
+        Self::upsert_row(&conn, address)
+    }
+
+    fn delete(&self, id: usize) -> Result<(), PeerStoreError> {
+        let conn = self.conn.lock().expect("peer store mutex poisoned");
+        conn.execute(
+            "DELETE FROM peers WHERE id = ?1",
+            rusqlite::params![id as i64],
+        )?;
+        Ok(())
+    }
+
+    fn mark_tried(&self, id: usize, when: u64) -> Result<(), PeerStoreError> {
+        self.update_state(id, AddressState::Tried(when).to_num(), when, 0)
+    }
+
+    fn mark_failed(&self, id: usize, when: u64, attempts: u32) -> Result<(), PeerStoreError> {
+        self.update_state(
+            id,
+            AddressState::Failed(when, attempts).to_num(),
+            when,
+            attempts,
+        )
+    }
+
+    fn mark_banned(&self, id: usize, when: u64) -> Result<(), PeerStoreError> {
+        self.update_state(id, AddressState::Banned(when).to_num(), when, 0)
+    }
+
+    fn mark_state(
+        &self,
+        id: usize,
+        tag: u8,
+        when: u64,
+        attempts: u32,
+    ) -> Result<(), PeerStoreError> {
+        self.update_state(id, tag, when, attempts)
+    }
+
+    fn iter_by_service(&self, service: ServiceFlags) -> Result<Vec<LocalAddress>, PeerStoreError> {
+        let conn = self.conn.lock().expect("peer store mutex poisoned");
+        let mut stmt = conn.prepare("SELECT id, services FROM peers")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?;
+
+        let mut ids = Vec::new();
+        for row in rows {
+            let (id, services) = row?;
+            if ServiceFlags::from(services as u64).has(service) {
+                ids.push(id);
+            }
+        }
+
+        drop(stmt);
+        ids.into_iter()
+            .map(|id| Self::load_one(&conn, id))
+            .collect()
+    }
+}
+
+#[cfg(feature = "sqlite-peer-store")]
+impl SqlitePeerStore {
+    /// Reconstructs a [`LocalAddress`] for `id`.
+    ///
+    /// The address/services/port/last_connected come from the `address` column, a JSON-encoded
+    /// [`DiskLocalAddress`] written on every [`Self::upsert_row`]. The state is instead read
+    /// from the dedicated `state`/`state_time`/`attempts` columns, which `mark_tried`/
+    /// `mark_failed`/`mark_banned`/`mark_state` update in place without touching that blob -
+    /// they're the source of truth for state, not what was last upserted.
+    fn load_one(conn: &rusqlite::Connection, id: i64) -> Result<LocalAddress, PeerStoreError> {
+        conn.query_row(
+            "SELECT address, state, state_time, attempts FROM peers WHERE id = ?1",
+            rusqlite::params![id],
+            |row| {
+                let address: String = row.get(0)?;
+                let state: i64 = row.get(1)?;
+                let state_time: i64 = row.get(2)?;
+                let attempts: i64 = row.get(3)?;
+                Ok((address, state, state_time, attempts))
+            },
+        )
+        .map_err(PeerStoreError::from)
+        .and_then(|(address, state, state_time, attempts)| {
+            let disk: DiskLocalAddress = serde_json::from_str(&address)?;
+            let mut address: LocalAddress = disk.into();
+
+            let state = AddressState::from_num(state as u8, state_time as u64, attempts as u32);
+            address.set_state(state);
+
+            Ok(address)
+        })
+    }
+}
@@ -10,12 +10,17 @@ mod tests {
     use floresta_chain::pruned_utreexo::BlockchainInterface;
     use floresta_common::bhash;
 
+    use crate::node::swift_sync_ctx::Hints;
     use crate::node::swift_sync_ctx::SwiftSync;
     use crate::p2p_wire::node_context::NodeContext;
     use crate::p2p_wire::tests::utils::mainnet_headers;
     use crate::p2p_wire::tests::utils::make_block_invalid;
+    use crate::p2p_wire::tests::utils::make_hints_inconsistent;
+    use crate::p2p_wire::tests::utils::setup_audit;
+    use crate::p2p_wire::tests::utils::setup_hints_gen;
     use crate::p2p_wire::tests::utils::setup_running_node;
     use crate::p2p_wire::tests::utils::setup_swiftsync;
+    use crate::p2p_wire::tests::utils::MisbehaviorProfile;
     use crate::p2p_wire::tests::utils::PeerData;
     use crate::p2p_wire::tests::utils::SetupNodeArgs;
 
@@ -45,7 +50,14 @@ mod tests {
         let blocks = read_blocks_txt();
         assert_eq!(blocks.len(), NUM_BLOCKS);
 
-        let peer = vec![PeerData::new(Vec::new(), blocks, HashMap::new())];
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks,
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
         let args = SetupNodeArgs::new(peer, false, Network::Bitcoin, datadir, NUM_BLOCKS);
 
         let chain = setup_swiftsync(args).await;
@@ -88,7 +100,16 @@ mod tests {
         //
         // NOTE: we need `MAX_OUTGOING_PEERS` for `ChainSelector` to start and move to `SwiftSync`.
         let peers: Vec<_> = (0..SwiftSync::MAX_OUTGOING_PEERS)
-            .map(|_| PeerData::new(Vec::new(), blocks.clone(), HashMap::new()))
+            .map(|_| {
+                PeerData::new(
+                    Vec::new(),
+                    blocks.clone(),
+                    HashMap::new(),
+                    Vec::new(),
+                    MisbehaviorProfile::Cooperative,
+                    HashMap::new(),
+                )
+            })
             .collect();
 
         let args = SetupNodeArgs::new(peers, false, Network::Bitcoin, datadir, NUM_BLOCKS);
@@ -102,4 +123,146 @@ mod tests {
         assert_eq!(chain.get_best_block().unwrap().1, headers[150].block_hash());
         assert!(!chain.is_in_ibd());
     }
+
+    #[tokio::test]
+    async fn test_swift_sync_mismatched_block_hash() {
+        let datadir = format!("./tmp-db/{}.swift_sync_node", rand::random::<u32>());
+        std::fs::create_dir_all(&datadir).unwrap();
+        // We need the hints in the datadir
+        std::fs::copy(
+            "./src/p2p_wire/tests/test_data/bitcoin.hints",
+            format!("{datadir}/bitcoin.hints"),
+        )
+        .unwrap();
+
+        let headers = mainnet_headers();
+        let blocks = read_blocks_txt();
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+
+        // Same outcome as `test_swift_sync_invalid_block`, but the fault is injected by the
+        // simulated peer itself (a block that doesn't hash to what was asked for) rather than by
+        // tampering with the block data up front.
+        //
+        // NOTE: we need `MAX_OUTGOING_PEERS` for `ChainSelector` to start and move to `SwiftSync`.
+        let peers: Vec<_> = (0..SwiftSync::MAX_OUTGOING_PEERS)
+            .map(|_| {
+                PeerData::new(
+                    Vec::new(),
+                    blocks.clone(),
+                    HashMap::new(),
+                    Vec::new(),
+                    MisbehaviorProfile::MismatchedBlockHash,
+                    HashMap::new(),
+                )
+            })
+            .collect();
+
+        let args = SetupNodeArgs::new(peers, false, Network::Bitcoin, datadir, NUM_BLOCKS);
+
+        // Running node ensures we switch from `SwiftSync` to `SyncNode`, as we can't verify the
+        // SwiftSync hints since the chain is invalid.
+        let chain = setup_running_node(args).await;
+
+        assert!(chain.get_validation_index().unwrap() < NUM_BLOCKS as u32);
+        assert!(!chain.is_in_ibd());
+    }
+
+    #[tokio::test]
+    async fn test_hints_gen_round_trip() {
+        let gen_datadir = format!("./tmp-db/{}.hints_gen_node", rand::random::<u32>());
+        std::fs::create_dir_all(&gen_datadir).unwrap();
+
+        let headers = mainnet_headers();
+        let blocks = read_blocks_txt();
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks.clone(),
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
+        let gen_args = SetupNodeArgs::new(
+            peer,
+            false,
+            Network::Bitcoin,
+            gen_datadir.clone(),
+            NUM_BLOCKS,
+        );
+
+        setup_hints_gen(gen_args, NUM_BLOCKS as u32).await;
+
+        // Feed the generated file straight back into `Hints::from_file`.
+        let generated_path = format!("{gen_datadir}/bitcoin.hints");
+        let hints = Hints::from_file(std::fs::File::open(&generated_path).unwrap()).unwrap();
+        assert_eq!(hints.stop_height(), NUM_BLOCKS as u32);
+
+        // Now run a full SwiftSync session against the generated file and confirm the final
+        // aggregator nets to zero, exactly as it would against the shipped fixture.
+        let swiftsync_datadir =
+            format!("./tmp-db/{}.hints_gen_swiftsync_node", rand::random::<u32>());
+        std::fs::create_dir_all(&swiftsync_datadir).unwrap();
+        std::fs::copy(&generated_path, format!("{swiftsync_datadir}/bitcoin.hints")).unwrap();
+
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks,
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
+        let args = SetupNodeArgs::new(peer, false, Network::Bitcoin, swiftsync_datadir, NUM_BLOCKS);
+
+        let chain = setup_swiftsync(args).await;
+
+        assert_eq!(chain.get_validation_index().unwrap(), NUM_BLOCKS as u32);
+        assert_eq!(chain.get_best_block().unwrap().1, headers[NUM_BLOCKS].block_hash());
+        assert!(!chain.is_in_ibd());
+    }
+
+    #[tokio::test]
+    async fn test_hints_audit_catches_inconsistent_hints() {
+        let datadir = format!("./tmp-db/{}.hints_audit_node", rand::random::<u32>());
+        std::fs::create_dir_all(&datadir).unwrap();
+
+        // The tampered height's coinbase output is really left unspent, but the hints file we
+        // hand the audit claims otherwise, so the audit's from-scratch replay disagrees with it
+        // at exactly that height.
+        const TAMPERED_HEIGHT: u32 = 2;
+        make_hints_inconsistent(
+            "./src/p2p_wire/tests/test_data/bitcoin.hints",
+            &format!("{datadir}/bitcoin.hints"),
+            TAMPERED_HEIGHT,
+        );
+
+        let headers = mainnet_headers();
+        let blocks = read_blocks_txt();
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks,
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
+        let args = SetupNodeArgs::new(peer, false, Network::Bitcoin, datadir, NUM_BLOCKS);
+
+        let chain = setup_audit(args).await;
+
+        // The audit invalidates from the first height where its own replay disagrees with the
+        // (tampered) hints file, mirroring the height-150 rollback in `test_swift_sync_invalid_block`.
+        assert_eq!(
+            chain.get_validation_index().unwrap(),
+            TAMPERED_HEIGHT - 1,
+        );
+        assert_eq!(
+            chain.get_best_block().unwrap().1,
+            headers[(TAMPERED_HEIGHT - 1) as usize].block_hash(),
+        );
+    }
 }
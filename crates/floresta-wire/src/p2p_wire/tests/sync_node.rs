@@ -9,6 +9,7 @@ mod tests {
     use crate::p2p_wire::tests::utils::setup_sync_node;
     use crate::p2p_wire::tests::utils::signet_blocks;
     use crate::p2p_wire::tests::utils::signet_headers;
+    use crate::p2p_wire::tests::utils::MisbehaviorProfile;
     use crate::p2p_wire::tests::utils::PeerData;
     use crate::p2p_wire::tests::utils::SetupNodeArgs;
 
@@ -20,7 +21,14 @@ mod tests {
         let headers = signet_headers();
         let blocks = signet_blocks();
 
-        let peer = vec![PeerData::new(Vec::new(), blocks, HashMap::new())];
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks,
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
         let args = SetupNodeArgs::new(peer, false, Network::Signet, datadir, NUM_BLOCKS);
 
         let chain = setup_sync_node(args).await;
@@ -41,7 +49,14 @@ mod tests {
             make_block_invalid(block);
         }
 
-        let peer = vec![PeerData::new(Vec::new(), blocks, HashMap::new())];
+        let peer = vec![PeerData::new(
+            Vec::new(),
+            blocks,
+            HashMap::new(),
+            Vec::new(),
+            MisbehaviorProfile::Cooperative,
+            HashMap::new(),
+        )];
         let args = SetupNodeArgs::new(peer, false, Network::Signet, datadir, NUM_BLOCKS);
 
         let chain = setup_sync_node(args).await;
@@ -1,17 +1,32 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
+use bitcoin::bip158::BlockFilter;
 use bitcoin::block::Header;
 use bitcoin::consensus::encode;
 use bitcoin::consensus::encode::deserialize_hex;
 use bitcoin::consensus::Decodable;
 use bitcoin::hex::FromHex;
+use bitcoin::p2p::address::AddrV2;
+use bitcoin::p2p::address::AddrV2Message;
 use bitcoin::p2p::ServiceFlags;
 use bitcoin::Block;
 use bitcoin::BlockHash;
+use bitcoin::FilterHeader;
 use bitcoin::Network;
+use bitcoin::OutPoint;
+use bitcoin::ScriptBuf;
 use derive_more::Constructor;
 use floresta_chain::pruned_utreexo::UpdatableChainstate;
 use floresta_chain::AssumeValidArg;
@@ -37,6 +52,8 @@ use tokio::time::timeout;
 use zstd;
 
 use crate::address_man::AddressMan;
+use crate::node::audit_ctx::HintsAudit;
+use crate::node::hints_gen_ctx::HintsGen;
 use crate::node::running_ctx::RunningNode;
 use crate::node::swift_sync_ctx::Hints;
 use crate::node::swift_sync_ctx::SwiftSync;
@@ -50,6 +67,7 @@ use crate::node::PeerStatus;
 use crate::node::UtreexoNode;
 use crate::node_context::NodeContext;
 use crate::p2p_wire::block_proof::UtreexoProof;
+use crate::p2p_wire::cfilters;
 use crate::p2p_wire::peer::PeerMessages;
 use crate::p2p_wire::peer::Version;
 use crate::p2p_wire::transport::TransportProtocol;
@@ -61,14 +79,46 @@ pub struct UtreexoRoots {
     numleaves: usize,
 }
 
+/// A per-message-type fault a [`SimulatedPeer`] can inject, so tests can exercise the node's
+/// banscore accounting, inflight-request requeueing, and peer-switch logic beyond the single
+/// tampered-block case `make_block_invalid` covers.
+///
+/// Mirrors dnsseed-rust's scanner, which tracks per-message `recvd_*` flags and wraps the
+/// connection in a `TimeoutStream` to catch a peer that goes quiet instead of answering.
+#[derive(Debug, Clone, Default)]
+pub enum MisbehaviorProfile {
+    /// Answer every request honestly. The default, and the only behavior before this profile
+    /// existed.
+    #[default]
+    Cooperative,
+    /// Never answer `GetHeaders`, so the node's own request timeout has to fire.
+    StallHeaders,
+    /// Answer `GetHeaders` with headers in an order that doesn't chain to the node's tip.
+    NonConnectingHeaders,
+    /// Answer `GetBlock` with a block that doesn't hash to the requested `BlockHash`.
+    MismatchedBlockHash,
+    /// Answer `GetBlockProof` with a structurally-valid but made-up `UtreexoProof`.
+    WrongUtreexoProof,
+    /// Answer some, but not all, of a `GetBlock` batch, then disconnect.
+    DisconnectMidBlockBatch,
+}
+
 #[derive(Debug, Constructor)]
 pub struct SimulatedPeer {
     headers: Vec<Header>,
     blocks: HashMap<BlockHash, Block>,
     accs: HashMap<BlockHash, Vec<u8>>,
+    addrs: Vec<AddrV2Message>,
+    profile: MisbehaviorProfile,
     node_tx: UnboundedSender<NodeNotification>,
     node_rx: UnboundedReceiver<NodeRequest>,
     peer_id: u32,
+    // Encoded BIP-158 basic filters this peer would serve over `GetCFilters`/`GetCFHeaders`.
+    //
+    // NOTE: `NodeRequest`/`PeerMessages` don't carry compact-filter variants in this tree yet
+    // (see `crate::p2p_wire::cfilters`), so `run` doesn't dispatch on this field yet; it's wired
+    // up so the filter-serving matrix from `PeerData` flows through once those variants exist.
+    filters: HashMap<BlockHash, Vec<u8>>,
 }
 
 impl SimulatedPeer {
@@ -102,12 +152,23 @@ impl SimulatedPeer {
 
             match req {
                 NodeRequest::GetHeaders(hashes) => {
-                    let headers = hashes
+                    if matches!(self.profile, MisbehaviorProfile::StallHeaders) {
+                        // Never answer; the node's own request timeout has to fire instead.
+                        continue;
+                    }
+
+                    let mut headers: Vec<Header> = hashes
                         .iter()
                         .filter_map(|h| self.headers.iter().find(|x| x.block_hash() == *h))
                         .copied()
                         .collect();
 
+                    if matches!(self.profile, MisbehaviorProfile::NonConnectingHeaders) {
+                        // Reversing breaks `prev_blockhash` continuity without touching any
+                        // individual header, so the fault is purely in the batch's ordering.
+                        headers.reverse();
+                    }
+
                     let peer_msg = PeerMessages::Headers(headers);
                     self.node_tx
                         .send(NodeNotification::FromPeer(self.peer_id, peer_msg, now))
@@ -122,24 +183,54 @@ impl SimulatedPeer {
                         .unwrap();
                 }
                 NodeRequest::GetBlock(hashes) => {
-                    for hash in hashes {
-                        let block = self.blocks.get(&hash).unwrap().clone();
+                    let disconnect_early =
+                        matches!(self.profile, MisbehaviorProfile::DisconnectMidBlockBatch);
+
+                    for (i, hash) in hashes.iter().enumerate() {
+                        if disconnect_early && i + 1 == hashes.len() {
+                            break;
+                        }
+
+                        let mut block = self.blocks.get(hash).unwrap().clone();
+                        if matches!(self.profile, MisbehaviorProfile::MismatchedBlockHash) {
+                            // Swap in a different known block, so the returned block's hash
+                            // doesn't match the one the node asked for.
+                            block = self
+                                .blocks
+                                .values()
+                                .find(|b| b.block_hash() != *hash)
+                                .unwrap()
+                                .clone();
+                        }
 
                         let peer_msg = PeerMessages::Block(block);
                         self.node_tx
                             .send(NodeNotification::FromPeer(self.peer_id, peer_msg, now))
                             .unwrap();
                     }
+
+                    if disconnect_early {
+                        break;
+                    }
                 }
                 NodeRequest::Shutdown => {
                     break;
                 }
                 NodeRequest::GetBlockProof((block_hash, _, _)) => {
-                    let proof = UtreexoProof {
-                        block_hash,
-                        leaf_data: vec![],
-                        targets: vec![],
-                        proof_hashes: vec![],
+                    let proof = if matches!(self.profile, MisbehaviorProfile::WrongUtreexoProof) {
+                        UtreexoProof {
+                            block_hash,
+                            leaf_data: vec![Default::default()],
+                            targets: vec![0, 1, 2],
+                            proof_hashes: vec![Default::default()],
+                        }
+                    } else {
+                        UtreexoProof {
+                            block_hash,
+                            leaf_data: vec![],
+                            targets: vec![],
+                            proof_hashes: vec![],
+                        }
                     };
 
                     let peer_msg = PeerMessages::UtreexoProof(proof);
@@ -147,6 +238,12 @@ impl SimulatedPeer {
                         .send(NodeNotification::FromPeer(self.peer_id, peer_msg, now))
                         .unwrap();
                 }
+                NodeRequest::GetAddr => {
+                    let peer_msg = PeerMessages::Addr(self.addrs.clone());
+                    self.node_tx
+                        .send(NodeNotification::FromPeer(self.peer_id, peer_msg, now))
+                        .unwrap();
+                }
                 _ => {}
             }
         }
@@ -171,9 +268,22 @@ pub fn spawn_peer(
         headers,
         blocks,
         accs,
+        addrs,
+        profile,
+        filters,
     } = peer_data;
 
-    let mut peer = SimulatedPeer::new(headers, blocks, accs, node_sender, node_rcv, peer_id);
+    let mut peer = SimulatedPeer::new(
+        headers,
+        blocks,
+        accs,
+        addrs,
+        profile,
+        node_sender,
+        node_rcv,
+        peer_id,
+        filters,
+    );
     task::spawn(async move {
         peer.run().await;
     });
@@ -297,6 +407,79 @@ pub fn signet_roots() -> HashMap<BlockHash, Vec<u8>> {
     accs
 }
 
+/// Builds a BIP-158 basic filter and its chained filter header for each of the 121 signet
+/// fixture blocks, genesis through height 120 (see [`signet_blocks`]), keyed by block hash.
+///
+/// The chain starts from the all-zero header, same as `FlatFilterStore`'s genesis convention.
+pub fn signet_filters() -> HashMap<BlockHash, (BlockFilter, FilterHeader)> {
+    let headers = signet_headers();
+    let blocks = signet_blocks();
+
+    // Every scriptPubKey these blocks spend was created earlier in this same slice, so a lookup
+    // built from the blocks themselves is enough to construct every filter.
+    let mut prevout_scripts: HashMap<OutPoint, ScriptBuf> = HashMap::new();
+    for block in blocks.values() {
+        for tx in &block.txdata {
+            let txid = tx.compute_txid();
+            for (vout, out) in tx.output.iter().enumerate() {
+                prevout_scripts.insert(OutPoint::new(txid, vout as u32), out.script_pubkey.clone());
+            }
+        }
+    }
+
+    let mut result = HashMap::new();
+    let mut previous_header = FilterHeader::all_zeros();
+
+    for header in &headers {
+        let Some(block) = blocks.get(&header.block_hash()) else {
+            break;
+        };
+
+        let filter = cfilters::build_basic_filter(block, |outpoint| {
+            prevout_scripts.get(outpoint).cloned()
+        })
+        .expect("signet fixture blocks always resolve their own prevouts");
+
+        let filter_header = cfilters::next_filter_header(&filter, previous_header);
+        result.insert(header.block_hash(), (filter, filter_header));
+        previous_header = filter_header;
+    }
+
+    result
+}
+
+/// One `addrv2` entry for each network type `AddressMan::push_addresses_from` understands, so a
+/// `NodeRequest::GetAddr` handler can exercise the full address book lifecycle instead of just
+/// the IPv4 case: IPv4, IPv6, Tor v3 onion, I2P, and CJDNS.
+///
+/// Every entry carries `WITNESS | NETWORK_LIMITED`, the minimum services `push_addresses_from`
+/// requires to keep an address at all.
+pub fn sample_addrv2_addresses() -> Vec<AddrV2Message> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+
+    let services = ServiceFlags::NETWORK_LIMITED | ServiceFlags::WITNESS;
+    let addrs = [
+        AddrV2::Ipv4(Ipv4Addr::new(12, 34, 56, 78)),
+        AddrV2::Ipv6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        AddrV2::TorV3([0x42; 32]),
+        AddrV2::I2p([0x24; 32]),
+        AddrV2::Cjdns(Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 1)),
+    ];
+
+    addrs
+        .into_iter()
+        .map(|addr| AddrV2Message {
+            services,
+            addr,
+            port: 8333,
+            time: now,
+        })
+        .collect()
+}
+
 /// Modifies a block to have an invalid output script (txdata is tampered with)
 pub fn make_block_invalid(block: &mut Block) {
     let mut rng = rand::thread_rng();
@@ -309,12 +492,38 @@ pub fn make_block_invalid(block: &mut Block) {
     *byte += 1;
 }
 
+/// Copies a hints file to `dst`, flipping the spent/unspent bit for one output at `height`, so the
+/// copy disagrees with the real chain while still parsing as a well-formed hints file.
+///
+/// Used to seed a `HintsAudit` test with a hints file that's wrong at exactly one height, so the
+/// first height the audit should flag is known in advance.
+pub fn make_hints_inconsistent(src: &str, dst: &str, height: u32) {
+    std::fs::copy(src, dst).unwrap();
+
+    let file_pos = {
+        let hints = Hints::from_file(File::open(src).unwrap()).unwrap();
+        *hints.map.get(&height).expect("height not covered by hints file")
+    };
+
+    // Skip the 4-byte bit count and flip the lowest bit of the bitmap's first byte.
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(dst).unwrap();
+    file.seek(SeekFrom::Start(file_pos + 4)).unwrap();
+
+    let mut byte = [0u8; 1];
+    file.read_exact(&mut byte).unwrap();
+    file.seek(SeekFrom::Start(file_pos + 4)).unwrap();
+    file.write_all(&[byte[0] ^ 0x01]).unwrap();
+}
+
 #[derive(Constructor)]
 /// The chain data that our simulated peer will have
 pub struct PeerData {
     headers: Vec<Header>,
     blocks: HashMap<BlockHash, Block>,
     accs: HashMap<BlockHash, Vec<u8>>,
+    addrs: Vec<AddrV2Message>,
+    profile: MisbehaviorProfile,
+    filters: HashMap<BlockHash, Vec<u8>>,
 }
 
 #[derive(Constructor)]
@@ -391,6 +600,29 @@ pub async fn setup_swiftsync(args: SetupNodeArgs) -> Arc<ChainState<FlatChainSto
     chain
 }
 
+pub async fn setup_audit(args: SetupNodeArgs) -> Arc<ChainState<FlatChainStore>> {
+    let node = setup_node::<HintsAudit>(args);
+    let chain = node.chain.clone();
+
+    timeout(NODE_TIMEOUT, node.run(|_| {})).await.unwrap();
+
+    chain
+}
+
+pub async fn setup_hints_gen(
+    args: SetupNodeArgs,
+    stop_height: u32,
+) -> Arc<ChainState<FlatChainStore>> {
+    let node = setup_node::<HintsGen>(args);
+    let chain = node.chain.clone();
+
+    timeout(NODE_TIMEOUT, node.run(stop_height, |_| {}))
+        .await
+        .unwrap();
+
+    chain
+}
+
 pub async fn setup_running_node(args: SetupNodeArgs) -> Arc<ChainState<FlatChainStore>> {
     let node = setup_node::<RunningNode>(args);
     let kill_signal = node.kill_signal.clone();
@@ -419,31 +651,44 @@ mod tests {
     use bitcoin::consensus::deserialize;
     use bitcoin::hashes::Hash;
     use bitcoin::BlockHash;
+    use bitcoin::FilterHeader;
+    use bitcoin::ScriptBuf;
     use floresta_common::bhash;
 
     use super::make_block_invalid;
+    use super::sample_addrv2_addresses;
     use super::signet_blocks;
+    use super::signet_filters;
     use super::signet_headers;
     use super::signet_roots;
+    use crate::address_man::AddressMan;
+    use crate::address_man::LocalAddress;
+    use crate::address_man::ReachableNetworks;
+    use crate::node::swift_sync_ctx::HintFileError;
+    use crate::p2p_wire::cfilters;
     use crate::p2p_wire::tests::utils::Hints;
 
     fn load_test_hints() -> Hints {
         let file = File::open("./src/p2p_wire/tests/test_data/bitcoin.hints").unwrap();
-        Hints::from_file(file)
+        Hints::from_file(file).unwrap()
     }
 
     #[test]
-    #[should_panic]
     fn test_hints_file_genesis() {
         let mut hints = load_test_hints();
-        let _ = hints.get_indexes(0);
+        assert!(matches!(
+            hints.get_indexes(0),
+            Err(HintFileError::HeightNotFound(0))
+        ));
     }
 
     #[test]
-    #[should_panic]
     fn test_hints_file_after_stop_height() {
         let mut hints = load_test_hints();
-        let _ = hints.get_indexes(176);
+        assert!(matches!(
+            hints.get_indexes(176),
+            Err(HintFileError::HeightNotFound(176))
+        ));
     }
 
     #[test]
@@ -458,10 +703,28 @@ mod tests {
                 _ => vec![0],         // Other blocks have just a coinbase output (here unspent)
             };
 
-            assert_eq!(hints.get_indexes(height), unspent_indices);
+            assert_eq!(hints.get_indexes(height).unwrap(), unspent_indices);
         }
     }
 
+    #[test]
+    fn test_sample_addrv2_addresses_are_filtered_by_reachability() {
+        // Only IPv4/IPv6 are reachable here, mirroring a node with no Tor/I2P/CJDNS proxy
+        // configured: addresses for those networks should be silently dropped on ingest,
+        // not rejected with an error, just like dnsseed-rust's `add_fresh_nodes_v2`.
+        let mut address_man =
+            AddressMan::new(None, &[ReachableNetworks::IPv4, ReachableNetworks::IPv6]);
+
+        let addrs = sample_addrv2_addresses();
+        let src = addrs[0].addr.clone();
+        let local_addresses: Vec<LocalAddress> =
+            addrs.into_iter().map(LocalAddress::from).collect();
+
+        address_man.push_addresses_from(&local_addresses, &src);
+
+        assert_eq!(address_man.addresses.len(), 2, "only IPv4 and IPv6 dial");
+    }
+
     #[test]
     fn test_get_headers_and_blocks() {
         let headers = signet_headers();
@@ -523,4 +786,49 @@ mod tests {
             assert_eq!(i as u64, leaves, "one leaf added per block");
         }
     }
+
+    #[test]
+    fn test_signet_filters_chain_and_match_known_scripts() {
+        let headers = signet_headers();
+        let blocks = signet_blocks();
+        let filters = super::signet_filters();
+
+        assert_eq!(filters.len(), 121, "one filter per fixture block, including genesis");
+
+        let genesis_hash = headers[0].block_hash();
+        let height_1_hash = headers[1].block_hash();
+
+        // Chaining: height 1's header must be derived from the genesis filter chained onto the
+        // all-zero previous header, exactly the way `FlatFilterStore` starts its own chain.
+        let (genesis_filter, genesis_header) = filters.get(&genesis_hash).unwrap();
+        assert_eq!(
+            cfilters::next_filter_header(genesis_filter, FilterHeader::all_zeros()),
+            *genesis_header,
+        );
+
+        let (height_1_filter, height_1_header) = filters.get(&height_1_hash).unwrap();
+        assert_eq!(
+            cfilters::next_filter_header(height_1_filter, *genesis_header),
+            *height_1_header,
+        );
+        assert_ne!(genesis_header, height_1_header, "headers advance block to block");
+
+        // Membership: the coinbase output script at height 1 must match that block's own filter.
+        let coinbase_script = blocks
+            .get(&height_1_hash)
+            .unwrap()
+            .txdata[0]
+            .output[0]
+            .script_pubkey
+            .clone();
+        assert!(cfilters::matches_script(height_1_filter, &height_1_hash, &coinbase_script).unwrap());
+
+        // A script that was never in this block shouldn't match (no false positive expected for
+        // a filter this small, though the scheme only promises no false negatives in general).
+        let unrelated_script = ScriptBuf::from_bytes(vec![
+            0x76, 0xa9, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x88, 0xac,
+        ]);
+        assert!(!cfilters::matches_script(height_1_filter, &height_1_hash, &unrelated_script).unwrap());
+    }
 }